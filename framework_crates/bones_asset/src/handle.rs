@@ -142,6 +142,7 @@ unsafe impl<T: HasSchema> HasSchema for Handle<T> {
                     fields: vec![StructFieldInfo {
                         name: Some("id".into()),
                         schema: u128::schema(),
+                        attrs: Vec::new(),
                     }],
                 }),
                 clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
@@ -191,6 +192,7 @@ unsafe impl HasSchema for UntypedHandle {
                     fields: vec![StructFieldInfo {
                         name: Some("id".into()),
                         schema: u128::schema(),
+                        attrs: Vec::new(),
                     }],
                 }),
                 clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
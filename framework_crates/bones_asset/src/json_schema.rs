@@ -0,0 +1,133 @@
+//! Export metadata asset schemas as JSON Schema documents.
+
+use serde_json::{json, Value};
+
+use crate::prelude::*;
+
+/// A JSON Schema document for a metadata asset.
+#[derive(Clone, Debug)]
+pub struct MetadataJsonSchema {
+    /// The extension of the asset from its [`AssetKind::Metadata`] type data, such as `atlas` for
+    /// `.atlas.yaml` files.
+    pub extension: String,
+    /// The schema of the asset.
+    pub schema: &'static Schema,
+    /// The JSON Schema document.
+    pub document: Value,
+}
+
+/// Create a [`JsonSchemaGenerator`] that describes data the way it is loaded in metadata assets.
+///
+/// Asset handles are described as the paths that they are loaded from, and [`Maybe`]s as optional
+/// values. Other schemas with a [`SchemaMetaAssetLoader`] accept any value.
+pub fn metadata_json_schema_generator() -> JsonSchemaGenerator {
+    JsonSchemaGenerator::new().with_override(|generator, schema| {
+        if let Some(handle) = schema.type_data.get::<SchemaAssetHandle>() {
+            let description = match handle.inner_schema() {
+                Some(inner) => format!("The path to a `{}` asset.", inner.name),
+                None => "The path to an asset.".into(),
+            };
+            return Some(json!({ "type": "string", "description": description }));
+        }
+
+        schema.type_data.get::<SchemaMetaAssetLoader>()?;
+        if schema.full_name == Maybe::<()>::schema().full_name {
+            // The `Set` variant has the value of the `Maybe` as its only field.
+            let value_schema = schema
+                .kind
+                .as_enum()
+                .and_then(|e| e.variants.iter().find(|v| &*v.name == "Set"))
+                .and_then(|v| v.schema.kind.as_struct()?.fields.first())
+                .map(|field| field.schema);
+            if let Some(value_schema) = value_schema {
+                return Some(json!({
+                    "anyOf": [generator.schema_value(value_schema), { "type": "null" }],
+                }));
+            }
+        }
+        Some(json!({}))
+    })
+}
+
+/// Generate a JSON Schema document for every registered schema with [`AssetKind::Metadata`] type
+/// data, sorted by their extension.
+///
+/// Schemas are registered when they are first used, so [`HasSchema::register_schema()`] should be
+/// called for the asset types before exporting them. Editors may use the documents to validate
+/// files with the matching extension, like `*.atlas.yaml`.
+pub fn metadata_asset_json_schemas() -> Vec<MetadataJsonSchema> {
+    let mut generator = metadata_json_schema_generator();
+    let mut schemas = SCHEMA_REGISTRY
+        .schemas
+        .iter()
+        .filter_map(|schema| match schema.type_data.get::<AssetKind>()? {
+            AssetKind::Metadata { extension } => Some(MetadataJsonSchema {
+                extension: extension.clone(),
+                schema,
+                document: generator.document(schema),
+            }),
+            AssetKind::Custom { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    schemas.sort_by(|a, b| a.extension.cmp(&b.extension));
+    schemas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Image(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    #[type_data(metadata_asset("test_meta"))]
+    #[repr(C)]
+    struct TestMeta {
+        image: Handle<Image>,
+        icon: Maybe<Handle<Image>>,
+        count: Maybe<u32>,
+        any: UntypedHandle,
+    }
+
+    #[test]
+    fn metadata_fields() {
+        let document = metadata_json_schema_generator().document(TestMeta::schema());
+        let properties =
+            &document["definitions"][json_schema_definition_name(TestMeta::schema())]["properties"];
+
+        let image = json!({ "type": "string", "description": "The path to a `Image` asset." });
+        assert_eq!(properties["image"], image);
+        assert_eq!(
+            properties["icon"],
+            json!({ "anyOf": [image, { "type": "null" }] })
+        );
+        assert_eq!(
+            properties["count"],
+            json!({
+                "anyOf": [
+                    { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                    { "type": "null" },
+                ],
+            })
+        );
+        assert_eq!(
+            properties["any"],
+            json!({ "type": "string", "description": "The path to an asset." })
+        );
+    }
+
+    #[test]
+    fn metadata_assets() {
+        TestMeta::register_schema();
+        let schemas = metadata_asset_json_schemas();
+        assert!(schemas.windows(2).all(|x| x[0].extension <= x[1].extension));
+
+        let meta = schemas.iter().find(|x| x.extension == "test_meta").unwrap();
+        assert_eq!(meta.schema, TestMeta::schema());
+        assert_eq!(meta.document["title"], "TestMeta");
+        // Schemas that aren't metadata assets are not exported.
+        assert!(schemas.iter().all(|x| x.schema != Image::schema()));
+    }
+}
@@ -10,10 +10,13 @@ use serde::{de::DeserializeSeed, Deserializer};
 /// Helper to export the same types in the crate root and in the prelude.
 macro_rules! pub_use {
     () => {
-        pub use crate::{asset::*, cid::*, handle::*, io::*, network_handle::*, server::*};
+        pub use crate::{
+            asset::*, cid::*, handle::*, io::*, json_schema::*, network_handle::*, server::*,
+        };
         pub use anyhow;
         pub use bones_schema::prelude::*;
         pub use dashmap;
+        pub use erased_serde;
         pub use futures_lite::future::Boxed as BoxedFuture;
         pub use path_absolutize::Absolutize;
         pub use semver::Version;
@@ -31,6 +34,7 @@ mod asset;
 mod cid;
 mod handle;
 mod io;
+mod json_schema;
 mod network_handle;
 mod parse;
 mod server;
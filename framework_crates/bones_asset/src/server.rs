@@ -1161,6 +1161,24 @@ mod metadata {
                             SchemaRefMut::from_ptr_schema(self.ptr.as_ptr(), s.fields[0].schema)
                         };
                         SchemaPtrLoadCtx { ptr, ctx: self.ctx }.deserialize(deserializer)?
+                    } else if let Some(version) = self
+                        .ptr
+                        .schema()
+                        .type_data
+                        .get::<SchemaVersion>()
+                        // The version is only serialized for structs with named fields.
+                        .filter(|_| s.fields.first().is_some_and(|f| f.name.is_some()))
+                    {
+                        // Migrate data that was serialized with an older version of the schema.
+                        let value = version.migrate(self.ptr.schema(), deserializer)?;
+                        serde::Deserializer::deserialize_any(
+                            value,
+                            StructVisitor {
+                                ptr: self.ptr,
+                                ctx: self.ctx,
+                            },
+                        )
+                        .map_err(D::Error::custom)?
                     } else {
                         deserializer.deserialize_any(StructVisitor {
                             ptr: self.ptr,
@@ -1185,6 +1203,10 @@ mod metadata {
                     ptr: self.ptr.into_box().unwrap(),
                 }
                 .deserialize(deserializer)?,
+                SchemaKind::Option(_) => deserializer.deserialize_option(OptionVisitor {
+                    ptr: self.ptr,
+                    ctx: self.ctx,
+                })?,
                 SchemaKind::Primitive(p) => {
                     match p {
                         Primitive::Bool => *self.ptr.cast_mut() = bool::deserialize(deserializer)?,
@@ -1241,9 +1263,15 @@ mod metadata {
         where
             A: serde::de::SeqAccess<'de>,
         {
-            let field_count = self.ptr.schema().kind.as_struct().unwrap().fields.len();
+            let info = self.ptr.schema().kind.as_struct().unwrap();
+            let mut deserialized = vec![false; info.fields.len()];
 
-            for i in 0..field_count {
+            for (i, _) in info
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| !x.skip_deserialize())
+            {
                 let field = self.ptr.access_mut().field(i).unwrap();
                 if seq
                     .next_element_seed(SchemaPtrLoadCtx {
@@ -1254,47 +1282,34 @@ mod metadata {
                 {
                     break;
                 }
+                deserialized[i] = true;
             }
 
-            Ok(())
+            info.apply_defaults(self.ptr, &deserialized).map_err(A::Error::custom)
         }
 
         fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
         where
             A: serde::de::MapAccess<'de>,
         {
+            let info = self.ptr.schema().kind.as_struct().unwrap();
+            let mut deserialized = vec![false; info.fields.len()];
+
             while let Some(key) = map.next_key::<String>()? {
-                match self.ptr.access_mut().field(&key) {
-                    Ok(field) => {
+                match info.deserialized_field_idx(&key) {
+                    Some(idx) => {
+                        let field = self.ptr.access_mut().field(idx).unwrap();
                         map.next_value_seed(SchemaPtrLoadCtx {
                             ctx: self.ctx,
                             ptr: field.into_schema_ref_mut(),
                         })?;
+                        deserialized[idx] = true;
                     }
-                    Err(_) => {
-                        let fields = &self.ptr.schema().kind.as_struct().unwrap().fields;
-                        let mut msg = format!("unknown field `{key}`, ");
-                        if !fields.is_empty() {
-                            msg += "expected one of ";
-                            for (i, field) in fields.iter().enumerate() {
-                                msg += &field
-                                    .name
-                                    .as_ref()
-                                    .map(|x| format!("`{x}`"))
-                                    .unwrap_or_else(|| format!("`{i}`"));
-                                if i < fields.len() - 1 {
-                                    msg += ", "
-                                }
-                            }
-                        } else {
-                            msg += "there are no fields"
-                        }
-                        return Err(A::Error::custom(msg));
-                    }
+                    None => return Err(A::Error::custom(info.unknown_field_message(&key))),
                 }
             }
 
-            Ok(())
+            info.apply_defaults(self.ptr, &deserialized).map_err(A::Error::custom)
         }
     }
 
@@ -1340,6 +1355,55 @@ mod metadata {
         }
     }
 
+    struct OptionVisitor<'a, 'srv, 'ptr> {
+        ctx: &'a mut MetaAssetLoadCtx<'srv>,
+        ptr: SchemaRefMut<'ptr>,
+    }
+
+    impl<'a, 'srv, 'ptr, 'de> Visitor<'de> for OptionVisitor<'a, 'srv, 'ptr> {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            // TODO: Write very verbose error messages for metadata asset deserializers.
+            write!(
+                formatter,
+                "asset metadata matching the schema: {}",
+                self.ptr.schema().full_name
+            )
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            OptionRefMutAccess(self.ptr).set_none();
+            Ok(())
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            self.visit_none()
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value_schema = self.ptr.schema().kind.as_option().unwrap().value;
+            let mut value = SchemaBox::default(value_schema);
+            SchemaPtrLoadCtx {
+                ctx: self.ctx,
+                ptr: value.as_mut(),
+            }
+            .deserialize(deserializer)?;
+            // The value was created with the schema of the option's value, so it matches.
+            OptionRefMutAccess(self.ptr).set_some(value).unwrap();
+            Ok(())
+        }
+    }
+
     struct MapVisitor<'a, 'srv, 'ptr> {
         ctx: &'a mut MetaAssetLoadCtx<'srv>,
         ptr: SchemaRefMut<'ptr>,
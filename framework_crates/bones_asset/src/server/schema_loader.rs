@@ -96,6 +96,7 @@ impl<'de> Deserialize<'de> for PackSchema {
                     .map(|field| StructFieldInfo {
                         name: field.name.as_deref().map(ustr),
                         schema: field.schema.0,
+                        attrs: Vec::new(),
                     })
                     .collect(),
             }),
@@ -127,6 +128,7 @@ impl<'de> Deserialize<'de> for PackSchema {
                 SchemaKind::Enum(_) => todo!(),
                 SchemaKind::Map { .. } => todo!(),
                 SchemaKind::Box(_) => todo!(),
+                SchemaKind::Option(_) => unreachable!("schemas in asset packs are always structs"),
                 SchemaKind::Primitive(_) => todo!(),
             }
         };
@@ -150,6 +152,7 @@ impl<'de> Deserialize<'de> for PackSchema {
                 SchemaKind::Enum(_) => todo!(),
                 SchemaKind::Map { .. } => todo!(),
                 SchemaKind::Box(_) => todo!(),
+                SchemaKind::Option(_) => unreachable!("schemas in asset packs are always structs"),
                 SchemaKind::Primitive(_) => todo!(),
             }
         };
@@ -170,6 +173,7 @@ impl<'de> Deserialize<'de> for PackSchema {
                 SchemaKind::Enum(_) => todo!(),
                 SchemaKind::Map { .. } => todo!(),
                 SchemaKind::Box(_) => todo!(),
+                SchemaKind::Option(_) => unreachable!("schemas in asset packs are always structs"),
                 SchemaKind::Primitive(_) => todo!(),
             }
         };
@@ -154,7 +154,7 @@ pub fn sync_cameras(
         camera_ent.insert((
             Camera {
                 is_active: bones_camera.active,
-                viewport: bones_camera.viewport.option().map(|x| x.into_bevy()),
+                viewport: bones_camera.viewport.map(|x| x.into_bevy()),
                 order: bones_camera.priority as isize,
                 ..default()
             },
@@ -282,12 +282,18 @@ pub fn extract_bones_sprites(
 
         let entities = world.resource::<bones::Entities>();
         let transforms = world.components.get::<bones::Transform>().borrow();
+        let global_transforms = world.components.get::<bones::GlobalTransform>().borrow();
         let sprites = world.components.get::<bones::Sprite>().borrow();
         let atlas_sprites = world.components.get::<bones::AtlasSprite>().borrow();
 
         // Extract normal sprites
         let mut z_offset = 0.0;
-        for (_, (sprite, transform)) in entities.iter_with((&sprites, &transforms)) {
+        for (entity, (sprite, transform)) in entities.iter_with((&sprites, &transforms)) {
+            // Prefer the propagated transform, for sprites that are children of other entities.
+            let transform = global_transforms
+                .get(entity)
+                .map(|x| x.0)
+                .unwrap_or(*transform);
             let sprite_image = match bones_assets.try_get(sprite.image) {
                 Some(Ok(image)) => image,
                 Some(Err(err)) => {
@@ -328,7 +334,12 @@ pub fn extract_bones_sprites(
         }
 
         // Extract atlas sprites
-        for (_, (atlas_sprite, transform)) in entities.iter_with((&atlas_sprites, &transforms)) {
+        for (entity, (atlas_sprite, transform)) in entities.iter_with((&atlas_sprites, &transforms))
+        {
+            let transform = global_transforms
+                .get(entity)
+                .map(|x| x.0)
+                .unwrap_or(*transform);
             let atlas = bones_assets.get(atlas_sprite.atlas);
             let atlas_image = bones_assets.get(atlas.image);
             let image_id = if let bones::Image::External(id) = &*atlas_image {
@@ -0,0 +1,152 @@
+//! Benchmarks for saving and loading [`World`] snapshots, like the GGRS session runner does every
+//! frame.
+//!
+//! Because component stores and resources are copy-on-write, the cost of a snapshot should scale
+//! with the number of components and resources that were mutated since the last snapshot, not with
+//! the size of the world.
+
+use bones_ecs::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[derive(HasSchema, Clone, Default)]
+#[repr(C)]
+struct Pos {
+    x: f32,
+    y: f32,
+}
+
+#[derive(HasSchema, Clone, Default)]
+#[repr(C)]
+struct Vel {
+    x: f32,
+    y: f32,
+}
+
+/// A large resource, like a tile map.
+#[derive(HasSchema, Clone, Default)]
+struct Map {
+    tiles: Vec<u32>,
+}
+
+/// A small resource that is changed every frame.
+#[derive(HasSchema, Clone, Default)]
+struct FrameCount(u32);
+
+const ENTITY_COUNTS: [usize; 3] = [1_000, 10_000, 100_000];
+const MUTATED_PERCENTS: [usize; 4] = [0, 1, 10, 100];
+const MAP_SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn world(entity_count: usize) -> World {
+    let world = World::new();
+    world.run_system(
+        move |mut entities: ResMut<Entities>, mut pos: CompMut<Pos>, mut vel: CompMut<Vel>| {
+            for _ in 0..entity_count {
+                let entity = entities.create();
+                pos.insert(entity, Pos::default());
+                vel.insert(entity, Vel { x: 1.0, y: 1.0 });
+            }
+        },
+        (),
+    );
+    world
+}
+
+/// Move the first `count` entities, like a game frame that only changes part of the world.
+fn mutate(world: &World, count: usize) {
+    world.run_system(
+        move |entities: Res<Entities>, mut pos: CompMut<Pos>, vel: Comp<Vel>| {
+            for (_, (pos, vel)) in entities.iter_with((&mut pos, &vel)).take(count) {
+                pos.x += vel.x;
+                pos.y += vel.y;
+            }
+        },
+        (),
+    );
+}
+
+fn resource_world(map_size: usize) -> World {
+    let world = World::new();
+    world.insert_resource(Map {
+        tiles: vec![0; map_size],
+    });
+    world.insert_resource(FrameCount(0));
+    world
+}
+
+/// Advance the frame count, and change a tile of the map if `mutate_map` is set.
+fn mutate_resources(world: &World, mutate_map: bool) {
+    world.resource_mut::<FrameCount>().0 += 1;
+    if mutate_map {
+        world.resource_mut::<Map>().tiles[0] += 1;
+    }
+}
+
+fn save(c: &mut Criterion) {
+    let mut group = c.benchmark_group("save_snapshot");
+    for entity_count in ENTITY_COUNTS {
+        for mutated_percent in MUTATED_PERCENTS {
+            let world = world(entity_count);
+            let mutated = entity_count * mutated_percent / 100;
+            let mut snapshot = world.clone();
+            group.bench_with_input(
+                BenchmarkId::new(format!("{entity_count}_entities"), mutated_percent),
+                &mutated,
+                |b, &mutated| {
+                    b.iter(|| {
+                        mutate(&world, mutated);
+                        snapshot = world.clone();
+                    })
+                },
+            );
+            drop(snapshot);
+        }
+    }
+    group.finish();
+}
+
+fn load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("load_snapshot");
+    for entity_count in ENTITY_COUNTS {
+        for mutated_percent in MUTATED_PERCENTS {
+            let mut world = world(entity_count);
+            let snapshot = world.clone();
+            let mutated = entity_count * mutated_percent / 100;
+            group.bench_with_input(
+                BenchmarkId::new(format!("{entity_count}_entities"), mutated_percent),
+                &mutated,
+                |b, &mutated| {
+                    b.iter(|| {
+                        mutate(&world, mutated);
+                        world.load_snapshot(snapshot.clone());
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn save_resources(c: &mut Criterion) {
+    let mut group = c.benchmark_group("save_snapshot_resources");
+    for map_size in MAP_SIZES {
+        for mutate_map in [false, true] {
+            let world = resource_world(map_size);
+            let mut snapshot = world.clone();
+            group.bench_with_input(
+                BenchmarkId::new(format!("{map_size}_tiles"), mutate_map),
+                &mutate_map,
+                |b, &mutate_map| {
+                    b.iter(|| {
+                        mutate_resources(&world, mutate_map);
+                        snapshot = world.clone();
+                    })
+                },
+            );
+            drop(snapshot);
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, save, load, save_resources);
+criterion_main!(benches);
@@ -50,6 +50,21 @@ fn _generate_system_param_impl(input: TokenStream) -> syn::Result<TokenStream> {
             quote! { <#ty as ::bones_ecs::prelude::SystemParam>::get_state(world) }
         }));
 
+    let get_state_for_system_items: Punctuated<TokenStream, Token![,]> =
+        Punctuated::from_iter(fields.named.iter().map(|field| {
+            let ty = &field.ty;
+            quote! { <#ty as ::bones_ecs::prelude::SystemParam>::get_state_for_system(world, meta) }
+        }));
+
+    let access_items: TokenStream = fields
+        .named
+        .iter()
+        .map(|field| {
+            let ty = &field.ty;
+            quote! { <#ty as ::bones_ecs::prelude::SystemParam>::access(access); }
+        })
+        .collect();
+
     let borrow_param_fields: Punctuated<TokenStream, Token![,]> = fields
         .named
         .iter()
@@ -74,6 +89,15 @@ fn _generate_system_param_impl(input: TokenStream) -> syn::Result<TokenStream> {
             fn get_state(world: &::bones_ecs::prelude::World) -> Self::State {
                 ( #get_state_items )
             }
+            fn get_state_for_system(
+                world: &::bones_ecs::prelude::World,
+                meta: &::bones_ecs::prelude::SystemMeta,
+            ) -> Self::State {
+                ( #get_state_for_system_items )
+            }
+            fn access(access: &mut ::bones_ecs::prelude::SystemAccess) {
+                #access_items
+            }
             fn borrow<'s>(
                 world: &'s ::bones_ecs::prelude::World,
                 state: &'s mut Self::State,
@@ -119,6 +143,19 @@ mod tests {
                         <ResMut<'a, Entities> as ::bones_ecs::prelude::SystemParam>::get_state(world)
                     )
                 }
+                fn get_state_for_system(
+                    world: &::bones_ecs::prelude::World,
+                    meta: &::bones_ecs::prelude::SystemMeta,
+                ) -> Self::State {
+                    (
+                        <Commands<'a> as ::bones_ecs::prelude::SystemParam>::get_state_for_system(world, meta),
+                        <ResMut<'a, Entities> as ::bones_ecs::prelude::SystemParam>::get_state_for_system(world, meta)
+                    )
+                }
+                fn access(access: &mut ::bones_ecs::prelude::SystemAccess) {
+                    <Commands<'a> as ::bones_ecs::prelude::SystemParam>::access(access);
+                    <ResMut<'a, Entities> as ::bones_ecs::prelude::SystemParam>::access(access);
+                }
                 fn borrow<'s>(
                     world: &'s ::bones_ecs::prelude::World,
                     state: &'s mut Self::State,
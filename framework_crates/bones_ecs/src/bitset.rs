@@ -2,134 +2,49 @@
 //!
 //! Bitsets are powered by the [`bitset_core`] crate.
 //!
-//! A Bones bitset is a vector of 32-byte sectors. The size of a bitset can be controlled by the
-//! `keysize*` features (note that they are all mutually exclusive with each other). The `keysize`
-//! is exponentially correlated to the number of bits, or entities, that the set can track; where
-//! the number of bits equals two raised to the power of `keysize`. Below are the available
-//! `keysize`s and the size of their resulting bitsets.
+//! A Bones bitset is a vector of 32-byte sectors. Sectors are 256 bits as they are comprised of 8
+//! `u32`s, which means that SIMD instructions can process 256 bits/entities at a time.
 //!
-//! | Keysize | Bit Count  | Sectors    | Memory |
-//! | ------- | ---------- | ---------- | ------ |
-//! |      32 |  4 billion | 16 million | 512 MB |
-//! |      24 | 16 million |      65536 |   2 MB |
-//! |      20 |  1 million |       4096 | 128 KB |
-//! |      16 |      65536 |        256 |   8 KB |
-//! |      12 |       4096 |         16 | 512  B |
-//! |      10 |       1024 |          4 | 128  B |
-//!
-//! Keysize (`K`) refers to the name of the feature. A keysize of 16 refers to the `keysize16`
-//! feature.
-//!
-//! Bit Count is the total number of bits in the bitset (`2^K`). Due to the nature of binary
-//! numbers this means that a value with more than `K` bits should not be used to index into the
-//! bitset since e.g. `u16::MAX` is the index of the last bit in a `keysize16` bitset.
-//!
-//! Sectors is the number of sub-arrays within the bitset `Vec`. Sectors are 256 bits as they are
-//! comprised of 8 `u32`s. Note that SIMD instructions process 256 bits/entities at a time.
-//!
-//! Memory is the total amount of memory that the bitset will occupy.
+//! Bitsets start out empty and grow on demand: setting a bit past the end of the bitset allocates
+//! enough sectors to hold it, and testing a bit past the end of the bitset simply returns `false`.
+//! This keeps the memory used by a bitset, and the cost of cloning it when snapshotting a
+//! [`World`], proportional to the highest entity index it tracks instead of to the maximum number
+//! of entities that could ever exist.
 //!
 //! [`bitset_core`]: https://docs.rs/bitset_core
 
 use crate::prelude::*;
 
-#[cfg(all(
-    feature = "keysize10",
-    not(feature = "keysize12"),
-    not(feature = "keysize16"),
-    not(feature = "keysize20"),
-    not(feature = "keysize24"),
-    not(feature = "keysize32")
-))]
-#[allow(missing_docs)]
-pub const BITSET_EXP: u32 = 10;
-
-#[cfg(all(
-    feature = "keysize12",
-    not(feature = "keysize10"),
-    not(feature = "keysize16"),
-    not(feature = "keysize20"),
-    not(feature = "keysize24"),
-    not(feature = "keysize32")
-))]
-#[allow(missing_docs)]
-pub const BITSET_EXP: u32 = 12;
-
-// 16 is the default, if no `keysize*` features are enabled then use this one.
-#[cfg(any(
-    feature = "keysize16",
-    all(
-        not(feature = "keysize10"),
-        not(feature = "keysize12"),
-        not(feature = "keysize20"),
-        not(feature = "keysize24"),
-        not(feature = "keysize32")
-    )
-))]
-#[allow(missing_docs)]
-pub const BITSET_EXP: u32 = 16;
-
-#[cfg(all(
-    feature = "keysize20",
-    not(feature = "keysize10"),
-    not(feature = "keysize12"),
-    not(feature = "keysize16"),
-    not(feature = "keysize24"),
-    not(feature = "keysize32")
-))]
-#[allow(missing_docs)]
-pub const BITSET_EXP: u32 = 20;
-
-#[cfg(all(
-    feature = "keysize24",
-    not(feature = "keysize10"),
-    not(feature = "keysize12"),
-    not(feature = "keysize16"),
-    not(feature = "keysize20"),
-    not(feature = "keysize32")
-))]
-#[allow(missing_docs)]
-pub const BITSET_EXP: u32 = 24;
-
-#[cfg(all(
-    feature = "keysize32",
-    not(feature = "keysize10"),
-    not(feature = "keysize12"),
-    not(feature = "keysize16"),
-    not(feature = "keysize20"),
-    not(feature = "keysize24")
-))]
-#[allow(missing_docs)]
-pub const BITSET_EXP: u32 = 32;
-
 pub use bitset_core::*;
 
-/// The number of bits in a bitset.
-pub(crate) const BITSET_SIZE: usize = 2usize.saturating_pow(BITSET_EXP);
-
 /// The number of bits in a bitset "sector" (one of the sub-arrays).
 ///
 /// A sector is an array of 8 `u32` values, so there are `8 * 32` bits in a sector.
 ///
 /// This value is constant, as a sector is appropriately sized to fit into a vector register for
 /// SIMD instructions.
-const BITSET_SECTOR_SIZE: usize = 32 * 8;
-
-const BITSET_SECTOR_COUNT: usize = BITSET_SIZE / BITSET_SECTOR_SIZE;
+pub(crate) const BITSET_SECTOR_SIZE: usize = 32 * 8;
 
 /// The type of bitsets used to track entities in component storages.
 /// Mostly used to create caches.
+///
+/// The bitset grows automatically when bits past its end are set. Bits that are past the end of
+/// the bitset are considered to be unset.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Deref, DerefMut, Clone, Debug)]
+#[derive(Deref, DerefMut, Clone, Debug, Default)]
 pub struct BitSetVec(pub Vec<[u32; 8]>);
 
-impl Default for BitSetVec {
-    fn default() -> Self {
-        create_bitset()
+impl BitSetVec {
+    /// Create an empty bitset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty bitset that can hold at least `bits` bits before it needs to reallocate.
+    pub fn with_capacity(bits: usize) -> Self {
+        Self(Vec::with_capacity(bits.div_ceil(BITSET_SECTOR_SIZE)))
     }
-}
 
-impl BitSetVec {
     /// Check whether or not the bitset contains the given entity.
     #[inline]
     pub fn contains(&self, entity: Entity) -> bool {
@@ -141,10 +56,141 @@ impl BitSetVec {
     pub fn set(&mut self, entity: Entity) {
         self.bit_set(entity.index() as usize);
     }
+
+    /// Make sure the bitset has allocated sectors for at least `bits` bits.
+    #[inline]
+    pub fn grow(&mut self, bits: usize) {
+        let sectors = bits.div_ceil(BITSET_SECTOR_SIZE);
+        if sectors > self.0.len() {
+            self.0.resize(sectors, [0u32; 8]);
+        }
+    }
+
+    /// Returns whether or not the given bit is set.
+    ///
+    /// Bits past the end of the bitset are always unset.
+    #[inline]
+    pub fn bit_test(&self, bit: usize) -> bool {
+        let (sector, word, mask) = locate(bit);
+        match self.0.get(sector) {
+            Some(sector) => sector[word] & mask != 0,
+            None => false,
+        }
+    }
+
+    /// Set the given bit, growing the bitset if necessary.
+    #[inline]
+    pub fn bit_set(&mut self, bit: usize) -> &mut Self {
+        let (sector, word, mask) = locate(bit);
+        self.grow(bit + 1);
+        self.0[sector][word] |= mask;
+        self
+    }
+
+    /// Unset the given bit.
+    #[inline]
+    pub fn bit_reset(&mut self, bit: usize) -> &mut Self {
+        let (sector, word, mask) = locate(bit);
+        if let Some(sector) = self.0.get_mut(sector) {
+            sector[word] &= !mask;
+        }
+        self
+    }
+
+    /// Intersect this bitset with `rhs`.
+    ///
+    /// Since bits past the end of `rhs` are unset, this bitset is truncated to the length of `rhs`
+    /// if it is longer.
+    #[inline]
+    pub fn bit_and(&mut self, rhs: &[[u32; 8]]) -> &mut Self {
+        self.0.truncate(rhs.len());
+        for (sector, rhs) in self.0.iter_mut().zip(rhs) {
+            for (word, rhs) in sector.iter_mut().zip(rhs) {
+                *word &= rhs;
+            }
+        }
+        self
+    }
+
+    /// Union this bitset with `rhs`, growing this bitset if `rhs` is longer.
+    #[inline]
+    pub fn bit_or(&mut self, rhs: &[[u32; 8]]) -> &mut Self {
+        self.grow(rhs.len() * BITSET_SECTOR_SIZE);
+        for (sector, rhs) in self.0.iter_mut().zip(rhs) {
+            for (word, rhs) in sector.iter_mut().zip(rhs) {
+                *word |= rhs;
+            }
+        }
+        self
+    }
+
+    /// Unset every bit in this bitset that is set in `rhs`.
+    #[inline]
+    pub fn bit_andnot(&mut self, rhs: &[[u32; 8]]) -> &mut Self {
+        for (sector, rhs) in self.0.iter_mut().zip(rhs) {
+            for (word, rhs) in sector.iter_mut().zip(rhs) {
+                *word &= !rhs;
+            }
+        }
+        self
+    }
 }
 
-/// Creates a bitset big enough to contain the index of each entity.
-/// Mostly used to create caches.
-pub fn create_bitset() -> BitSetVec {
-    BitSetVec(vec![[0u32; 8]; BITSET_SECTOR_COUNT])
+/// Get the sector index, word index, and bit mask of the given bit.
+#[inline(always)]
+fn locate(bit: usize) -> (usize, usize, u32) {
+    (
+        bit / BITSET_SECTOR_SIZE,
+        (bit % BITSET_SECTOR_SIZE) / 32,
+        1 << (bit % 32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn bitset(enabled: &[usize]) -> BitSetVec {
+        let mut bitset = BitSetVec::default();
+        for &i in enabled {
+            bitset.bit_set(i);
+        }
+        bitset
+    }
+
+    #[test]
+    fn grows_on_demand() {
+        let mut bitset = BitSetVec::default();
+        assert_eq!(bitset.len(), 0);
+        assert!(!bitset.bit_test(1000));
+
+        bitset.bit_set(1000);
+        assert_eq!(bitset.len(), 4);
+        assert!(bitset.bit_test(1000));
+        assert!(!bitset.bit_test(999));
+        assert!(!bitset.bit_test(100_000));
+
+        bitset.bit_reset(1000);
+        bitset.bit_reset(100_000);
+        assert!(!bitset.bit_test(1000));
+        assert_eq!(bitset.len(), 4);
+    }
+
+    #[test]
+    fn ops_with_mismatched_lengths() {
+        let mut and = bitset(&[1, 300, 5000]);
+        and.bit_and(&bitset(&[1, 5000]));
+        assert!(and.bit_test(1) && !and.bit_test(300) && and.bit_test(5000));
+        and.bit_and(&bitset(&[1, 300]));
+        assert_eq!(and.bit_count(), 1);
+        assert!(and.bit_test(1) && !and.bit_test(5000));
+
+        let mut or = bitset(&[1]);
+        or.bit_or(&bitset(&[300]));
+        assert!(or.bit_test(1) && or.bit_test(300));
+
+        let mut andnot = bitset(&[1, 2, 5000]);
+        andnot.bit_andnot(&bitset(&[2]));
+        assert!(andnot.bit_test(1) && !andnot.bit_test(2) && andnot.bit_test(5000));
+    }
 }
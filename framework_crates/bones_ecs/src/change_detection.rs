@@ -0,0 +1,616 @@
+//! Change detection for components and resources.
+//!
+//! Every time a system runs, the [`World`]'s change tick is advanced, and the system is given the
+//! tick that it is running at, along with the tick that it last ran at, in the form of
+//! [`SystemTicks`].
+//!
+//! Component stores and resources record the tick at which their data was added and at which it
+//! was last mutably accessed. Comparing those with a system's [`SystemTicks`] lets the system find
+//! out what has been added or changed since the last time it ran, for example with the [`Added`]
+//! and [`Changed`] system parameters:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! # #[derive(HasSchema, Clone, Default)]
+//! # #[repr(C)]
+//! # struct Pos(f32, f32);
+//! fn print_moved(entities: Res<Entities>, pos: Changed<Pos>) {
+//!     for (entity, pos) in entities.iter_with(&pos) {
+//!         println!("{entity:?} moved to ({}, {})", pos.0, pos.1);
+//!     }
+//! }
+//! ```
+//!
+//! All of the ticks are stored inside of the [`World`], so they are captured in world snapshots and
+//! stay consistent when a snapshot is restored.
+
+use std::{
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::prelude::*;
+
+/// A point in time of the [`World`], used for change detection.
+///
+/// The world tick is advanced every time a system is run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tick(pub u64);
+
+impl Tick {
+    /// The tick used for systems that have never run before. Every change is newer than this tick.
+    pub const NEVER: Tick = Tick(0);
+
+    /// Returns whether or not this tick is newer than `last_run`.
+    ///
+    /// Every tick is newer than [`Tick::NEVER`].
+    #[inline]
+    pub fn is_newer_than(self, last_run: Tick) -> bool {
+        last_run == Tick::NEVER || self > last_run
+    }
+}
+
+/// The ticks at which a component or resource was added and last changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentTicks {
+    /// The tick at which the data was added.
+    pub added: Tick,
+    /// The tick at which the data was last mutably accessed.
+    pub changed: Tick,
+}
+
+impl ComponentTicks {
+    /// Create ticks for data that has just been added at `tick`.
+    pub fn new(tick: Tick) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    /// Returns whether or not the data was added since the system last ran.
+    #[inline]
+    pub fn is_added(&self, ticks: SystemTicks) -> bool {
+        self.added.is_newer_than(ticks.last_run)
+    }
+
+    /// Returns whether or not the data was added or changed since the system last ran.
+    #[inline]
+    pub fn is_changed(&self, ticks: SystemTicks) -> bool {
+        self.changed.is_newer_than(ticks.last_run)
+    }
+}
+
+/// The change ticks for a single run of a system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemTicks {
+    /// The tick that the system last ran at, or [`Tick::NEVER`] if this is its first run.
+    pub last_run: Tick,
+    /// The tick that the system is running at. Changes made by the system are recorded with this
+    /// tick.
+    pub this_run: Tick,
+}
+
+impl SystemTicks {
+    /// Get ticks for code that is not running inside of a tracked system.
+    ///
+    /// Everything in the world is considered to be newer than the last run, and changes are
+    /// recorded at the world's current change tick.
+    pub fn untracked(world: &World) -> Self {
+        Self {
+            last_run: Tick::NEVER,
+            this_run: world.change_tick(),
+        }
+    }
+}
+
+impl SystemParam for SystemTicks {
+    type State = SystemTicks;
+    type Param<'s> = SystemTicks;
+
+    fn get_state(world: &World) -> Self::State {
+        SystemTicks::untracked(world)
+    }
+
+    fn get_state_for_system(_world: &World, meta: &SystemMeta) -> Self::State {
+        meta.ticks
+    }
+
+    fn access(_access: &mut SystemAccess) {}
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        *state
+    }
+}
+
+/// The change ticks of a resource.
+///
+/// These are stored atomically so that they can be updated through a shared reference to the
+/// resource cell.
+#[derive(Debug, Default)]
+pub struct ResourceTicks {
+    added: AtomicU64,
+    changed: AtomicU64,
+}
+
+impl Clone for ResourceTicks {
+    fn clone(&self) -> Self {
+        let ticks = self.get();
+        Self {
+            added: AtomicU64::new(ticks.added.0),
+            changed: AtomicU64::new(ticks.changed.0),
+        }
+    }
+}
+
+impl ResourceTicks {
+    /// Get the current ticks.
+    pub fn get(&self) -> ComponentTicks {
+        ComponentTicks {
+            added: Tick(self.added.load(Ordering::Relaxed)),
+            changed: Tick(self.changed.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Overwrite the ticks.
+    pub fn set(&self, ticks: ComponentTicks) {
+        self.added.store(ticks.added.0, Ordering::Relaxed);
+        self.changed.store(ticks.changed.0, Ordering::Relaxed);
+    }
+
+    /// Mark the resource as added, and changed, at the given tick.
+    pub fn set_added(&self, tick: Tick) {
+        self.set(ComponentTicks::new(tick));
+    }
+
+    /// Mark the resource as changed at the given tick.
+    pub fn set_changed(&self, tick: Tick) {
+        self.changed.store(tick.0, Ordering::Relaxed);
+    }
+}
+
+/// The change tick of a [`World`] and the tick that each system last ran at.
+#[derive(Debug)]
+pub(crate) struct WorldTicks {
+    /// The next tick that will be given to a system.
+    ///
+    /// This is also the tick that changes made outside of systems are recorded at.
+    change_tick: AtomicU64,
+    /// The tick that each system last ran at.
+    last_runs: Mutex<HashMap<SystemId, Tick>>,
+    /// Ticks that have been reserved for the next run of a system.
+    reserved: Mutex<HashMap<SystemId, Tick>>,
+    /// The ticks of the outer system, for systems that are running as part of another system.
+    lent: Mutex<HashMap<SystemId, SystemTicks>>,
+}
+
+impl Default for WorldTicks {
+    fn default() -> Self {
+        Self {
+            // Start after `Tick::NEVER` so that everything is newer than a system's first run.
+            change_tick: AtomicU64::new(1),
+            last_runs: default(),
+            reserved: default(),
+            lent: default(),
+        }
+    }
+}
+
+impl Clone for WorldTicks {
+    fn clone(&self) -> Self {
+        Self {
+            change_tick: AtomicU64::new(self.change_tick.load(Ordering::Relaxed)),
+            last_runs: Mutex::new(self.last_runs.lock().unwrap().clone()),
+            reserved: Mutex::new(self.reserved.lock().unwrap().clone()),
+            lent: Mutex::new(self.lent.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl WorldTicks {
+    pub fn change_tick(&self) -> Tick {
+        Tick(self.change_tick.load(Ordering::Relaxed))
+    }
+
+    /// Advance the change tick without running a system, and get the new tick.
+    pub fn advance_change_tick(&self) -> Tick {
+        Tick(self.change_tick.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    pub fn reserve_system_run(&self, id: SystemId) {
+        let tick = Tick(self.change_tick.fetch_add(1, Ordering::Relaxed));
+        self.reserved.lock().unwrap().insert(id, tick);
+    }
+
+    pub fn begin_system_run(&self, id: SystemId) -> SystemTicks {
+        if let Some(ticks) = self.lent.lock().unwrap().remove(&id) {
+            return ticks;
+        }
+        let reserved = self.reserved.lock().unwrap().remove(&id);
+        let this_run =
+            reserved.unwrap_or_else(|| Tick(self.change_tick.fetch_add(1, Ordering::Relaxed)));
+        let last_run = self
+            .last_runs
+            .lock()
+            .unwrap()
+            .insert(id, this_run)
+            .unwrap_or(Tick::NEVER);
+        SystemTicks { last_run, this_run }
+    }
+
+    pub fn forget_system(&self, id: SystemId) {
+        self.last_runs.lock().unwrap().remove(&id);
+    }
+
+    /// Give the system the ticks of another system for its next run, instead of tracking its own.
+    pub fn lend_system_run(&self, id: SystemId, ticks: SystemTicks) {
+        self.lent.lock().unwrap().insert(id, ticks);
+    }
+
+    /// Remove the lent ticks of a system, if it didn't use them.
+    pub fn end_lent_system_run(&self, id: SystemId) {
+        self.lent.lock().unwrap().remove(&id);
+    }
+
+    /// Get the number of systems that the ticks are tracked for.
+    #[cfg(test)]
+    pub fn tracked_systems(&self) -> usize {
+        self.last_runs.lock().unwrap().len() + self.reserved.lock().unwrap().len()
+    }
+}
+
+/// [`SystemParam`] for iterating over components that have been added since the system last ran.
+///
+/// This dereferences to the [`ComponentStore`], so it may also be used like [`Comp`], and may be
+/// used as a [`QueryItem`] to only yield entities whose component was added since the system last
+/// ran.
+pub struct Added<'a, T: HasSchema> {
+    store: Comp<'a, T>,
+    ticks: SystemTicks,
+}
+
+/// [`SystemParam`] for iterating over components that have been added or changed since the system
+/// last ran.
+///
+/// This dereferences to the [`ComponentStore`], so it may also be used like [`Comp`], and may be
+/// used as a [`QueryItem`] to only yield entities whose component was added or mutably accessed
+/// since the system last ran.
+pub struct Changed<'a, T: HasSchema> {
+    store: Comp<'a, T>,
+    ticks: SystemTicks,
+}
+
+macro_rules! impl_change_filter {
+    ($filter:ident, $bitset_fn:ident) => {
+        impl<'a, T: HasSchema> $filter<'a, T> {
+            /// Get the ticks of the system that this parameter was borrowed for.
+            pub fn system_ticks(&self) -> SystemTicks {
+                self.ticks
+            }
+        }
+
+        impl<'a, T: HasSchema> std::ops::Deref for $filter<'a, T> {
+            type Target = ComponentStore<T>;
+            fn deref(&self) -> &Self::Target {
+                &self.store
+            }
+        }
+
+        impl<'a, T: HasSchema> SystemParam for $filter<'a, T> {
+            type State = (AtomicComponentStore<T>, SystemTicks);
+            type Param<'p> = $filter<'p, T>;
+
+            fn get_state(world: &World) -> Self::State {
+                (
+                    world.components.get_cell::<T>(),
+                    SystemTicks::untracked(world),
+                )
+            }
+
+            fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+                (world.components.get_cell::<T>(), meta.ticks)
+            }
+
+            fn access(access: &mut SystemAccess) {
+                access.add_component_read(T::schema());
+            }
+
+            fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+                $filter {
+                    store: state.0.borrow(),
+                    ticks: state.1,
+                }
+            }
+        }
+
+        impl<'a, 'q, T: HasSchema> QueryItem for &'a $filter<'q, T> {
+            type Iter = ComponentBitsetIterator<'a, T>;
+
+            fn apply_bitset(&self, bitset: &mut BitSetVec) {
+                bitset.bit_and(&self.store.$bitset_fn(self.ticks));
+            }
+
+            fn get_single_with_bitset(
+                self,
+                bitset: Rc<BitSetVec>,
+            ) -> Result<<Self::Iter as Iterator>::Item, QuerySingleError> {
+                ComponentStore::get_single_with_bitset(&**self, bitset)
+            }
+
+            fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
+                ComponentStore::iter_with_bitset(&**self, bitset)
+            }
+        }
+    };
+}
+impl_change_filter!(Added, added_bitset);
+impl_change_filter!(Changed, changed_bitset);
+
+/// [`SystemParam`] for iterating over the entities whose component was removed, or who were killed
+/// while they had one, since the system last ran.
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Sprite;
+/// fn cleanup_sprites(removed: RemovedComponents<Sprite>) {
+///     for entity in removed.iter() {
+///         println!("{entity:?} no longer has a sprite");
+///     }
+/// }
+/// ```
+///
+/// Like [`Events`], removals are only kept until the end of the frame after the one they happened
+/// in, when they are dropped by [`World::maintain()`], so systems that don't run every frame may
+/// miss some of them.
+pub struct RemovedComponents<'a, T: HasSchema> {
+    store: Comp<'a, T>,
+    ticks: SystemTicks,
+}
+
+impl<'a, T: HasSchema> RemovedComponents<'a, T> {
+    /// Iterate over the entities whose component was removed since the system last ran.
+    ///
+    /// An entity may be yielded more than once if its component was removed more than once.
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.store.removed(self.ticks)
+    }
+
+    /// Get the ticks of the system that this parameter was borrowed for.
+    pub fn system_ticks(&self) -> SystemTicks {
+        self.ticks
+    }
+}
+
+impl<'a, T: HasSchema> SystemParam for RemovedComponents<'a, T> {
+    type State = (AtomicComponentStore<T>, SystemTicks);
+    type Param<'p> = RemovedComponents<'p, T>;
+
+    fn get_state(world: &World) -> Self::State {
+        (
+            world.components.get_cell::<T>(),
+            SystemTicks::untracked(world),
+        )
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        (world.components.get_cell::<T>(), meta.ticks)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_component_read(T::schema());
+    }
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        RemovedComponents {
+            store: state.0.borrow(),
+            ticks: state.1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq, Eq)]
+    #[repr(C)]
+    struct Pos(i32);
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq, Eq)]
+    #[repr(C)]
+    struct Counter(u32);
+
+    #[test]
+    fn added_and_changed_filters() {
+        let world = World::new();
+
+        let (e1, e2) = world.run_system(
+            |mut entities: ResMut<Entities>, mut pos: CompMut<Pos>| {
+                let e1 = entities.create();
+                let e2 = entities.create();
+                pos.insert(e1, Pos(0));
+                pos.insert(e2, Pos(0));
+                (e1, e2)
+            },
+            (),
+        );
+
+        let mut added = (|entities: Res<Entities>, pos: Added<Pos>| {
+            entities.iter_with(&pos).map(|(e, _)| e).collect::<Vec<_>>()
+        })
+        .system();
+        let mut changed = (|entities: Res<Entities>, pos: Changed<Pos>| {
+            entities.iter_with(&pos).map(|(e, _)| e).collect::<Vec<_>>()
+        })
+        .system();
+
+        // Everything is new the first time the systems run.
+        assert_eq!(added.run(&world, ()), vec![e1, e2]);
+        assert_eq!(changed.run(&world, ()), vec![e1, e2]);
+
+        // Nothing has happened since then.
+        assert_eq!(added.run(&world, ()), vec![]);
+        assert_eq!(changed.run(&world, ()), vec![]);
+
+        // Mutate one of the components.
+        world.run_system(
+            move |mut pos: CompMut<Pos>| {
+                pos.get_mut(e2).unwrap().0 += 1;
+            },
+            (),
+        );
+        assert_eq!(added.run(&world, ()), vec![]);
+        assert_eq!(changed.run(&world, ()), vec![e2]);
+
+        // Mutably iterating marks everything that was visited as changed.
+        world.run_system(
+            |entities: Res<Entities>, mut pos: CompMut<Pos>| {
+                for (_, pos) in entities.iter_with(&mut pos) {
+                    pos.0 += 1;
+                }
+            },
+            (),
+        );
+        assert_eq!(changed.run(&world, ()), vec![e1, e2]);
+
+        // Re-inserting a component counts as a change, not an addition.
+        world.component_mut::<Pos>().insert(e1, Pos(5));
+        assert_eq!(added.run(&world, ()), vec![]);
+        assert_eq!(changed.run(&world, ()), vec![e1]);
+    }
+
+    #[test]
+    fn changes_after_a_system_in_the_same_frame_are_seen() {
+        let world = World::new();
+        let e = world.resource_mut::<Entities>().create();
+        world.component_mut::<Pos>().insert(e, Pos(0));
+
+        let mut reader =
+            (|entities: Res<Entities>, pos: Changed<Pos>| entities.iter_with(&pos).count())
+                .system();
+        let mut writer = (move |mut pos: CompMut<Pos>| {
+            pos.get_mut(e).unwrap().0 += 1;
+        })
+        .system();
+
+        assert_eq!(reader.run(&world, ()), 1);
+        writer.run(&world, ());
+        assert_eq!(reader.run(&world, ()), 1);
+        assert_eq!(reader.run(&world, ()), 0);
+    }
+
+    #[test]
+    fn removed_components() {
+        let world = World::new();
+        let (e1, e2, e3) = world.run_system(
+            |mut entities: ResMut<Entities>, mut pos: CompMut<Pos>| {
+                let entities = [entities.create(), entities.create(), entities.create()];
+                for entity in entities {
+                    pos.insert(entity, Pos(0));
+                }
+                (entities[0], entities[1], entities[2])
+            },
+            (),
+        );
+        let stored_removals = |world: &World| {
+            let ticks = SystemTicks {
+                last_run: Tick::NEVER,
+                this_run: world.change_tick(),
+            };
+            world.component::<Pos>().removed(ticks).count()
+        };
+
+        let mut removed =
+            (|removed: RemovedComponents<Pos>| removed.iter().collect::<Vec<_>>()).system();
+        assert_eq!(removed.run(&world, ()), vec![]);
+
+        // Frame 1: a component is removed, and an entity that has one is killed.
+        world.run_system(move |mut pos: CompMut<Pos>| pos.remove(e1), ());
+        world.run_system(move |mut entities: ResMut<Entities>| entities.kill(e2), ());
+        world.maintain();
+
+        // Frame 2: the removals are seen once.
+        assert_eq!(removed.run(&world, ()), vec![e1, e2]);
+        assert_eq!(removed.run(&world, ()), vec![]);
+        let mut late =
+            (|removed: RemovedComponents<Pos>| removed.iter().collect::<Vec<_>>()).system();
+        world.run_system(move |mut pos: CompMut<Pos>| pos.remove(e3), ());
+        world.maintain();
+
+        // Frame 3: the removals from frame 1 have been dropped.
+        assert_eq!(stored_removals(&world), 1);
+        assert_eq!(late.run(&world, ()), vec![e3]);
+        world.maintain();
+
+        // Frame 4: the removal from frame 2 has been dropped, even though `removed` never saw it.
+        assert_eq!(stored_removals(&world), 0);
+        assert_eq!(removed.run(&world, ()), vec![]);
+
+        // Removals outside of systems are dropped too.
+        world.component_mut::<Pos>().insert(e1, Pos(0));
+        world.component_mut::<Pos>().remove(e1);
+        world.maintain();
+        assert_eq!(stored_removals(&world), 1);
+        world.maintain();
+        assert_eq!(stored_removals(&world), 0);
+    }
+
+    #[test]
+    fn resource_change_detection() {
+        let world = World::new();
+        world.insert_resource(Counter(0));
+
+        let mut reader =
+            (|counter: Res<Counter>| (counter.is_added(), counter.is_changed())).system();
+        let mut writer = (|mut counter: ResMut<Counter>| counter.0 += 1).system();
+        let mut peeker = (|counter: ResMut<Counter>| counter.0).system();
+
+        assert_eq!(reader.run(&world, ()), (true, true));
+        assert_eq!(reader.run(&world, ()), (false, false));
+
+        // Only mutably dereferencing the resource counts as a change.
+        peeker.run(&world, ());
+        assert_eq!(reader.run(&world, ()), (false, false));
+
+        writer.run(&world, ());
+        assert_eq!(reader.run(&world, ()), (false, true));
+
+        // Changes made outside of systems are detected too.
+        world.resource_mut::<Counter>().0 += 1;
+        assert_eq!(reader.run(&world, ()), (false, true));
+        assert_eq!(reader.run(&world, ()), (false, false));
+    }
+
+    #[test]
+    fn snapshot_restores_ticks() {
+        let mut world = World::new();
+        let e = world.resource_mut::<Entities>().create();
+        world.component_mut::<Pos>().insert(e, Pos(0));
+
+        let mut changed =
+            (|entities: Res<Entities>, pos: Changed<Pos>| entities.iter_with(&pos).count())
+                .system();
+        let mut writer = (move |mut pos: CompMut<Pos>| {
+            pos.get_mut(e).unwrap().0 += 1;
+        })
+        .system();
+
+        changed.run(&world, ());
+        writer.run(&world, ());
+        let snapshot = world.clone();
+
+        // Run into the "future" and observe the change.
+        assert_eq!(changed.run(&world, ()), 1);
+        assert_eq!(changed.run(&world, ()), 0);
+
+        // After rolling back, the system should observe the change again, exactly like it did the
+        // first time.
+        world.load_snapshot(snapshot);
+        assert_eq!(changed.run(&world, ()), 1);
+        assert_eq!(changed.run(&world, ()), 0);
+    }
+}
@@ -0,0 +1,248 @@
+//! Deterministic checksums of the [`World`].
+//!
+//! Checksums are used to detect when the worlds of the peers in a networked session have gone out
+//! of sync. Only the components and resources that opt in, by adding the [`IncludeInChecksum`]
+//! type data to their schema, are hashed, along with the alive entities.
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! #[derive(HasSchema, Clone, Default)]
+//! #[derive_type_data(IncludeInChecksum)]
+//! #[repr(C)]
+//! struct Health(u32);
+//!
+//! let world = World::new();
+//! let entity = world.resource_mut::<Entities>().create();
+//! world.component_mut::<Health>().insert(entity, Health(100));
+//!
+//! let checksum = world.checksum();
+//! assert!(checksum.skipped.is_empty());
+//! ```
+
+use std::hash::Hasher;
+
+use fxhash::FxHasher;
+
+use crate::prelude::*;
+
+/// [Type data][bones_schema::alloc::TypeDatas] that includes a component or resource in the
+/// [`World::checksum()`].
+///
+/// Types are hashed using their schema's `hash_fn` if they have one, or by hashing their fields
+/// otherwise. Types that contain opaque data without a `hash_fn` cannot be hashed, and are reported
+/// in [`WorldChecksum::skipped`].
+#[derive(HasSchema, Clone, Copy, Default, Debug)]
+pub struct IncludeInChecksum;
+
+impl<T> FromType<T> for IncludeInChecksum {
+    fn from_type() -> Self {
+        IncludeInChecksum
+    }
+}
+
+/// The result of [`World::checksum()`].
+#[derive(Clone, Debug, Default)]
+pub struct WorldChecksum {
+    /// The checksum of the world.
+    pub checksum: u64,
+    /// The components and resources that are [included in the checksum][IncludeInChecksum], but
+    /// were skipped because they contain data that cannot be hashed.
+    pub skipped: Vec<&'static Schema>,
+}
+
+impl World {
+    /// Compute a checksum of the world.
+    ///
+    /// The checksum includes all of the alive entities, and all of the components and resources
+    /// that have the [`IncludeInChecksum`] type data. The same world will always produce the same
+    /// checksum, no matter the order that its components or resources were initialized in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the included components or resources are mutably borrowed.
+    pub fn checksum(&self) -> WorldChecksum {
+        let mut hasher = FxHasher::default();
+        let mut skipped = Vec::new();
+
+        let entities = self.resource::<Entities>();
+        for entity in entities.iter() {
+            hasher.write_u32(entity.index());
+            hasher.write_u32(entity.generation());
+        }
+
+        let mut stores = self
+            .components
+            .components
+            .read_only_view()
+            .values()
+            .filter(|store| {
+                let store = store.borrow();
+                // Stores are created whenever they are accessed, so empty stores are skipped to
+                // avoid depending on which stores happen to exist.
+                is_included(store.schema()) && store.bitset().bit_any()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        stores.sort_by_key(|store| store.borrow().schema().full_name.as_str());
+        for store in stores {
+            let store = store.borrow();
+            let schema = store.schema();
+            let mut store_hasher = FxHasher::default();
+            let hashed = entities.iter_with_bitset(store.bitset()).all(|entity| {
+                store_hasher.write_u32(entity.index());
+                hash_value(store.get_ref(entity).unwrap(), &mut store_hasher)
+            });
+            if hashed {
+                hasher.write(schema.full_name.as_bytes());
+                hasher.write_u64(store_hasher.finish());
+            } else {
+                skipped.push(schema);
+            }
+        }
+
+        let mut resources = self
+            .resources
+            .untyped()
+            .owned_cells()
+            .into_iter()
+            .filter(|cell| is_included(cell.schema()))
+            .collect::<Vec<_>>();
+        resources.sort_by_key(|cell| cell.schema().full_name.as_str());
+        for cell in resources {
+            let schema = cell.schema();
+            let Some(resource) = cell.borrow() else {
+                continue;
+            };
+            let mut resource_hasher = FxHasher::default();
+            if hash_value(resource.as_ref(), &mut resource_hasher) {
+                hasher.write(schema.full_name.as_bytes());
+                hasher.write_u64(resource_hasher.finish());
+            } else {
+                skipped.push(schema);
+            }
+        }
+
+        WorldChecksum {
+            checksum: hasher.finish(),
+            skipped,
+        }
+    }
+}
+
+/// Whether or not the type with the given schema is included in checksums.
+fn is_included(schema: &Schema) -> bool {
+    schema.type_data.get::<IncludeInChecksum>().is_some()
+}
+
+/// Hash a value, returning `false` if it contains data that cannot be hashed.
+fn hash_value(value: SchemaRef, hasher: &mut FxHasher) -> bool {
+    if let Some(hash) = value.hash() {
+        hasher.write_u64(hash);
+        return true;
+    }
+
+    match value.access() {
+        SchemaRefAccess::Struct(s) => s.fields().all(|field| hash_value(field.value, hasher)),
+        SchemaRefAccess::Enum(e) => {
+            hasher.write_u32(e.variant_idx());
+            e.value()
+                .fields()
+                .all(|field| hash_value(field.value, hasher))
+        }
+        SchemaRefAccess::Vec(v) => {
+            hasher.write_u64(v.len() as u64);
+            v.iter().all(|item| hash_value(item, hasher))
+        }
+        SchemaRefAccess::Map(m) => {
+            // The iteration order of maps is not deterministic, so we combine the hashes of the
+            // entries in a way that doesn't depend on the order.
+            let mut entries = 0u64;
+            for (key, value) in m.iter() {
+                let mut entry_hasher = FxHasher::default();
+                if !(hash_value(key, &mut entry_hasher) && hash_value(value, &mut entry_hasher)) {
+                    return false;
+                }
+                entries = entries.wrapping_add(entry_hasher.finish());
+            }
+            hasher.write_u64(m.len() as u64);
+            hasher.write_u64(entries);
+            true
+        }
+        SchemaRefAccess::Option(o) => match o.value() {
+            Some(value) => {
+                hasher.write_u8(1);
+                hash_value(value, hasher)
+            }
+            None => {
+                hasher.write_u8(0);
+                true
+            }
+        },
+        // Primitives with a `hash_fn` were handled above.
+        SchemaRefAccess::Primitive(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[derive_type_data(IncludeInChecksum)]
+    #[repr(C)]
+    struct Pos(i32, i32);
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Untracked(i32);
+
+    #[derive(HasSchema, Clone, Default)]
+    #[derive_type_data(IncludeInChecksum)]
+    #[repr(C)]
+    struct Extra(i32);
+
+    #[derive(HasSchema, Clone, Default)]
+    #[derive_type_data(IncludeInChecksum)]
+    struct Opaque(i32);
+
+    fn world(positions: &[(i32, i32)]) -> World {
+        let mut world = World::new();
+        world.init_resource::<Untracked>();
+        for &(x, y) in positions {
+            let entity = world.resource_mut::<Entities>().create();
+            world.component_mut::<Pos>().insert(entity, Pos(x, y));
+            world.component_mut::<Opaque>().insert(entity, Opaque(x));
+        }
+        world
+    }
+
+    #[test]
+    fn checksums_are_deterministic() {
+        let a = world(&[(1, 2), (3, 4)]);
+        let b = world(&[(1, 2), (3, 4)]);
+        let c = world(&[(1, 2), (3, 5)]);
+        assert_eq!(a.checksum().checksum, b.checksum().checksum);
+        assert_ne!(a.checksum().checksum, c.checksum().checksum);
+
+        // Types that aren't included don't affect the checksum.
+        b.resource_mut::<Untracked>().0 = 7;
+        assert_eq!(a.checksum().checksum, b.checksum().checksum);
+
+        // Cloning the world doesn't change the checksum.
+        assert_eq!(a.checksum().checksum, a.clone().checksum().checksum);
+    }
+
+    #[test]
+    fn empty_stores_are_ignored() {
+        let a = world(&[(1, 2)]);
+        let b = world(&[(1, 2)]);
+        b.components.get::<Extra>();
+        assert_eq!(a.checksum().checksum, b.checksum().checksum);
+    }
+
+    #[test]
+    fn opaque_types_are_skipped() {
+        let checksum = world(&[(1, 2)]).checksum();
+        assert_eq!(checksum.skipped, [Opaque::schema()]);
+    }
+}
@@ -5,10 +5,14 @@ use std::sync::Arc;
 
 use crate::prelude::*;
 
+mod bundle;
+mod hooks;
 mod iterator;
 mod typed;
 mod untyped;
 
+pub use bundle::*;
+pub use hooks::*;
 pub use iterator::*;
 pub use typed::*;
 pub use untyped::*;
@@ -95,6 +99,39 @@ impl ComponentStores {
             |_key, value| value.clone(),
         )
     }
+
+    /// Get a clone of the cells of all of the component stores that have been initialized.
+    pub fn all_cells(&self) -> Vec<UntypedAtomicComponentStore> {
+        self.components.read_only_view().values().cloned().collect()
+    }
+
+    /// Set the [`ComponentHooks`] for the components of type `T`.
+    ///
+    /// This replaces any hooks from the type data of `T`'s schema.
+    pub fn set_hooks<T: HasSchema>(&self, hooks: ComponentHooks) {
+        self.get_by_schema(T::schema())
+            .borrow_mut()
+            .set_hooks(hooks);
+    }
+
+    /// Take the commands that were added by the [`ComponentHooks`] of all of the component stores.
+    ///
+    /// The commands are sorted by the name of their component type, so that they are run in the
+    /// same order every time.
+    pub(crate) fn take_hook_commands(&self) -> Vec<StaticSystem<(), ()>> {
+        let mut queues = self
+            .components
+            .read_only_view()
+            .values()
+            .filter_map(|store| {
+                let mut store = store.borrow_mut();
+                let queue = store.take_hook_commands().queue;
+                (!queue.is_empty()).then(|| (store.schema().full_name, queue))
+            })
+            .collect::<Vec<_>>();
+        queues.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        queues.into_iter().flat_map(|(_, queue)| queue).collect()
+    }
 }
 
 #[cfg(test)]
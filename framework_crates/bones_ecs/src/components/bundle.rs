@@ -0,0 +1,58 @@
+//! Bundles of components.
+
+use crate::prelude::*;
+
+/// A set of components that may be inserted on an entity together.
+///
+/// Bundles are implemented for tuples of up to 16 components. A single component may be used as a
+/// bundle by putting it in a tuple of one: `(component,)`.
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Pos(f32, f32);
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Vel(f32, f32);
+/// fn spawn_bullet(mut commands: Commands) {
+///     commands.spawn((Pos(0.0, 0.0), Vel(1.0, 0.0)));
+/// }
+/// ```
+pub trait Bundle: Sync + Send + 'static {
+    /// Insert the components in the bundle on `entity`.
+    ///
+    /// Components that the entity already has are replaced.
+    fn insert(self, world: &World, entity: Entity);
+}
+
+macro_rules! impl_bundle {
+    ( $( $args:ident, )* ) => {
+        impl<$( $args: HasSchema, )*> Bundle for ($( $args, )*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn insert(self, world: &World, entity: Entity) {
+                let ($( $args, )*) = self;
+                $(
+                    world.component_mut::<$args>().insert(entity, $args);
+                )*
+            }
+        }
+    };
+}
+
+macro_rules! impl_bundles {
+    // base case
+    () => {
+        impl_bundle!();
+    };
+    (
+        $head:ident,
+        $(
+            $tail:ident,
+        )*
+    ) => {
+        // recursive call
+        impl_bundle!($head, $( $tail, )* );
+        impl_bundles!($( $tail, )* );
+    }
+}
+
+impl_bundles!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P,);
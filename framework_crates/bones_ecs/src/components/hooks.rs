@@ -0,0 +1,167 @@
+//! Component lifecycle hooks.
+
+use crate::prelude::*;
+
+/// A function that is called by a component store when a component's lifecycle event happens.
+///
+/// See [`ComponentHooks`].
+pub type ComponentHook = fn(ComponentHookCtx<'_>);
+
+/// The context that is passed to a [`ComponentHook`].
+pub struct ComponentHookCtx<'a> {
+    /// The entity that the component belongs to.
+    pub entity: Entity,
+    /// The component.
+    ///
+    /// For [`on_insert`][ComponentHooks::on_insert] this is the component that was inserted, and
+    /// for the other hooks it is the component that is about to be replaced or removed.
+    pub component: SchemaRef<'a>,
+    /// The queue that the hook can add commands to.
+    ///
+    /// Hooks are called while the component store is borrowed, so they cannot access the [`World`]
+    /// directly. Instead, the commands they add are run at the end of the current [`SystemStage`],
+    /// or when the world is [maintained][World::maintain].
+    pub commands: &'a mut CommandQueue,
+}
+
+/// Hooks that are run by a component store when components are inserted, replaced, or removed.
+///
+/// Hooks are usually registered as [type data][bones_schema::alloc::TypeDatas] on the component,
+/// and are picked up automatically when the component store is created:
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// #[derive(HasSchema, Clone, Default)]
+/// #[type_data(ComponentHooks::new().on_kill(forget_player))]
+/// struct Player;
+///
+/// #[derive(HasSchema, Clone, Default)]
+/// struct PlayerList(Vec<Entity>);
+///
+/// fn forget_player(ctx: ComponentHookCtx) {
+///     let entity = ctx.entity;
+///     ctx.commands.add(move |mut list: ResMutInit<PlayerList>| {
+///         list.0.retain(|&x| x != entity);
+///     });
+/// }
+/// ```
+///
+/// They may also be set on the store at runtime using [`ComponentStores::set_hooks()`].
+#[derive(HasSchema, Clone, Copy, Default)]
+pub struct ComponentHooks {
+    /// Called after a component is inserted for an entity that didn't already have one.
+    pub on_insert: Option<ComponentHook>,
+    /// Called before a component is replaced by inserting another one for the same entity.
+    pub on_replace: Option<ComponentHook>,
+    /// Called before a component is removed.
+    ///
+    /// This is not called for the components of killed entities, see
+    /// [`on_kill`][Self::on_kill].
+    pub on_remove: Option<ComponentHook>,
+    /// Called before the component of a killed entity is removed by [`World::maintain()`].
+    pub on_kill: Option<ComponentHook>,
+}
+
+impl ComponentHooks {
+    /// Create an empty set of hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`on_insert`][Self::on_insert] hook.
+    pub fn on_insert(mut self, hook: ComponentHook) -> Self {
+        self.on_insert = Some(hook);
+        self
+    }
+
+    /// Set the [`on_replace`][Self::on_replace] hook.
+    pub fn on_replace(mut self, hook: ComponentHook) -> Self {
+        self.on_replace = Some(hook);
+        self
+    }
+
+    /// Set the [`on_remove`][Self::on_remove] hook.
+    pub fn on_remove(mut self, hook: ComponentHook) -> Self {
+        self.on_remove = Some(hook);
+        self
+    }
+
+    /// Set the [`on_kill`][Self::on_kill] hook.
+    pub fn on_kill(mut self, hook: ComponentHook) -> Self {
+        self.on_kill = Some(hook);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[type_data(ComponentHooks::new()
+        .on_insert(|ctx| log(ctx, "insert"))
+        .on_replace(|ctx| log(ctx, "replace"))
+        .on_remove(|ctx| log(ctx, "remove"))
+        .on_kill(|ctx| log(ctx, "kill")))]
+    struct Tracked(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Log(Vec<(Entity, &'static str, u32)>);
+
+    fn log(ctx: ComponentHookCtx, event: &'static str) {
+        let entity = ctx.entity;
+        let value = ctx.component.cast::<Tracked>().0;
+        ctx.commands.add(move |mut log: ResMutInit<Log>| {
+            log.0.push((entity, event, value));
+        });
+    }
+
+    #[test]
+    fn hooks_are_run() {
+        let world = World::new();
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        let (a, b) = {
+            let mut entities = world.resource_mut::<Entities>();
+            (entities.create(), entities.create())
+        };
+        stage.add_system(
+            (move |mut tracked: CompMut<Tracked>| {
+                tracked.insert(a, Tracked(1));
+                tracked.insert(a, Tracked(2));
+                tracked.insert(b, Tracked(3));
+                tracked.remove(a);
+            })
+            .system(),
+        );
+        stage.run(&world);
+
+        assert_eq!(
+            world.resource::<Log>().0,
+            [
+                (a, "insert", 1),
+                (a, "replace", 1),
+                (b, "insert", 3),
+                (a, "remove", 2)
+            ]
+        );
+
+        world.resource_mut::<Log>().0.clear();
+        world.resource_mut::<Entities>().kill(b);
+        world.maintain();
+        assert_eq!(world.resource::<Log>().0, [(b, "kill", 3)]);
+    }
+
+    #[test]
+    fn clone_world_with_queued_hook_commands() {
+        let world = World::new();
+        let entity = world.resource_mut::<Entities>().create();
+        world.component_mut::<Tracked>().insert(entity, Tracked(1));
+
+        // The hook command is still queued, since it was inserted outside of a stage.
+        let snapshot = world.clone();
+        assert_eq!(snapshot.component::<Tracked>().get(entity).unwrap().0, 1);
+
+        SimpleSystemStage::new(CoreStage::Update).run(&world);
+        assert_eq!(world.resource::<Log>().0, [(entity, "insert", 1)]);
+    }
+}
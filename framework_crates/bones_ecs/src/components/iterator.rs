@@ -48,7 +48,7 @@ impl<'a> Iterator for UntypedComponentBitsetIterator<'a> {
             // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
             Some(unsafe {
                 SchemaRef::from_ptr_schema(
-                    self.components.storage.unchecked_idx(self.current_id),
+                    self.components.unchecked_idx(self.current_id),
                     self.components.schema,
                 )
             })
@@ -102,10 +102,7 @@ impl<'a> Iterator for UntypedComponentOptionalBitsetIterator<'a> {
             // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
             Some(Some(unsafe {
                 SchemaRef::from_ptr_schema(
-                    self.inner
-                        .components
-                        .storage
-                        .unchecked_idx(self.inner.current_id),
+                    self.inner.components.unchecked_idx(self.inner.current_id),
                     self.inner.components.schema,
                 )
             }))
@@ -161,13 +158,11 @@ impl<'a> Iterator for UntypedComponentOptionalBitsetIteratorMut<'a> {
 
         let ret = if self.inner.components.bitset.bit_test(self.inner.current_id) {
             self.found += 1;
+            self.inner.components.mark_changed(self.inner.current_id);
             // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
             Some(Some(unsafe {
                 SchemaRefMut::from_ptr_schema(
-                    self.inner
-                        .components
-                        .storage
-                        .unchecked_idx(self.inner.current_id),
+                    self.inner.components.unchecked_idx(self.inner.current_id),
                     self.inner.components.schema,
                 )
             }))
@@ -203,11 +198,12 @@ impl<'a> Iterator for UntypedComponentBitsetIteratorMut<'a> {
             self.current_id += 1;
         }
         let ret = if self.current_id < max_id {
+            self.components.mark_changed(self.current_id);
             // SAFE: We know that the index is within bounds, and we know that the pointer will be
             // valid for the new lifetime.
             Some(unsafe {
                 SchemaRefMut::from_ptr_schema(
-                    self.components.storage.unchecked_idx(self.current_id),
+                    self.components.unchecked_idx(self.current_id),
                     self.components.schema,
                 )
             })
@@ -102,6 +102,61 @@ impl<T: HasSchema> ComponentStore<T> {
         })
     }
 
+    /// Get the tick that insertions and mutable accesses are currently recorded at.
+    #[inline]
+    pub fn change_tick(&self) -> Tick {
+        self.untyped.change_tick()
+    }
+
+    /// Set the tick that insertions and mutable accesses will be recorded at.
+    ///
+    /// This is done automatically when borrowing the store through [`CompMut`] or
+    /// [`World::component_mut()`].
+    #[inline]
+    pub fn set_change_tick(&mut self, tick: Tick) {
+        self.untyped.set_change_tick(tick)
+    }
+
+    /// Get the change detection ticks for the component of `Entity`.
+    #[inline]
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        self.untyped.ticks(entity)
+    }
+
+    /// Returns whether or not the component of `Entity` was added since the system with the given
+    /// ticks last ran.
+    #[inline]
+    pub fn is_added(&self, entity: Entity, ticks: SystemTicks) -> bool {
+        self.ticks(entity).is_some_and(|t| t.is_added(ticks))
+    }
+
+    /// Returns whether or not the component of `Entity` was added or changed since the system
+    /// with the given ticks last ran.
+    #[inline]
+    pub fn is_changed(&self, entity: Entity, ticks: SystemTicks) -> bool {
+        self.ticks(entity).is_some_and(|t| t.is_changed(ticks))
+    }
+
+    /// Get a bitset of the entities whose component was added since the system with the given
+    /// ticks last ran.
+    #[inline]
+    pub fn added_bitset(&self, ticks: SystemTicks) -> BitSetVec {
+        self.untyped.added_bitset(ticks)
+    }
+
+    /// Iterate over the entities whose component was removed, or who were killed while they had
+    /// one, since the system with the given ticks last ran.
+    pub fn removed(&self, ticks: SystemTicks) -> impl Iterator<Item = Entity> + '_ {
+        self.untyped.removed(ticks)
+    }
+
+    /// Get a bitset of the entities whose component was added or changed since the system with the
+    /// given ticks last ran.
+    #[inline]
+    pub fn changed_bitset(&self, ticks: SystemTicks) -> BitSetVec {
+        self.untyped.changed_bitset(ticks)
+    }
+
     /// Removes the component of `Entity`.
     /// Returns `Some(T)` if the entity did have the component.
     /// Returns `None` if the entity did not have the component.
@@ -303,7 +358,7 @@ impl<'a, T: HasSchema> ComponentIterBitset<'a, T> for ComponentStore<T> {
 mod tests {
     #![allow(non_snake_case)]
 
-    use std::{iter, rc::Rc};
+    use std::{iter, rc::Rc, sync::Arc};
 
     use crate::prelude::*;
 
@@ -380,6 +435,25 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn clones_share_unmodified_chunks() {
+        let chunk_size = COMPONENT_CHUNK_SIZE as u32;
+        let mut a = store(&[1, chunk_size + 1]);
+        let b = a.clone();
+        assert!(Arc::ptr_eq(&a.untyped.chunks[0], &b.untyped.chunks[0]));
+
+        // Only the modified chunk is cloned.
+        a.get_mut(entity(1)).unwrap().0 = 2;
+        assert!(!Arc::ptr_eq(&a.untyped.chunks[0], &b.untyped.chunks[0]));
+        assert!(Arc::ptr_eq(&a.untyped.chunks[1], &b.untyped.chunks[1]));
+        assert_eq!(a.get(entity(1)), Some(&A(2)));
+        assert_eq!(b.get(entity(1)), Some(&A(1)));
+
+        a.remove(entity(chunk_size + 1));
+        assert_eq!(a.get(entity(chunk_size + 1)), None);
+        assert_eq!(b.get(entity(chunk_size + 1)), Some(&A(chunk_size + 1)));
+    }
+
     #[test]
     fn get_single_with_bitset() {
         {
@@ -6,16 +6,48 @@ use std::{
     mem::MaybeUninit,
     ptr::{self},
     rc::Rc,
+    sync::Arc,
 };
 
+/// The number of components stored in each chunk of an [`UntypedComponentStore`].
+pub const COMPONENT_CHUNK_SIZE: usize = 64;
+
 /// Holds components of a given type indexed by `Entity`.
 ///
 /// We do not check if the given entity is alive here, this should be done using `Entities`.
+///
+/// The store also keeps track of the tick at which each component was added and last mutably
+/// accessed, for [change detection][crate::change_detection]. Changes are recorded at the store's
+/// [change tick][Self::change_tick], which is updated automatically when the store is borrowed
+/// through [`CompMut`] or [`World::component_mut()`].
+///
+/// The store runs the [`ComponentHooks`] registered for its components when they are inserted,
+/// replaced, or removed.
+///
+/// ## Copy-on-write
+///
+/// Components are stored in reference-counted chunks of [`COMPONENT_CHUNK_SIZE`] components.
+/// Cloning the store, which happens every time the [`World`] is snapshotted for rollback, only
+/// clones the references to the chunks. A chunk is cloned the first time one of its components is
+/// inserted, removed, or mutably accessed while it is shared with another store, so the cost of a
+/// snapshot depends on how many components were changed since the last one, and not on the
+/// number of components in the store.
+///
+/// Because of this, the `clone_fn` of the component schema isn't called when the store is cloned,
+/// but when a shared chunk is first modified.
 pub struct UntypedComponentStore {
     pub(crate) bitset: BitSetVec,
-    pub(crate) storage: ResizableAlloc,
+    pub(crate) chunks: Vec<Arc<ComponentChunk>>,
+    pub(crate) change_tick: Tick,
     pub(crate) max_id: usize,
     pub(crate) schema: &'static Schema,
+    pub(crate) hooks: ComponentHooks,
+    pub(crate) hook_commands: CommandQueue,
+    /// The entities whose component was removed, along with the tick that it was removed at.
+    pub(crate) removed: Vec<(Entity, Tick)>,
+    /// The tick that the current frame started at, when [`World::maintain()`] last ran. Removals
+    /// from before this are dropped the next time it runs.
+    pub(crate) removed_since: Tick,
 }
 
 unsafe impl Sync for UntypedComponentStore {}
@@ -23,10 +55,72 @@ unsafe impl Send for UntypedComponentStore {}
 
 impl Clone for UntypedComponentStore {
     fn clone(&self) -> Self {
-        let new_storage = self.storage.clone();
+        Self {
+            bitset: self.bitset.clone(),
+            // Only clone the references to the chunks, they are cloned when they are modified.
+            chunks: self.chunks.clone(),
+            change_tick: self.change_tick,
+            max_id: self.max_id,
+            schema: self.schema,
+            hooks: self.hooks,
+            // Hooks may queue commands outside of a stage, which stay queued until the next time
+            // commands are applied. Those commands belong to the original store, so they aren't
+            // copied.
+            hook_commands: default(),
+            removed: self.removed.clone(),
+            removed_since: self.removed_since,
+        }
+    }
+}
 
-        for i in 0..self.max_id {
-            if self.bitset.bit_test(i) {
+/// A chunk of [`COMPONENT_CHUNK_SIZE`] components in an [`UntypedComponentStore`], which may be
+/// shared between clones of the store.
+pub(crate) struct ComponentChunk {
+    schema: &'static Schema,
+    storage: ResizableAlloc,
+    /// Bitmask of the indices in the chunk that contain a component.
+    occupied: u64,
+    ticks: [ComponentTicks; COMPONENT_CHUNK_SIZE],
+}
+
+// SOUND: the chunk only stores data of a schema, which must be `Sync + Send`.
+unsafe impl Sync for ComponentChunk {}
+unsafe impl Send for ComponentChunk {}
+
+impl ComponentChunk {
+    fn new(schema: &'static Schema) -> Self {
+        Self {
+            schema,
+            storage: ResizableAlloc::with_capacity(schema.layout(), COMPONENT_CHUNK_SIZE).unwrap(),
+            occupied: 0,
+            ticks: [ComponentTicks::default(); COMPONENT_CHUNK_SIZE],
+        }
+    }
+
+    /// Iterate over the indices in the chunk that contain a component.
+    fn occupied(&self) -> impl Iterator<Item = usize> {
+        let occupied = self.occupied;
+        (0..COMPONENT_CHUNK_SIZE).filter(move |i| occupied & (1 << i) != 0)
+    }
+}
+
+impl Clone for ComponentChunk {
+    fn clone(&self) -> Self {
+        let new = Self {
+            schema: self.schema,
+            storage: ResizableAlloc::with_capacity(self.schema.layout(), COMPONENT_CHUNK_SIZE)
+                .unwrap(),
+            occupied: self.occupied,
+            ticks: self.ticks,
+        };
+
+        if self.occupied != 0 {
+            let clone_fn = self
+                .schema
+                .clone_fn
+                .as_ref()
+                .expect("Cannot clone component");
+            for i in self.occupied() {
                 // SAFE: constructing an UntypedComponent store is unsafe, and the user affirms that
                 // clone_fn will not do anything unsound.
                 //
@@ -34,39 +128,27 @@ impl Clone for UntypedComponentStore {
                 // - And our new pointer is a writable pointer with the same layout
                 unsafe {
                     let prev_ptr = self.storage.unchecked_idx(i);
-                    let new_ptr = new_storage.unchecked_idx(i);
-                    (self
-                        .schema
-                        .clone_fn
-                        .as_ref()
-                        .expect("Cannot clone component")
-                        .get())(prev_ptr, new_ptr);
+                    let new_ptr = new.storage.unchecked_idx(i);
+                    (clone_fn.get())(prev_ptr, new_ptr);
                 }
             }
         }
 
-        Self {
-            bitset: self.bitset.clone(),
-            storage: new_storage,
-            max_id: self.max_id,
-            schema: self.schema,
-        }
+        new
     }
 }
 
-impl Drop for UntypedComponentStore {
+impl Drop for ComponentChunk {
     fn drop(&mut self) {
         if let Some(drop_fn) = &self.schema.drop_fn {
-            for i in 0..self.storage.capacity() {
-                if self.bitset.bit_test(i) {
-                    // SAFE: constructing an UntypedComponent store is unsafe, and the user affirms
-                    // that drop_fn will not do anything unsound.
-                    //
-                    // And our pointer is valid.
-                    unsafe {
-                        let ptr = self.storage.unchecked_idx(i);
-                        drop_fn.get()(ptr);
-                    }
+            for i in self.occupied() {
+                // SAFE: constructing an UntypedComponent store is unsafe, and the user affirms
+                // that drop_fn will not do anything unsound.
+                //
+                // And our pointer is valid.
+                unsafe {
+                    let ptr = self.storage.unchecked_idx(i);
+                    drop_fn.get()(ptr);
                 }
             }
         }
@@ -78,23 +160,29 @@ impl UntypedComponentStore {
     ///
     /// In Rust, you will usually not use [`UntypedComponentStore`] and will use the statically
     /// typed [`ComponentStore<T>`] instead.
+    ///
+    /// The store will use the [`ComponentHooks`] from the schema's type data, if it has any.
     pub fn new(schema: &'static Schema) -> Self {
         Self {
-            bitset: create_bitset(),
-            storage: ResizableAlloc::new(schema.layout()),
+            bitset: BitSetVec::default(),
+            chunks: Vec::new(),
+            change_tick: Tick::default(),
             max_id: 0,
             schema,
+            hooks: schema
+                .type_data
+                .get::<ComponentHooks>()
+                .copied()
+                .unwrap_or_default(),
+            hook_commands: CommandQueue::default(),
+            removed: Vec::new(),
+            removed_since: Tick::NEVER,
         }
     }
 
     /// Create an [`UntypedComponentStore`] that is valid for the given type `T`.
     pub fn for_type<T: HasSchema>() -> Self {
-        Self {
-            bitset: create_bitset(),
-            storage: ResizableAlloc::new(T::schema().layout()),
-            max_id: 0,
-            schema: T::schema(),
-        }
+        Self::new(T::schema())
     }
 
     /// Get the schema of the components stored.
@@ -102,6 +190,142 @@ impl UntypedComponentStore {
         self.schema
     }
 
+    /// Get the hooks that are run when components are inserted, replaced, or removed.
+    pub fn hooks(&self) -> &ComponentHooks {
+        &self.hooks
+    }
+
+    /// Set the hooks that are run when components are inserted, replaced, or removed.
+    ///
+    /// This replaces any hooks from the schema's type data.
+    pub fn set_hooks(&mut self, hooks: ComponentHooks) {
+        self.hooks = hooks;
+    }
+
+    /// Take the commands that were added by the store's hooks.
+    pub(crate) fn take_hook_commands(&mut self) -> CommandQueue {
+        std::mem::take(&mut self.hook_commands)
+    }
+
+    /// Run a hook for the component at the given pointer.
+    ///
+    /// # Safety
+    /// The pointer must point to a component with the store's schema.
+    unsafe fn run_hook(&mut self, hook: Option<ComponentHook>, entity: Entity, ptr: *mut c_void) {
+        if let Some(hook) = hook {
+            hook(ComponentHookCtx {
+                entity,
+                component: SchemaRef::from_ptr_schema(ptr, self.schema),
+                commands: &mut self.hook_commands,
+            });
+        }
+    }
+
+    /// Get the tick that insertions and mutable accesses are currently recorded at.
+    pub fn change_tick(&self) -> Tick {
+        self.change_tick
+    }
+
+    /// Set the tick that insertions and mutable accesses will be recorded at.
+    ///
+    /// This is done automatically when borrowing the store through [`CompMut`] or
+    /// [`World::component_mut()`].
+    pub fn set_change_tick(&mut self, tick: Tick) {
+        self.change_tick = tick;
+    }
+
+    /// Get the change detection ticks for the given entity's component, if it has one.
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        let idx = entity.index() as usize;
+        if self.bitset.bit_test(idx) {
+            Some(self.chunk(idx).ticks[idx % COMPONENT_CHUNK_SIZE])
+        } else {
+            None
+        }
+    }
+
+    /// Get a bitset of the entities whose component was added since the system with the given
+    /// ticks last ran.
+    pub fn added_bitset(&self, ticks: SystemTicks) -> BitSetVec {
+        self.filter_bitset(|t| t.is_added(ticks))
+    }
+
+    /// Get a bitset of the entities whose component was added or changed since the system with the
+    /// given ticks last ran.
+    pub fn changed_bitset(&self, ticks: SystemTicks) -> BitSetVec {
+        self.filter_bitset(|t| t.is_changed(ticks))
+    }
+
+    /// Iterate over the entities whose component was removed, or who were killed while they had
+    /// one, since the system with the given ticks last ran.
+    ///
+    /// Removals are only kept until the end of the frame after the one they happened in, see
+    /// [`RemovedComponents`].
+    pub fn removed(&self, ticks: SystemTicks) -> impl Iterator<Item = Entity> + '_ {
+        self.removed
+            .iter()
+            .filter(move |(_, tick)| tick.is_newer_than(ticks.last_run))
+            .map(|(entity, _)| *entity)
+    }
+
+    /// Drop the removals from before the frame that is ending, and start a new frame of removals at
+    /// `tick`.
+    ///
+    /// This is called by [`World::maintain()`].
+    pub(crate) fn update_removed(&mut self, tick: Tick) {
+        let since = self.removed_since;
+        self.removed.retain(|(_, removed)| *removed >= since);
+        self.removed_since = tick;
+    }
+
+    fn filter_bitset(&self, filter: impl Fn(&ComponentTicks) -> bool) -> BitSetVec {
+        let mut bitset = BitSetVec::with_capacity(self.max_id);
+        for i in 0..self.max_id {
+            if self.bitset.bit_test(i) && filter(&self.chunk(i).ticks[i % COMPONENT_CHUNK_SIZE]) {
+                bitset.bit_set(i);
+            }
+        }
+        bitset
+    }
+
+    /// Record that the component at the given index was mutably accessed.
+    ///
+    /// This makes sure that the chunk containing the component isn't shared with another store, so
+    /// it must be called before getting a mutable pointer to the component.
+    #[inline]
+    pub(crate) fn mark_changed(&mut self, idx: usize) {
+        let change_tick = self.change_tick;
+        self.chunk_mut(idx).ticks[idx % COMPONENT_CHUNK_SIZE].changed = change_tick;
+    }
+
+    /// Get the chunk containing the given index.
+    #[inline]
+    fn chunk(&self, idx: usize) -> &ComponentChunk {
+        &self.chunks[idx / COMPONENT_CHUNK_SIZE]
+    }
+
+    /// Get the chunk containing the given index, cloning it first if it is shared with another
+    /// store.
+    #[inline]
+    fn chunk_mut(&mut self, idx: usize) -> &mut ComponentChunk {
+        Arc::make_mut(&mut self.chunks[idx / COMPONENT_CHUNK_SIZE])
+    }
+
+    /// Get a pointer to the component data at the given index.
+    ///
+    /// # Safety
+    ///
+    /// There must be a chunk allocated for the index. The pointer may only be written to if
+    /// [`mark_changed()`][Self::mark_changed] was called for the index since the store was last
+    /// cloned.
+    #[inline]
+    pub(crate) unsafe fn unchecked_idx(&self, idx: usize) -> *mut c_void {
+        self.chunks
+            .get_unchecked(idx / COMPONENT_CHUNK_SIZE)
+            .storage
+            .unchecked_idx(idx % COMPONENT_CHUNK_SIZE)
+    }
+
     /// Insert component data for the given entity and get the previous component data if present.
     /// # Panics
     /// Panics if the schema of `T` doesn't match the store.
@@ -183,7 +407,10 @@ impl UntypedComponentStore {
 
         // If the component already exists on the entity
         if self.bitset.bit_test(entity.index() as usize) {
-            let ptr = self.storage.unchecked_idx(index);
+            self.mark_changed(index);
+
+            let ptr = self.unchecked_idx(index);
+            self.run_hook(self.hooks.on_replace, entity, ptr);
 
             // Swap the data with the data already there
             ptr::swap_nonoverlapping(ptr, data, size);
@@ -202,10 +429,17 @@ impl UntypedComponentStore {
             // Set the bit indicating that this entity has this component data stored.
             self.bitset.bit_set(index);
 
+            // Record when the component was added.
+            let change_tick = self.change_tick;
+            let chunk = self.chunk_mut(index);
+            let chunk_idx = index % COMPONENT_CHUNK_SIZE;
+            chunk.occupied |= 1 << chunk_idx;
+            chunk.ticks[chunk_idx] = ComponentTicks::new(change_tick);
+
             // Copy the data from the data pointer into our storage
-            self.storage
-                .unchecked_idx(index)
-                .copy_from_nonoverlapping(data, size);
+            let ptr = chunk.storage.unchecked_idx(chunk_idx);
+            ptr.copy_from_nonoverlapping(data, size);
+            self.run_hook(self.hooks.on_insert, entity, ptr);
 
             // There was not already a component of this type
             false
@@ -216,18 +450,13 @@ impl UntypedComponentStore {
     ///
     /// Usually, set this to `entity.index`.
     fn allocate_enough(&mut self, until: usize) {
-        if self.storage.capacity() <= until {
-            self.storage
-                // TODO: Determine a better policy for resizing and pre-allocating component storage.
-                // Right now we double the size of the storage every time we run out. It seems like we
-                // might be able to come up with a smarter policy. On top of that we should
-                // be able to create a type data for components ( see
-                // `bones_framework::metadata_asset()` for example ) that lets you customize the resize
-                // and also pre-allocation strategy for the component. Right now we don't pre-allocate
-                // any memory, but that could be useful for components that know there will be a lot of
-                // them, such as bullets.
-                .resize((until + 1) * 2)
-                .unwrap();
+        // TODO: Determine a better policy for pre-allocating component storage. We should be able
+        // to create a type data for components ( see `bones_framework::metadata_asset()` for
+        // example ) that lets you customize the pre-allocation strategy for the component. Right
+        // now we don't pre-allocate any memory, but that could be useful for components that know
+        // there will be a lot of them, such as bullets.
+        while self.chunks.len() * COMPONENT_CHUNK_SIZE <= until {
+            self.chunks.push(Arc::new(ComponentChunk::new(self.schema)));
         }
     }
 
@@ -258,7 +487,7 @@ impl UntypedComponentStore {
     fn get_idx(&self, idx: usize) -> Option<SchemaRef<'_>> {
         if self.bitset.bit_test(idx) {
             // SOUND: we ensure that there is allocated storge for entities that have their bit set.
-            let ptr = unsafe { self.storage.unchecked_idx(idx) };
+            let ptr = unsafe { self.unchecked_idx(idx) };
             // SOUND: we know that the pointer has our schema.
             Some(unsafe { SchemaRef::from_ptr_schema(ptr, self.schema) })
         } else {
@@ -310,9 +539,10 @@ impl UntypedComponentStore {
 
     fn get_idx_mut<'a>(&mut self, idx: usize) -> Option<SchemaRefMut<'a>> {
         if self.bitset.bit_test(idx) {
+            self.mark_changed(idx);
             // SOUND: we ensure that there is allocated storage for entities that have their bit
-            // set.
-            let ptr = unsafe { self.storage.unchecked_idx(idx) };
+            // set, and we've just made sure its chunk isn't shared.
+            let ptr = unsafe { self.unchecked_idx(idx) };
             // SOUND: we know that the pointer has our schema.
             Some(unsafe { SchemaRefMut::from_ptr_schema(ptr, self.schema) })
         } else {
@@ -396,11 +626,12 @@ impl UntypedComponentStore {
             let index = entities[i].index() as usize;
 
             if self.bitset.bit_test(index) {
+                self.mark_changed(index);
                 // SOUND: we've already validated that the contents of storage is valid for type T.
                 // The new lifetime is sound because we validate that all of these borrows don't
                 // overlap and their lifetimes are that of the &mut self borrow.
                 unsafe {
-                    let ptr = self.storage.unchecked_idx(index);
+                    let ptr = self.unchecked_idx(index);
                     Some(SchemaRefMut::from_ptr_schema(ptr, self.schema))
                 }
             } else {
@@ -464,13 +695,38 @@ impl UntypedComponentStore {
     ///
     /// If set, the `out` pointer, must not overlap the internal component storage.
     pub unsafe fn remove_raw(&mut self, entity: Entity, out: Option<*mut c_void>) -> bool {
+        self.remove_raw_with_hook(entity, out, self.hooks.on_remove)
+    }
+
+    /// Remove the component for an entity that has been killed, running the
+    /// [`on_kill`][ComponentHooks::on_kill] hook instead of the `on_remove` hook.
+    pub(crate) fn remove_killed(&mut self, entity: Entity) {
+        // SOUND: we don't provide an out pointer.
+        unsafe { self.remove_raw_with_hook(entity, None, self.hooks.on_kill) };
+    }
+
+    /// Implementation of [`remove_raw()`][Self::remove_raw] that runs the given hook.
+    ///
+    /// # Safety
+    ///
+    /// If set, the `out` pointer, must not overlap the internal component storage.
+    unsafe fn remove_raw_with_hook(
+        &mut self,
+        entity: Entity,
+        out: Option<*mut c_void>,
+        hook: Option<ComponentHook>,
+    ) -> bool {
         let index = entity.index() as usize;
         let size = self.schema.layout().size();
 
         if self.bitset.bit_test(index) {
-            self.bitset.bit_reset(index);
+            let chunk = self.chunk_mut(index);
+            chunk.occupied &= !(1 << (index % COMPONENT_CHUNK_SIZE));
+            let ptr = chunk.storage.unchecked_idx(index % COMPONENT_CHUNK_SIZE);
+            self.run_hook(hook, entity, ptr);
 
-            let ptr = self.storage.unchecked_idx(index);
+            self.bitset.bit_reset(index);
+            self.removed.push((entity, self.change_tick));
 
             if let Some(out) = out {
                 // SAFE: user asserts `out` is non-overlapping
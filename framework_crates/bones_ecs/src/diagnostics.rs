@@ -0,0 +1,223 @@
+//! Per-system timing diagnostics.
+//!
+//! When the [`SystemDiagnostics`] resource is present in the [`World`], the
+//! [`SimpleSystemStage`] records how long each of its systems takes to run into it. The resource
+//! is not present by default, so diagnostics have no cost unless they are enabled.
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! let mut world = World::new();
+//! world.init_resource::<SystemDiagnostics>();
+//!
+//! let mut stage = SimpleSystemStage::new(CoreStage::Update);
+//! stage.add_system((|| ()).system());
+//! stage.run(&world);
+//!
+//! let diagnostics = world.resource::<SystemDiagnostics>();
+//! for stage in diagnostics.stages() {
+//!     for system in &stage.systems {
+//!         println!("{}: {:?}", system.name, system.timing.average);
+//!     }
+//! }
+//! ```
+
+use instant::Duration;
+
+use crate::prelude::*;
+
+/// The weight of the newest sample in the rolling averages of a [`Timing`].
+///
+/// The rolling averages are exponential moving averages, so older samples have less and less
+/// weight as new samples are recorded.
+pub const DIAGNOSTICS_SMOOTHING: f64 = 0.05;
+
+/// Resource containing the time taken by each of the systems that have run in the world.
+///
+/// The timings are measured with the wall clock, so they aren't part of the game's state. The
+/// resource is [excluded from snapshots][ExcludeFromSnapshot], so it is never rolled back.
+///
+/// See the [module documentation][self].
+#[derive(HasSchema, Clone, Debug, Default)]
+#[derive_type_data(ExcludeFromSnapshot)]
+pub struct SystemDiagnostics {
+    stages: Vec<StageDiagnostics>,
+}
+
+/// The time taken by a stage and its systems. See [`SystemDiagnostics`].
+#[derive(Clone, Debug)]
+pub struct StageDiagnostics {
+    /// The ID of the stage.
+    pub id: Ulid,
+    /// The name of the stage.
+    pub name: String,
+    /// The time taken to run the whole stage, including the commands that were applied at the
+    /// end of it.
+    pub timing: Timing,
+    /// The time taken by each of the systems in the stage, in the order that they run.
+    pub systems: Vec<SystemDiagnostic>,
+}
+
+/// The time taken by a system. See [`SystemDiagnostics`].
+#[derive(Clone, Debug)]
+pub struct SystemDiagnostic {
+    /// The ID of the system.
+    pub id: SystemId,
+    /// The name of the system.
+    pub name: &'static str,
+    /// The time taken to run the system.
+    pub timing: Timing,
+}
+
+/// Timing statistics for a stage or system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    /// The time taken by the last run.
+    pub last: Duration,
+    /// The rolling average of the time taken, see [`DIAGNOSTICS_SMOOTHING`].
+    pub average: Duration,
+    /// The longest time taken since the statistics were last [reset][SystemDiagnostics::reset].
+    pub max: Duration,
+    /// The number of runs that have been recorded.
+    pub samples: u64,
+}
+
+impl Timing {
+    /// Record the time taken by a run.
+    pub fn record(&mut self, duration: Duration) {
+        self.last = duration;
+        self.average = if self.samples == 0 {
+            duration
+        } else {
+            self.average.mul_f64(1.0 - DIAGNOSTICS_SMOOTHING)
+                + duration.mul_f64(DIAGNOSTICS_SMOOTHING)
+        };
+        self.max = self.max.max(duration);
+        self.samples += 1;
+    }
+}
+
+impl SystemDiagnostics {
+    /// Get the diagnostics of every stage that has been recorded, in the order that they were
+    /// first run.
+    pub fn stages(&self) -> &[StageDiagnostics] {
+        &self.stages
+    }
+
+    /// Get the diagnostics of the stage with the given ID.
+    pub fn stage(&self, id: Ulid) -> Option<&StageDiagnostics> {
+        self.stages.iter().find(|stage| stage.id == id)
+    }
+
+    /// Record a run of a stage.
+    ///
+    /// `systems` contains the ID, name, and time taken of each system that was run, in order.
+    /// Systems that were skipped by their run conditions are left out, and keep their previous
+    /// statistics.
+    pub fn record_stage(
+        &mut self,
+        id: Ulid,
+        name: &str,
+        duration: Duration,
+        systems: impl IntoIterator<Item = (SystemId, &'static str, Duration)>,
+    ) {
+        let stage_idx = match self.stages.iter().position(|stage| stage.id == id) {
+            Some(idx) => idx,
+            None => {
+                self.stages.push(StageDiagnostics {
+                    id,
+                    name: name.into(),
+                    timing: default(),
+                    systems: Vec::new(),
+                });
+                self.stages.len() - 1
+            }
+        };
+        let stage = &mut self.stages[stage_idx];
+        stage.timing.record(duration);
+
+        // Systems usually run in the same order every time, so we check the slot after the last
+        // system we found before searching the whole stage.
+        let mut next_idx = 0;
+        for (id, name, duration) in systems {
+            let idx = if stage.systems.get(next_idx).map(|s| s.id) == Some(id) {
+                next_idx
+            } else if let Some(idx) = stage.systems.iter().position(|s| s.id == id) {
+                idx
+            } else {
+                stage.systems.insert(
+                    next_idx,
+                    SystemDiagnostic {
+                        id,
+                        name,
+                        timing: default(),
+                    },
+                );
+                next_idx
+            };
+            stage.systems[idx].timing.record(duration);
+            next_idx = idx + 1;
+        }
+    }
+
+    /// Reset the maximum times of every stage and system.
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.timing.max = Duration::ZERO;
+            for system in &mut stage.systems {
+                system.timing.max = Duration::ZERO;
+            }
+        }
+    }
+
+    /// Remove all of the recorded diagnostics.
+    pub fn clear(&mut self) {
+        self.stages.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use instant::Duration;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn records_system_timings() {
+        let mut world = World::new();
+        world.init_resource::<SystemDiagnostics>();
+
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        let fast = (|| ()).system();
+        let slow = (|| std::thread::sleep(Duration::from_millis(2))).system();
+        let skipped = (|| ()).run_if(|| false);
+        let ids = [fast.id, slow.id];
+        stage.add_system(fast);
+        stage.add_system(skipped);
+        stage.add_system(slow);
+
+        stage.run(&world);
+        stage.run(&world);
+
+        let diagnostics = world.resource::<SystemDiagnostics>();
+        let stage = diagnostics.stage(CoreStage::Update.id()).unwrap();
+        assert_eq!(stage.name, "Update");
+        assert_eq!(stage.timing.samples, 2);
+        assert_eq!(stage.systems.iter().map(|s| s.id).collect::<Vec<_>>(), ids);
+        let slow = &stage.systems[1];
+        assert_eq!(slow.timing.samples, 2);
+        assert!(slow.timing.max >= Duration::from_millis(2));
+        assert!(slow.timing.max >= slow.timing.last);
+        assert!(stage.timing.last >= slow.timing.last);
+    }
+
+    #[test]
+    fn rolling_average() {
+        let mut timing = Timing::default();
+        timing.record(Duration::from_millis(10));
+        assert_eq!(timing.average, Duration::from_millis(10));
+        timing.record(Duration::from_millis(30));
+        assert_eq!(timing.last, Duration::from_millis(30));
+        assert_eq!(timing.max, Duration::from_millis(30));
+        assert!((timing.average.as_secs_f64() - 0.011).abs() < 1e-9);
+    }
+}
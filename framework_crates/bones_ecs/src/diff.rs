@@ -0,0 +1,513 @@
+//! Structural diffs between two [`World`]s.
+//!
+//! Diffs are used to investigate de-syncs in networked sessions, by comparing the local world to
+//! the world of another peer. Components and resources are compared field-by-field using their
+//! schemas, so every difference can be pointed to by a path such as `pos.x`.
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! #[derive(HasSchema, Clone, Default)]
+//! #[repr(C)]
+//! struct Pos {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! let a = World::new();
+//! let entity = a.resource_mut::<Entities>().create();
+//! a.component_mut::<Pos>().insert(entity, Pos { x: 1.0, y: 2.0 });
+//!
+//! let b = a.clone();
+//! b.component_mut::<Pos>().get_mut(entity).unwrap().x = 3.0;
+//!
+//! let diff = a.diff(&b);
+//! assert_eq!(diff.components[0].fields[0].path, "x");
+//! println!("{diff}");
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::prelude::*;
+
+/// The result of [`World::diff()`].
+///
+/// The two compared worlds are referred to as `a`, the world that `diff()` was called on, and `b`,
+/// the world that it was passed.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldDiff {
+    /// Entities that are alive in `a` but not in `b`.
+    pub only_in_a: Vec<Entity>,
+    /// Entities that are alive in `b` but not in `a`.
+    pub only_in_b: Vec<Entity>,
+    /// The components that differ for entities that are alive in both worlds.
+    pub components: Vec<ComponentDiff>,
+    /// The resources that differ.
+    pub resources: Vec<ResourceDiff>,
+    /// The full names of the components and resources that could not be compared because they
+    /// contain opaque data without an `eq_fn`.
+    pub skipped: Vec<String>,
+}
+
+/// The differences in a component of an entity, found by [`World::diff()`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentDiff {
+    /// The full name of the component type.
+    pub component: String,
+    /// The entity that the component belongs to.
+    pub entity: Entity,
+    /// The fields of the component that differ.
+    pub fields: Vec<FieldDiff>,
+}
+
+/// The differences in a resource, found by [`World::diff()`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResourceDiff {
+    /// The full name of the resource type.
+    pub resource: String,
+    /// The fields of the resource that differ.
+    pub fields: Vec<FieldDiff>,
+}
+
+/// A value that differs between two worlds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldDiff {
+    /// The path to the value, relative to the component or resource.
+    ///
+    /// Struct fields and vec items are separated by `.`, so paths that only contain struct fields
+    /// may be used with [`FieldPath`]. Map entries are written as `[key]`. The path is empty if the
+    /// whole value differs.
+    pub path: String,
+    /// The debug representation of the value in `a`, or `None` if it doesn't exist in `a`.
+    pub a: Option<String>,
+    /// The debug representation of the value in `b`, or `None` if it doesn't exist in `b`.
+    pub b: Option<String>,
+}
+
+impl WorldDiff {
+    /// Whether or not the worlds were found to be the same.
+    ///
+    /// Note that this doesn't take [skipped][Self::skipped] types into account.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty()
+            && self.only_in_b.is_empty()
+            && self.components.is_empty()
+            && self.resources.is_empty()
+    }
+}
+
+impl Display for WorldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "The worlds are the same.")?;
+        }
+        if !self.only_in_a.is_empty() {
+            writeln!(f, "Entities only alive in a: {:?}", self.only_in_a)?;
+        }
+        if !self.only_in_b.is_empty() {
+            writeln!(f, "Entities only alive in b: {:?}", self.only_in_b)?;
+        }
+        for diff in &self.components {
+            writeln!(f, "Component `{}` of {:?}:", diff.component, diff.entity)?;
+            for field in &diff.fields {
+                writeln!(f, "    {field}")?;
+            }
+        }
+        for diff in &self.resources {
+            writeln!(f, "Resource `{}`:", diff.resource)?;
+            for field in &diff.fields {
+                writeln!(f, "    {field}")?;
+            }
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f, "Skipped: {}", self.skipped.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for FieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "<value>"
+        } else {
+            &self.path
+        };
+        let a = self.a.as_deref().unwrap_or("<missing>");
+        let b = self.b.as_deref().unwrap_or("<missing>");
+        write!(f, "{path}: {a} != {b}")
+    }
+}
+
+impl World {
+    /// Compare this world, `a`, to another world, `b`.
+    ///
+    /// All of the alive entities, component stores, and resources of the two worlds are compared,
+    /// except for the [`Entities`] resource itself. Components are only compared for entities that
+    /// are alive in both worlds. Shared resources are not compared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the components or resources are mutably borrowed.
+    pub fn diff(&self, other: &World) -> WorldDiff {
+        let mut diff = WorldDiff::default();
+
+        let entities_a = self.resource::<Entities>();
+        let entities_b = other.resource::<Entities>();
+        let alive_a = entities_a.iter().collect::<HashSet<_>>();
+        let alive_b = entities_b.iter().collect::<HashSet<_>>();
+        diff.only_in_a = entities_a.iter().filter(|x| !alive_b.contains(x)).collect();
+        diff.only_in_b = entities_b.iter().filter(|x| !alive_a.contains(x)).collect();
+
+        // Compare the stores for every component that either of the worlds has a store for.
+        let stores_a = component_stores(self);
+        let stores_b = component_stores(other);
+        let mut schemas = stores_a
+            .values()
+            .chain(stores_b.values())
+            .map(|store| store.borrow().schema())
+            .collect::<Vec<_>>();
+        schemas.sort_by_key(|schema| schema.full_name.as_str());
+        schemas.dedup_by_key(|schema| schema.id());
+        for schema in schemas {
+            let store_a = stores_a.get(&schema.id()).map(|x| x.borrow());
+            let store_b = stores_b.get(&schema.id()).map(|x| x.borrow());
+
+            let mut comparable = true;
+            for entity in entities_a.iter().filter(|x| alive_b.contains(x)) {
+                let mut fields = Vec::new();
+                comparable &= diff_optional(
+                    store_a.as_ref().and_then(|x| x.get_ref(entity)),
+                    store_b.as_ref().and_then(|x| x.get_ref(entity)),
+                    &mut fields,
+                );
+                if !comparable {
+                    break;
+                }
+                if !fields.is_empty() {
+                    diff.components.push(ComponentDiff {
+                        component: schema.full_name.to_string(),
+                        entity,
+                        fields,
+                    });
+                }
+            }
+            if !comparable {
+                diff.components
+                    .retain(|x| x.component != schema.full_name.as_str());
+                diff.skipped.push(schema.full_name.to_string());
+            }
+        }
+
+        // Compare the resources that either of the worlds has.
+        let resources_a = resource_cells(self);
+        let resources_b = resource_cells(other);
+        let mut schemas = resources_a
+            .values()
+            .chain(resources_b.values())
+            .map(|cell| cell.schema())
+            .filter(|schema| *schema != Entities::schema())
+            .collect::<Vec<_>>();
+        schemas.sort_by_key(|schema| schema.full_name.as_str());
+        schemas.dedup_by_key(|schema| schema.id());
+        for schema in schemas {
+            let resource_a = resources_a.get(&schema.id()).and_then(|x| x.borrow());
+            let resource_b = resources_b.get(&schema.id()).and_then(|x| x.borrow());
+
+            let mut fields = Vec::new();
+            if !diff_optional(
+                resource_a.as_deref().map(|x| x.as_ref()),
+                resource_b.as_deref().map(|x| x.as_ref()),
+                &mut fields,
+            ) {
+                diff.skipped.push(schema.full_name.to_string());
+            } else if !fields.is_empty() {
+                diff.resources.push(ResourceDiff {
+                    resource: schema.full_name.to_string(),
+                    fields,
+                });
+            }
+        }
+
+        diff
+    }
+}
+
+/// Get the component stores of a world by schema.
+fn component_stores(world: &World) -> HashMap<SchemaId, UntypedAtomicComponentStore> {
+    world
+        .components
+        .components
+        .read_only_view()
+        .iter()
+        .map(|(id, store)| (*id, store.clone()))
+        .collect()
+}
+
+/// Get the owned resources of a world by schema.
+fn resource_cells(world: &World) -> HashMap<SchemaId, AtomicUntypedResource> {
+    world
+        .resources
+        .untyped()
+        .owned_cells()
+        .into_iter()
+        .map(|cell| (cell.schema().id(), cell))
+        .collect()
+}
+
+/// Compare two values that may not exist, returning `false` if they cannot be compared.
+fn diff_optional(a: Option<SchemaRef>, b: Option<SchemaRef>, diffs: &mut Vec<FieldDiff>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => diff_values(&mut String::new(), a, b, diffs),
+        (None, None) => true,
+        (a, b) => {
+            diffs.push(FieldDiff {
+                path: String::new(),
+                a: a.map(|x| x.to_string()),
+                b: b.map(|x| x.to_string()),
+            });
+            true
+        }
+    }
+}
+
+/// Compare two values with the same schema, adding the differences to `diffs` and returning
+/// `false` if they contain data that cannot be compared.
+fn diff_values(path: &mut String, a: SchemaRef, b: SchemaRef, diffs: &mut Vec<FieldDiff>) -> bool {
+    let whole_value = |diffs: &mut Vec<FieldDiff>, path: &str| {
+        diffs.push(FieldDiff {
+            path: path.to_string(),
+            a: Some(a.to_string()),
+            b: Some(b.to_string()),
+        });
+    };
+
+    // Use the schema's `eq_fn` to skip values that are the same, if it has one.
+    if let Some(eq_fn) = &a.schema().eq_fn {
+        // SOUND: both of the pointers point to valid data with the same schema.
+        if unsafe { (eq_fn.get())(a.as_ptr(), b.as_ptr()) } {
+            return true;
+        }
+        // Try to narrow the difference down to specific fields, and fall back to reporting the
+        // whole value if that isn't possible.
+        let mut field_diffs = Vec::new();
+        if diff_fields(path, a, b, &mut field_diffs) && !field_diffs.is_empty() {
+            diffs.append(&mut field_diffs);
+        } else {
+            whole_value(diffs, path);
+        }
+        return true;
+    }
+
+    diff_fields(path, a, b, diffs)
+}
+
+/// Compare the inner values of two values with the same schema.
+fn diff_fields(path: &mut String, a: SchemaRef, b: SchemaRef, diffs: &mut Vec<FieldDiff>) -> bool {
+    let len = path.len();
+    let comparable = match (a.access(), b.access()) {
+        (SchemaRefAccess::Struct(a), SchemaRefAccess::Struct(b)) => diff_struct(path, a, b, diffs),
+        (SchemaRefAccess::Enum(a), SchemaRefAccess::Enum(b)) => {
+            if a.variant_idx() == b.variant_idx() {
+                diff_struct(path, a.value(), b.value(), diffs)
+            } else {
+                diffs.push(FieldDiff {
+                    path: path.clone(),
+                    a: Some(a.0.to_string()),
+                    b: Some(b.0.to_string()),
+                });
+                true
+            }
+        }
+        (SchemaRefAccess::Vec(a), SchemaRefAccess::Vec(b)) => {
+            let mut comparable = true;
+            for i in 0..a.len().max(b.len()) {
+                push_segment(path, &i.to_string());
+                match (a.get_ref(i), b.get_ref(i)) {
+                    (Some(a), Some(b)) => comparable &= diff_values(path, a, b, diffs),
+                    (a, b) => diffs.push(FieldDiff {
+                        path: path.clone(),
+                        a: a.map(|x| x.to_string()),
+                        b: b.map(|x| x.to_string()),
+                    }),
+                }
+                path.truncate(len);
+            }
+            comparable
+        }
+        (SchemaRefAccess::Map(a), SchemaRefAccess::Map(b)) => {
+            let mut map_diffs = Vec::new();
+            let mut comparable = true;
+            for (key, value_a) in a.iter() {
+                path.push_str(&format!("[{key}]"));
+                match b.get_ref(key) {
+                    Some(value_b) => {
+                        comparable &= diff_values(path, value_a, value_b, &mut map_diffs)
+                    }
+                    None => map_diffs.push(FieldDiff {
+                        path: path.clone(),
+                        a: Some(value_a.to_string()),
+                        b: None,
+                    }),
+                }
+                path.truncate(len);
+            }
+            for (key, value_b) in b.iter() {
+                if a.get_ref(key).is_none() {
+                    map_diffs.push(FieldDiff {
+                        path: format!("{path}[{key}]"),
+                        a: None,
+                        b: Some(value_b.to_string()),
+                    });
+                }
+            }
+            // The iteration order of maps is not deterministic, so sort the differences.
+            map_diffs.sort_by(|x, y| x.path.cmp(&y.path));
+            diffs.append(&mut map_diffs);
+            comparable
+        }
+        (SchemaRefAccess::Option(a), SchemaRefAccess::Option(b)) => match (a.value(), b.value()) {
+            (Some(a), Some(b)) => diff_values(path, a, b, diffs),
+            (None, None) => true,
+            _ => {
+                diffs.push(FieldDiff {
+                    path: path.clone(),
+                    a: Some(a.0.to_string()),
+                    b: Some(b.0.to_string()),
+                });
+                true
+            }
+        },
+        // Primitives that can be compared have an `eq_fn`, which is handled by `diff_values()`.
+        _ => false,
+    };
+    path.truncate(len);
+    comparable
+}
+
+/// Compare the fields of two structs with the same schema.
+fn diff_struct(
+    path: &mut String,
+    a: StructRefAccess,
+    b: StructRefAccess,
+    diffs: &mut Vec<FieldDiff>,
+) -> bool {
+    let len = path.len();
+    let mut comparable = true;
+    for (i, (a, b)) in a.fields().zip(b.fields()).enumerate() {
+        match a.name {
+            Some(name) => push_segment(path, name),
+            None => push_segment(path, &i.to_string()),
+        }
+        comparable &= diff_values(path, a.value, b.value, diffs);
+        path.truncate(len);
+    }
+    comparable
+}
+
+/// Add a `.` separated segment to a field path.
+fn push_segment(path: &mut String, segment: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(segment);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Pos {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Inventory {
+        items: SVec<u32>,
+        counts: SMap<String, u32>,
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Opaque(i32);
+
+    fn field(path: &str, a: Option<&str>, b: Option<&str>) -> FieldDiff {
+        FieldDiff {
+            path: path.into(),
+            a: a.map(String::from),
+            b: b.map(String::from),
+        }
+    }
+
+    #[test]
+    fn same_worlds_have_no_diff() {
+        let world = World::new();
+        let entity = world.resource_mut::<Entities>().create();
+        world
+            .component_mut::<Pos>()
+            .insert(entity, Pos { x: 1, y: 2 });
+        world.insert_resource(Inventory::default());
+
+        let diff = world.diff(&world.clone());
+        assert!(diff.is_empty(), "{diff}");
+    }
+
+    #[test]
+    fn diff_worlds() {
+        let a = World::new();
+        let [e1, e2] = {
+            let mut entities = a.resource_mut::<Entities>();
+            [entities.create(), entities.create()]
+        };
+        a.component_mut::<Pos>().insert(e1, Pos { x: 1, y: 2 });
+        a.component_mut::<Opaque>().insert(e1, Opaque(1));
+        let mut inventory = Inventory::default();
+        inventory.items.push(1);
+        inventory.items.push(2);
+        inventory.counts.insert("gold".into(), 3);
+        a.insert_resource(inventory);
+
+        let b = a.clone();
+        b.resource_mut::<Entities>().kill(e2);
+        b.component_mut::<Pos>().get_mut(e1).unwrap().y = 5;
+        b.component_mut::<Opaque>().insert(e1, Opaque(2));
+        {
+            let mut inventory = b.resource_mut::<Inventory>();
+            inventory.items.push(3);
+            inventory.counts.insert("gold".into(), 4);
+            inventory.counts.insert("gems".into(), 1);
+        }
+
+        let diff = a.diff(&b);
+        assert_eq!(diff.only_in_a, [e2]);
+        assert!(diff.only_in_b.is_empty());
+
+        assert_eq!(diff.components.len(), 1);
+        assert_eq!(diff.components[0].entity, e1);
+        assert_eq!(
+            diff.components[0].fields,
+            [field("y", Some("2"), Some("5"))]
+        );
+
+        assert_eq!(diff.resources.len(), 1);
+        assert_eq!(
+            diff.resources[0].fields,
+            [
+                field("items.2", None, Some("3")),
+                field("counts[\"gems\"]", None, Some("1")),
+                field("counts[\"gold\"]", Some("3"), Some("4")),
+            ]
+        );
+
+        assert_eq!(diff.skipped, [Opaque::schema().full_name.to_string()]);
+    }
+}
@@ -1,6 +1,13 @@
 //! [`Entity`] implementation, storage, and interation.
 
-use std::{marker::PhantomData, rc::Rc};
+use std::{
+    marker::PhantomData,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use crate::prelude::*;
 
@@ -55,8 +62,11 @@ impl Default for Entity {
 ///
 /// It also holds a list of entities that were recently killed, which allows to remove components of
 /// deleted entities at the end of a game frame.
+///
+/// The storage for entities grows on demand as new entities are created. Use
+/// [`Entities::with_capacity()`] to pre-allocate room for a known number of entities.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, HasSchema)]
+#[derive(HasSchema)]
 pub struct Entities {
     /// Bitset containing all living entities
     alive: BitSetVec,
@@ -66,6 +76,20 @@ pub struct Entities {
     /// helps to know if we should directly append after next_id or if we should look through the
     /// bitset.
     has_deleted: bool,
+    /// Hands out the indices of [reserved][Entities::reserve] entities, starting at `next_id`.
+    reserver: EntityReserver,
+}
+impl Clone for Entities {
+    fn clone(&self) -> Self {
+        Self {
+            alive: self.alive.clone(),
+            generation: self.generation.clone(),
+            killed: self.killed.clone(),
+            next_id: self.next_id,
+            has_deleted: self.has_deleted,
+            reserver: EntityReserver::new(self.reserver.next_index()),
+        }
+    }
 }
 impl std::fmt::Debug for Entities {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -75,13 +99,7 @@ impl std::fmt::Debug for Entities {
 
 impl Default for Entities {
     fn default() -> Self {
-        Self {
-            alive: create_bitset(),
-            generation: vec![0u32; BITSET_SIZE],
-            killed: vec![],
-            next_id: 0,
-            has_deleted: false,
-        }
+        Self::with_capacity(0)
     }
 }
 
@@ -169,6 +187,48 @@ where
     OptionalQueryItemMut(component_ref, PhantomData)
 }
 
+/// Filter used as a [`QueryItem`] to only iterate over entities that have a component, without
+/// retrieving the component.
+///
+/// See [`With`] helper func for constructing `WithQueryItem` and usage.
+pub struct WithQueryItem<'a, T: HasSchema, S>(pub &'a S, pub PhantomData<&'a T>);
+
+/// Filter used as a [`QueryItem`] to only iterate over entities that don't have a component.
+///
+/// See [`Without`] helper func for constructing `WithoutQueryItem` and usage.
+pub struct WithoutQueryItem<'a, T: HasSchema, S>(pub &'a S, pub PhantomData<&'a T>);
+
+/// Helper func to construct a [`WithQueryItem`] wrapping a [`Comp`] or [`CompMut`].
+/// Entities iterated over will be filtered by the component, but the component is not retrieved,
+/// and `()` is returned for this `QueryItem` instead.
+///
+/// This example iterates over the `compA` of entities that also have a `compB`:
+///
+/// `entities.iter_with((&mut compA, With(&compB)))`
+#[allow(non_snake_case)]
+pub fn With<'a, T: HasSchema, C, S>(component_ref: &'a S) -> WithQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    WithQueryItem(component_ref, PhantomData)
+}
+
+/// Helper func to construct a [`WithoutQueryItem`] wrapping a [`Comp`] or [`CompMut`].
+/// Entities that have the component will be skipped, and `()` is returned for this `QueryItem`.
+///
+/// This example iterates over the `compA` of entities that don't have a `compB`:
+///
+/// `entities.iter_with((&mut compA, Without(&compB)))`
+#[allow(non_snake_case)]
+pub fn Without<'a, T: HasSchema, C, S>(component_ref: &'a S) -> WithoutQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    WithoutQueryItem(component_ref, PhantomData)
+}
+
 impl<'a> QueryItem for &'a Ref<'a, UntypedComponentStore> {
     type Iter = UntypedComponentBitsetIterator<'a>;
 
@@ -298,6 +358,97 @@ where
     }
 }
 
+/// Iterator for filter query items, such as [`With`] and [`Without`], that returns `()` for every
+/// entity in the bitset.
+pub struct QueryFilterIter {
+    current_id: usize,
+    bitset: Rc<BitSetVec>,
+}
+
+impl QueryFilterIter {
+    /// Create an iterator over the given bitset.
+    pub fn new(bitset: Rc<BitSetVec>) -> Self {
+        Self {
+            current_id: 0,
+            bitset,
+        }
+    }
+}
+
+impl Iterator for QueryFilterIter {
+    type Item = ();
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.bitset.bit_len();
+        while self.current_id < len {
+            let id = self.current_id;
+            self.current_id += 1;
+            if self.bitset.bit_test(id) {
+                return Some(());
+            }
+        }
+        None
+    }
+}
+
+/// Return `()` if there is exactly one entity in the bitset.
+fn get_single_filter(bitset: Rc<BitSetVec>) -> Result<(), QuerySingleError> {
+    let mut iter = QueryFilterIter::new(bitset);
+    match (iter.next(), iter.next()) {
+        (None, _) => Err(QuerySingleError::NoEntities),
+        (Some(()), None) => Ok(()),
+        (Some(()), Some(())) => Err(QuerySingleError::MultipleEntities),
+    }
+}
+
+/// Filter entities by component with syntax: `With(&Comp<T>)` / `With(&CompMut<T>)`.
+impl<'a, T: HasSchema, S, C> QueryItem for WithQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type Iter = QueryFilterIter;
+
+    fn apply_bitset(&self, bitset: &mut BitSetVec) {
+        bitset.bit_and(self.0.bitset());
+    }
+
+    fn get_single_with_bitset(
+        self,
+        bitset: Rc<BitSetVec>,
+    ) -> Result<<Self::Iter as Iterator>::Item, QuerySingleError> {
+        get_single_filter(bitset)
+    }
+
+    fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
+        QueryFilterIter::new(bitset)
+    }
+}
+
+/// Exclude entities by component with syntax: `Without(&Comp<T>)` / `Without(&CompMut<T>)`.
+impl<'a, T: HasSchema, S, C> QueryItem for WithoutQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type Iter = QueryFilterIter;
+
+    fn apply_bitset(&self, bitset: &mut BitSetVec) {
+        bitset.bit_andnot(self.0.bitset());
+    }
+
+    fn get_single_with_bitset(
+        self,
+        bitset: Rc<BitSetVec>,
+    ) -> Result<<Self::Iter as Iterator>::Item, QuerySingleError> {
+        get_single_filter(bitset)
+    }
+
+    fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
+        QueryFilterIter::new(bitset)
+    }
+}
+
 #[doc(hidden)]
 pub struct MultiQueryIter<T> {
     data: T,
@@ -463,6 +614,26 @@ impl<'a, I: Iterator> Iterator for EntitiesIterWith<'a, I> {
 }
 
 impl Entities {
+    /// The maximum number of entities that may be alive at the same time.
+    ///
+    /// Entity indices must fit in a `u32`, and `u32::MAX` is reserved for the invalid entity.
+    pub const MAX_ENTITIES: usize = u32::MAX as usize;
+
+    /// Create an empty [`Entities`] with room for at least `capacity` entities before it needs to
+    /// re-allocate.
+    ///
+    /// Storage will still grow as needed if more entities than `capacity` are created.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            alive: BitSetVec::with_capacity(capacity),
+            generation: Vec::with_capacity(capacity),
+            killed: vec![],
+            next_id: 0,
+            has_deleted: false,
+            reserver: EntityReserver::new(0),
+        }
+    }
+
     /// Get a single entity and components in the given query if there is exactly one entity
     /// matching the query.
     ///
@@ -610,6 +781,32 @@ impl Entities {
     ///     }
     /// }
     /// ```
+    ///
+    /// Entities may also be filtered by components without retrieving them, using `With(&comp)` to
+    /// only iterate over entities that have the component, or `Without(&comp)` to only iterate over
+    /// entities that don't have it. `()` is returned for these.
+    ///
+    /// # [`With`] and [`Without`] Example
+    ///
+    /// ```
+    /// # use bones_ecs::prelude::*;
+    /// # #[derive(HasSchema, Clone, Default)]
+    /// # #[repr(C)]
+    /// # struct Pos { x: f32, y: f32 };
+    /// # #[derive(HasSchema, Clone, Default)]
+    /// # #[repr(C)]
+    /// # struct Player;
+    /// # #[derive(HasSchema, Clone, Default)]
+    /// # #[repr(C)]
+    /// # struct Frozen;
+    ///
+    /// fn my_system(entities: Res<Entities>, mut pos: CompMut<Pos>, players: Comp<Player>, frozen: Comp<Frozen>) {
+    ///     // Move all of the players that aren't frozen
+    ///     for (entity, (pos, (), ())) in entities.iter_with((&mut pos, With(&players), Without(&frozen))) {
+    ///         pos.x += 1.0;
+    ///     }
+    /// }
+    /// ```
     pub fn iter_with<Q: QueryItem>(
         &self,
         query: Q,
@@ -620,7 +817,9 @@ impl Entities {
 
         EntitiesIterWith {
             current_id: 0,
-            next_id: self.next_id,
+            // The bitset may have been truncated by the query, and there can't be any matches past
+            // it's end.
+            next_id: self.next_id.min(bitset.bit_len()),
             bitset: bitset.clone(),
             generations: &self.generation,
             query: query.iter_with_bitset(bitset),
@@ -632,38 +831,36 @@ impl Entities {
     /// This function will not reuse the index of an entity that is still in the killed entities.
     pub fn create(&mut self) -> Entity {
         if !self.has_deleted {
-            let i = self.next_id;
-            if i >= BITSET_SIZE {
+            // Take the index from the reserver, so that it can't be handed out again.
+            let i = self.reserver.next_index.fetch_add(1, Ordering::Relaxed);
+            if i >= Self::MAX_ENTITIES {
                 panic!("Exceeded maximum amount of concurrent entities.");
             }
-            self.next_id += 1;
-            self.alive.bit_set(i);
+            self.flush_reserved();
             Entity::new(i as u32, self.generation[i])
         } else {
+            self.flush_reserved();
+
             // Skip over sections where all bits are enabled
             let mut section = 0;
-            while self.alive[section].bit_all() {
+            while section < self.alive.len() && self.alive[section].bit_all() {
                 section += 1;
             }
 
             // Start at the beginning of the first section with at least 1 unset bit
-            let mut i = section * (32 * 8);
+            let mut i = section * BITSET_SECTOR_SIZE;
             // Find the first bit that is not used by an alive or dead entity
-            while i < BITSET_SIZE
-                && (self.alive.bit_test(i) || self.killed.iter().any(|e| e.index() == i as u32))
-            {
+            while self.alive.bit_test(i) || self.killed.iter().any(|e| e.index() == i as u32) {
                 i += 1;
             }
-            if i >= BITSET_SIZE {
-                panic!("Exceeded maximum amount of concurrent entities.");
+            if i >= self.next_id {
+                // There are no free indices left, so append the entity like when none are deleted.
+                self.has_deleted = false;
+                return self.create();
             }
 
             // Create the entity
             self.alive.bit_set(i);
-            if i >= self.next_id {
-                self.next_id = i + 1;
-                self.has_deleted = false;
-            }
             let entity = Entity::new(i as u32, self.generation[i]);
 
             // Make sure we never return the invalid entity.
@@ -675,6 +872,36 @@ impl Entities {
         }
     }
 
+    /// Reserve an `Entity` without mutably borrowing the entities, and return it.
+    ///
+    /// The entity isn't alive until the reserved entities are created with
+    /// [`flush_reserved()`][Self::flush_reserved], which also happens the next time an entity is
+    /// [created][Self::create]. Use [`reserver()`][Self::reserver] to reserve entities without
+    /// borrowing the [`Entities`] at all.
+    pub fn reserve(&self) -> Entity {
+        self.reserver.reserve()
+    }
+
+    /// Get an [`EntityReserver`] that reserves entities in these [`Entities`].
+    ///
+    /// This is used by [`Commands::spawn()`] to get the spawned entity before the spawn command is
+    /// applied, even if another system is mutably borrowing the [`Entities`].
+    pub fn reserver(&self) -> EntityReserver {
+        self.reserver.clone()
+    }
+
+    /// Create the entities that have been [reserved][Self::reserve].
+    pub fn flush_reserved(&mut self) {
+        let end = self.reserver.next_index();
+        for i in self.next_id..end {
+            self.alive.bit_set(i);
+        }
+        self.next_id = self.next_id.max(end);
+        if self.generation.len() < self.next_id {
+            self.generation.resize(self.next_id, 0);
+        }
+    }
+
     /// Checks if the `Entity` is still alive.
     ///
     /// Returns true if it is alive. Returns false if it has been killed.
@@ -736,6 +963,53 @@ impl Entities {
     }
 }
 
+/// Reserves entities in an [`Entities`], without needing to borrow it.
+///
+/// Get one with [`Entities::reserver()`]. Like with [`Entities::reserve()`], the reserved entities
+/// aren't alive until the [`Entities`] are [flushed][Entities::flush_reserved].
+#[derive(Clone, Debug)]
+pub struct EntityReserver {
+    /// The index of the next entity to reserve, shared with the [`Entities`] it belongs to.
+    next_index: Arc<AtomicUsize>,
+}
+
+impl EntityReserver {
+    fn new(next_index: usize) -> Self {
+        Self {
+            next_index: Arc::new(AtomicUsize::new(next_index)),
+        }
+    }
+
+    fn next_index(&self) -> usize {
+        self.next_index.load(Ordering::Relaxed)
+    }
+
+    /// Reserve an `Entity` and return it.
+    pub fn reserve(&self) -> Entity {
+        let i = self.next_index.fetch_add(1, Ordering::Relaxed);
+        if i >= Entities::MAX_ENTITIES {
+            panic!("Exceeded maximum amount of concurrent entities.");
+        }
+        // Entities are always created in order after the last created index, so this index has
+        // never been used and is still on its first generation.
+        Entity::new(i as u32, 0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EntityReserver {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.next_index().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EntityReserver {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        usize::deserialize(deserializer).map(Self::new)
+    }
+}
+
 /// Iterator over entities using the provided bitset.
 pub struct EntityIterator<'a> {
     pub(crate) current_id: usize,
@@ -783,6 +1057,25 @@ mod tests {
     #[repr(C)]
     struct B(u32);
 
+    #[test]
+    fn reserve_entities() {
+        let mut entities = Entities::default();
+        let a = entities.create();
+        let b = entities.reserve();
+        let c = entities.reserve();
+        assert_ne!(b, c);
+        assert!(!entities.is_alive(b));
+
+        // Reserved entities are created before any other entity.
+        let d = entities.create();
+        assert!([a, b, c, d].iter().all(|&e| entities.is_alive(e)));
+        assert_eq!(entities.iter().collect::<Vec<_>>(), [a, b, c, d]);
+
+        let e = entities.reserve();
+        entities.flush_reserved();
+        assert!(entities.is_alive(e));
+    }
+
     #[test]
     fn entities__create_kill() {
         let mut entities = Entities::default();
@@ -867,23 +1160,35 @@ mod tests {
         entities.create();
     }
 
+    /// Make sure that we can create more entities than the initial capacity, and more than the
+    /// old 16 bit limit.
     #[test]
-    #[should_panic(expected = "Exceeded maximum amount")]
-    fn entities__force_max_entity_panic() {
-        let mut entities = Entities::default();
-        for _ in 0..(BITSET_SIZE + 1) {
-            entities.create();
-        }
+    #[cfg_attr(miri, ignore)]
+    fn entities__grow_past_capacity() {
+        let mut entities = Entities::with_capacity(16);
+        let e = (0..70_000).fold(default(), |_, _| entities.create());
+        assert_eq!(e.index(), 69_999);
+        assert!(entities.is_alive(e));
+
+        entities.kill(e);
+        let e2 = entities.create();
+        let e3 = entities.create();
+        assert_eq!(e2.index(), 70_000);
+        assert_eq!(e3.index(), 70_001);
+        assert!(!entities.is_alive(e));
+        assert!(entities.is_alive(e2));
+        assert_eq!(entities.iter().count(), 70_001);
     }
 
     #[test]
-    #[should_panic(expected = "Exceeded maximum amount")]
-    fn entities__force_max_entity_panic2() {
+    fn entities__clone_is_proportional_to_entity_count() {
         let mut entities = Entities::default();
-        let e = (0..BITSET_SIZE).fold(default(), |_, _| entities.create());
-        entities.kill(e);
-        entities.create();
-        entities.create();
+        for _ in 0..300 {
+            entities.create();
+        }
+        let snapshot = entities.clone();
+        assert_eq!(snapshot.bitset().len(), 2);
+        assert_eq!(snapshot.iter().count(), 300);
     }
 
     #[test]
@@ -1056,4 +1361,48 @@ mod tests {
             entities.kill(e);
         }
     }
+
+    #[test]
+    fn entities__iter_with__filters() {
+        let mut entities = Entities::default();
+        let mut store_a = ComponentStore::<A>::default();
+        let mut store_b = ComponentStore::<B>::default();
+
+        // Entity 0 has A and B, 1 has A, 2 has B, and 3 has neither.
+        let e: Vec<Entity> = (0..4).map(|_| entities.create()).collect();
+        store_a.insert(e[0], A(0));
+        store_a.insert(e[1], A(1));
+        store_b.insert(e[0], B(0));
+        store_b.insert(e[2], B(2));
+
+        let a = Ref::new(&store_a);
+        let b = Ref::new(&store_b);
+
+        let with = entities
+            .iter_with((&a, With(&b)))
+            .map(|(entity, (a, ()))| (entity, a.0))
+            .collect::<Vec<_>>();
+        assert_eq!(with, [(e[0], 0)]);
+
+        let without = entities
+            .iter_with((&a, Without(&b)))
+            .map(|(entity, (a, ()))| (entity, a.0))
+            .collect::<Vec<_>>();
+        assert_eq!(without, [(e[1], 1)]);
+
+        let neither = entities
+            .iter_with((Without(&a), Without(&b)))
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        assert_eq!(neither, [e[3]]);
+
+        assert_eq!(
+            entities.get_single_with(With(&b)),
+            Err(QuerySingleError::MultipleEntities)
+        );
+        assert_eq!(
+            entities.get_single_with((With(&a), Without(&b))),
+            Ok((e[1], ((), ())))
+        );
+    }
 }
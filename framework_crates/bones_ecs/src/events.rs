@@ -0,0 +1,470 @@
+//! Typed event channels.
+//!
+//! Events are sent with an [`EventWriter`] and received with an [`EventReader`]. They are stored in
+//! an [`Events`] resource, so, like everything else in the [`World`], they are captured by world
+//! snapshots and behave correctly when a snapshot is restored.
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! #[derive(HasSchema, Clone, Default)]
+//! #[repr(C)]
+//! struct Explosion {
+//!     strength: f32,
+//! }
+//!
+//! fn explode(mut explosions: EventWriter<Explosion>) {
+//!     explosions.send(Explosion { strength: 5.0 });
+//! }
+//!
+//! fn shake_screen(mut explosions: EventReader<Explosion>) {
+//!     for explosion in explosions.iter() {
+//!         println!("Boom: {}", explosion.strength);
+//!     }
+//! }
+//! ```
+//!
+//! Events are double-buffered: an event sent during one [`SystemStages::run`] can be read until the
+//! end of the next one, after which it is dropped. Each [`EventReader`] keeps its own cursor, so
+//! every system sees every event exactly once, no matter which order the systems run in.
+
+use std::{
+    alloc::Layout,
+    any::{type_name, TypeId},
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+use bones_schema::raw_fns::*;
+
+use crate::prelude::*;
+
+/// A resource storing the events of type `T`.
+///
+/// Usually you will use [`EventWriter`] and [`EventReader`] to access events instead of using this
+/// resource directly.
+pub struct Events<T: HasSchema> {
+    /// The events sent during the previous update.
+    previous: EventBuffer<T>,
+    /// The events sent during the current update.
+    current: EventBuffer<T>,
+    /// The total number of events that have ever been sent.
+    event_count: usize,
+    /// The number of events that each [`EventReader`] has read, by the system it belongs to.
+    cursors: Mutex<HashMap<SystemId, usize>>,
+}
+
+struct EventBuffer<T: HasSchema> {
+    /// The number of events sent before the first event in this buffer.
+    start: usize,
+    events: SVec<T>,
+}
+
+impl<T: HasSchema> Default for EventBuffer<T> {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            events: SVec::new(),
+        }
+    }
+}
+
+impl<T: HasSchema> Clone for EventBuffer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            start: self.start,
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<T: HasSchema> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: default(),
+            current: default(),
+            event_count: 0,
+            cursors: default(),
+        }
+    }
+}
+
+impl<T: HasSchema> Clone for Events<T> {
+    fn clone(&self) -> Self {
+        Self {
+            previous: self.previous.clone(),
+            current: self.current.clone(),
+            event_count: self.event_count,
+            cursors: Mutex::new(self.cursors.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl<T: HasSchema + std::fmt::Debug> std::fmt::Debug for Events<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("previous", &self.previous.events)
+            .field("current", &self.current.events)
+            .field("event_count", &self.event_count)
+            .finish_non_exhaustive()
+    }
+}
+
+// SAFE: We return a valid schema.
+unsafe impl<T: HasSchema> HasSchema for Events<T> {
+    fn schema() -> &'static Schema {
+        static S: OnceLock<RwLock<HashMap<TypeId, &'static Schema>>> = OnceLock::new();
+        let schema = {
+            S.get_or_init(default)
+                .read()
+                .unwrap()
+                .get(&TypeId::of::<Self>())
+                .copied()
+        };
+        schema.unwrap_or_else(|| {
+            let layout = Layout::new::<Self>();
+            let schema = SCHEMA_REGISTRY.register(SchemaData {
+                name: type_name::<Self>().into(),
+                full_name: format!("{}::{}", module_path!(), type_name::<Self>()).into(),
+                kind: SchemaKind::Primitive(Primitive::Opaque {
+                    size: layout.size(),
+                    align: layout.align(),
+                }),
+                type_id: Some(TypeId::of::<Self>()),
+                clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
+                drop_fn: Some(<Self as RawDrop>::raw_drop_cb()),
+                default_fn: Some(<Self as RawDefault>::raw_default_cb()),
+                hash_fn: None,
+                eq_fn: None,
+                type_data: default(),
+            });
+
+            S.get_or_init(default)
+                .write()
+                .unwrap()
+                .insert(TypeId::of::<Self>(), schema);
+
+            schema
+        })
+    }
+}
+
+impl<T: HasSchema> Events<T> {
+    /// Initialize the [`Events`] resource in the world, if it doesn't exist yet, and register it
+    /// so that it is updated at the end of every [`SystemStages::run`].
+    ///
+    /// This is done automatically by [`EventWriter`] and [`EventReader`].
+    pub fn init(world: &World) -> AtomicResource<Self> {
+        let cell = world.resources.get_cell::<Self>();
+        if unlikely(cell.borrow().is_none()) {
+            cell.init(world);
+            world
+                .resources
+                .get_cell::<EventRegistry>()
+                .init_borrow_mut(world)
+                .register::<T>();
+        }
+        cell
+    }
+
+    /// Send an event.
+    pub fn send(&mut self, event: T) {
+        self.current.events.push(event);
+        self.event_count += 1;
+    }
+
+    /// Send a batch of events.
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    /// Get the number of events that are currently stored.
+    pub fn len(&self) -> usize {
+        self.previous.events.len() + self.current.events.len()
+    }
+
+    /// Returns `true` if there are no events currently stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over all of the events currently stored, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.previous
+            .events
+            .iter()
+            .chain(self.current.events.iter())
+    }
+
+    /// Drop all events, including the ones that have not been read yet.
+    pub fn clear(&mut self) {
+        self.previous.events.clear();
+        self.current.events.clear();
+        self.previous.start = self.event_count;
+        self.current.start = self.event_count;
+    }
+
+    /// Swap the event buffers, dropping the events from the previous update.
+    ///
+    /// This is called automatically at the end of every [`SystemStages::run`] for every event type
+    /// that has been initialized with [`Events::init()`].
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.events.clear();
+        self.current.start = self.event_count;
+
+        // Cursors that are behind the oldest remaining event don't need to be stored anymore,
+        // since a reader without a cursor starts reading at the oldest event anyway. This makes
+        // sure that readers for systems that don't run anymore are eventually forgotten.
+        let oldest = self.previous.start;
+        self.cursors
+            .get_mut()
+            .unwrap()
+            .retain(|_, cursor| *cursor > oldest);
+    }
+
+    /// Iterate over the events that were sent after the first `cursor` events.
+    fn iter_since(&self, cursor: usize) -> impl Iterator<Item = &T> + '_ {
+        let previous_skip = cursor.saturating_sub(self.previous.start);
+        let current_skip = cursor.saturating_sub(self.current.start);
+        self.previous
+            .events
+            .iter()
+            .skip(previous_skip)
+            .chain(self.current.events.iter().skip(current_skip))
+    }
+
+    /// Get the cursor stored for the given system, if any.
+    fn cursor(&self, id: SystemId) -> Option<usize> {
+        self.cursors.lock().unwrap().get(&id).copied()
+    }
+
+    /// Store the cursor for the given system.
+    fn set_cursor(&self, id: SystemId, cursor: usize) {
+        self.cursors.lock().unwrap().insert(id, cursor);
+    }
+}
+
+/// Resource containing the update functions of every [`Events`] type that has been initialized in
+/// the world.
+#[derive(HasSchema, Clone, Default)]
+pub struct EventRegistry {
+    registered: HashSet<SchemaId>,
+    updaters: Vec<fn(&World)>,
+}
+
+impl EventRegistry {
+    /// Register an event type, so that it is updated by [`EventRegistry::update_events()`].
+    pub fn register<T: HasSchema>(&mut self) {
+        if self.registered.insert(Events::<T>::schema().id()) {
+            self.updaters.push(|world| {
+                if let Some(mut events) = world.resources.get_mut::<Events<T>>() {
+                    events.update();
+                }
+            });
+        }
+    }
+
+    /// Update all of the registered [`Events`] in the world.
+    ///
+    /// This is called automatically at the end of every [`SystemStages::run`].
+    pub fn update_events(world: &World) {
+        let updaters = match world.resources.get::<EventRegistry>() {
+            Some(registry) => registry.updaters.clone(),
+            None => return,
+        };
+        for update in updaters {
+            update(world);
+        }
+    }
+}
+
+/// [`SystemParam`] for sending events of type `T`.
+pub struct EventWriter<'a, T: HasSchema> {
+    events: RefMut<'a, Events<T>>,
+}
+
+impl<'a, T: HasSchema> EventWriter<'a, T> {
+    /// Send an event.
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    /// Send a batch of events.
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.events.send_batch(events);
+    }
+}
+
+impl<'a, T: HasSchema> SystemParam for EventWriter<'a, T> {
+    type State = AtomicResource<Events<T>>;
+    type Param<'s> = EventWriter<'s, T>;
+
+    fn get_state(world: &World) -> Self::State {
+        Events::<T>::init(world)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_write(Events::<T>::schema());
+        access.add_resource_write(EventRegistry::schema());
+    }
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        EventWriter {
+            events: state.borrow_mut().unwrap(),
+        }
+    }
+}
+
+/// [`SystemParam`] for receiving events of type `T`.
+///
+/// Each system has its own cursor into the events, so every system will see every event once. The
+/// cursor is stored in the [`Events`] resource, so it is captured in world snapshots along with the
+/// events themselves.
+pub struct EventReader<'a, T: HasSchema> {
+    events: Ref<'a, Events<T>>,
+    cursor: usize,
+    system: Option<SystemId>,
+}
+
+impl<'a, T: HasSchema> EventReader<'a, T> {
+    /// Iterate over the events that this reader hasn't read yet, marking them as read.
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> + '_ {
+        let cursor = std::mem::replace(&mut self.cursor, self.events.event_count);
+        self.events.iter_since(cursor)
+    }
+
+    /// Get the number of events that this reader hasn't read yet.
+    pub fn len(&self) -> usize {
+        self.events.iter_since(self.cursor).count()
+    }
+
+    /// Returns `true` if there are no events that this reader hasn't read yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Mark all of the events as read, without iterating over them.
+    pub fn clear(&mut self) {
+        self.cursor = self.events.event_count;
+    }
+}
+
+impl<'a, T: HasSchema> Drop for EventReader<'a, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.system {
+            self.events.set_cursor(id, self.cursor);
+        }
+    }
+}
+
+impl<'a, T: HasSchema> SystemParam for EventReader<'a, T> {
+    type State = (AtomicResource<Events<T>>, Option<SystemId>);
+    type Param<'s> = EventReader<'s, T>;
+
+    fn get_state(world: &World) -> Self::State {
+        (Events::<T>::init(world), None)
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        (Events::<T>::init(world), Some(meta.id))
+    }
+
+    fn access(access: &mut SystemAccess) {
+        // The events resource may be initialized when the state is created, and the reader's
+        // cursor is written back to it, so readers need write access.
+        access.add_resource_write(Events::<T>::schema());
+        access.add_resource_write(EventRegistry::schema());
+    }
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        let (cell, system) = state;
+        let events = cell.borrow().unwrap();
+        // Readers without a cursor start at the oldest event that is still stored.
+        let cursor = system
+            .and_then(|id| events.cursor(id))
+            .unwrap_or(events.previous.start);
+        EventReader {
+            events,
+            cursor,
+            system: *system,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq, Eq)]
+    #[repr(C)]
+    struct Ping(u32);
+
+    fn read_pings(mut reader: EventReader<Ping>) -> Vec<u32> {
+        reader.iter().map(|p| p.0).collect()
+    }
+
+    #[test]
+    fn readers_have_independent_cursors() {
+        let world = World::new();
+        let mut send = (|mut writer: EventWriter<Ping>| writer.send(Ping(1))).system();
+        let mut reader_a = read_pings.system();
+        let mut reader_b = read_pings.system();
+
+        send.run(&world, ());
+        assert_eq!(reader_a.run(&world, ()), vec![1]);
+        assert_eq!(reader_a.run(&world, ()), vec![]);
+
+        send.run(&world, ());
+        assert_eq!(reader_a.run(&world, ()), vec![1]);
+        assert_eq!(reader_b.run(&world, ()), vec![1, 1]);
+        assert_eq!(reader_b.run(&world, ()), vec![]);
+    }
+
+    #[test]
+    fn events_are_double_buffered() {
+        let mut world = World::new();
+        let mut stages = SystemStages::default();
+        let mut reader = read_pings.system();
+
+        world.run_system(
+            |mut writer: EventWriter<Ping>| writer.send_batch([Ping(1), Ping(2)]),
+            (),
+        );
+
+        // Events survive the end of the frame they were sent in...
+        stages.run(&mut world);
+        assert_eq!(world.resource::<Events<Ping>>().len(), 2);
+
+        // ...but not the end of the one after that.
+        stages.run(&mut world);
+        assert!(world.resource::<Events<Ping>>().is_empty());
+        assert_eq!(reader.run(&world, ()), vec![]);
+    }
+
+    #[test]
+    fn snapshot_restores_events_and_cursors() {
+        let mut world = World::new();
+        let mut send = (|mut writer: EventWriter<Ping>, mut n: ResMutInit<u32>| {
+            *n += 1;
+            writer.send(Ping(*n));
+        })
+        .system();
+        let mut reader = read_pings.system();
+
+        send.run(&world, ());
+        assert_eq!(reader.run(&world, ()), vec![1]);
+        send.run(&world, ());
+        let snapshot = world.clone();
+
+        assert_eq!(reader.run(&world, ()), vec![2]);
+        send.run(&world, ());
+        assert_eq!(reader.run(&world, ()), vec![3]);
+
+        // Re-simulating after a rollback produces exactly the same events.
+        world.load_snapshot(snapshot);
+        assert_eq!(reader.run(&world, ()), vec![2]);
+        send.run(&world, ());
+        assert_eq!(reader.run(&world, ()), vec![3]);
+    }
+}
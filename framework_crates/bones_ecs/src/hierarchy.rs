@@ -0,0 +1,299 @@
+//! Parent/child relationships between entities.
+//!
+//! The hierarchy is stored in the [`Parent`] and [`Children`] components. Use the [`Hierarchy`]
+//! system parameter to modify it, so that both of the components are kept in sync.
+//!
+//! When an entity is killed, it is removed from its parent's [`Children`] and its children lose
+//! their [`Parent`] the next time the world is [maintained][World::maintain]. Use
+//! [`Entities::kill_recursive()`] to kill an entity along with all of its descendants instead.
+
+use crate::prelude::*;
+
+/// Component containing the parent of an entity.
+///
+/// This is kept in sync with the parent's [`Children`] component by [`Hierarchy`].
+#[derive(HasSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[schema(no_default)]
+#[repr(C)]
+pub struct Parent(Entity);
+
+impl Parent {
+    /// Get the parent entity.
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Component containing the children of an entity.
+///
+/// This is kept in sync with the children's [`Parent`] components by [`Hierarchy`].
+#[derive(HasSchema, Clone, Default, Debug)]
+#[repr(C)]
+pub struct Children(SVec<Entity>);
+
+impl std::ops::Deref for Children {
+    type Target = [Entity];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// [`SystemParam`] for reading and modifying the entity hierarchy.
+pub struct Hierarchy<'a> {
+    parents: CompMut<'a, Parent>,
+    children: CompMut<'a, Children>,
+}
+
+impl<'a> SystemParam for Hierarchy<'a> {
+    type State = (
+        <CompMut<'a, Parent> as SystemParam>::State,
+        <CompMut<'a, Children> as SystemParam>::State,
+    );
+    type Param<'s> = Hierarchy<'s>;
+
+    fn get_state(world: &World) -> Self::State {
+        (
+            CompMut::<Parent>::get_state(world),
+            CompMut::<Children>::get_state(world),
+        )
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        (
+            CompMut::<Parent>::get_state_for_system(world, meta),
+            CompMut::<Children>::get_state_for_system(world, meta),
+        )
+    }
+
+    fn access(access: &mut SystemAccess) {
+        CompMut::<Parent>::access(access);
+        CompMut::<Children>::access(access);
+    }
+
+    fn borrow<'s>(world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        Hierarchy {
+            parents: CompMut::<Parent>::borrow(world, &mut state.0),
+            children: CompMut::<Children>::borrow(world, &mut state.1),
+        }
+    }
+}
+
+impl<'a> Hierarchy<'a> {
+    /// Get the parent of an entity.
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.parents.get(entity).map(Parent::get)
+    }
+
+    /// Get the children of an entity.
+    pub fn children(&self, entity: Entity) -> &[Entity] {
+        self.children
+            .get(entity)
+            .map(|x| &x[..])
+            .unwrap_or_default()
+    }
+
+    /// Get all of the descendants of an entity, parents first.
+    pub fn descendants(&self, entity: Entity) -> Vec<Entity> {
+        descendants(&self.children, entity)
+    }
+
+    /// Make `parent` the parent of `child`, removing it from its previous parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is `child` or one of its descendants.
+    #[track_caller]
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        let mut ancestor = Some(parent);
+        while let Some(entity) = ancestor {
+            if entity == child {
+                panic!(
+                    "Cannot make {parent:?} the parent of {child:?}, because it is one of its \
+                    descendants."
+                );
+            }
+            ancestor = self.parent(entity);
+        }
+
+        self.remove_parent(child);
+        self.parents.insert(child, Parent(parent));
+        match self.children.get_mut(parent) {
+            Some(children) => children.0.push(child),
+            None => {
+                self.children
+                    .insert(parent, Children([child].into_iter().collect()));
+            }
+        }
+    }
+
+    /// Remove the parent of `child`, making it a root entity, and return the old parent.
+    pub fn remove_parent(&mut self, child: Entity) -> Option<Entity> {
+        let parent = self.parents.remove(child)?.get();
+        remove_child(&mut self.children, parent, child);
+        Some(parent)
+    }
+}
+
+/// Remove `child` from the [`Children`] of `parent`, removing the component if it is left empty.
+fn remove_child(children: &mut ComponentStore<Children>, parent: Entity, child: Entity) {
+    if let Some(siblings) = children.get_mut(parent) {
+        siblings.0.retain(|&x| x != child);
+        if siblings.is_empty() {
+            children.remove(parent);
+        }
+    }
+}
+
+/// Get all of the descendants of an entity, parents first.
+fn descendants(children: &ComponentStore<Children>, entity: Entity) -> Vec<Entity> {
+    let mut descendants = Vec::new();
+    let mut stack = vec![entity];
+    while let Some(entity) = stack.pop() {
+        if let Some(children) = children.get(entity) {
+            descendants.extend(children.iter().copied());
+            stack.extend(children.iter().rev().copied());
+        }
+    }
+    descendants
+}
+
+impl Entities {
+    /// Kill an entity along with all of its descendants in the [hierarchy][crate::hierarchy].
+    pub fn kill_recursive(&mut self, entity: Entity, children: &ComponentStore<Children>) {
+        for descendant in descendants(children, entity) {
+            self.kill(descendant);
+        }
+        self.kill(entity);
+    }
+}
+
+/// Give `clone` the same parent as `entity`, adding it to the parent's [`Children`].
+///
+/// This is called by [`World::clone_entity()`], which doesn't copy the hierarchy components.
+pub(crate) fn clone_parent(world: &World, entity: Entity, clone: Entity) {
+    let mut parents = world.component_mut::<Parent>();
+    let Some(parent) = parents.get(entity).copied() else {
+        return;
+    };
+    parents.insert(clone, parent);
+
+    let mut children = world.component_mut::<Children>();
+    match children.get_mut(parent.get()) {
+        Some(siblings) => siblings.0.push(clone),
+        None => {
+            children.insert(parent.get(), Children([clone].into_iter().collect()));
+        }
+    }
+}
+
+/// Remove killed entities from the hierarchy.
+///
+/// This is called by [`World::maintain()`] before the components of the killed entities are removed.
+pub(crate) fn remove_killed_from_hierarchy(world: &World, killed: &[Entity]) {
+    let mut parents = world.component_mut::<Parent>();
+    let mut children = world.component_mut::<Children>();
+    if !parents.bitset().bit_any() && !children.bitset().bit_any() {
+        return;
+    }
+
+    for &entity in killed {
+        if let Some(parent) = parents.get(entity).copied() {
+            remove_child(&mut children, parent.get(), entity);
+        }
+        if let Some(orphans) = children.remove(entity) {
+            for orphan in orphans.iter() {
+                if parents.get(*orphan) == Some(&Parent(entity)) {
+                    parents.remove(*orphan);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn spawn(world: &World, count: usize) -> Vec<Entity> {
+        let mut entities = world.resource_mut::<Entities>();
+        (0..count).map(|_| entities.create()).collect()
+    }
+
+    #[test]
+    fn set_and_remove_parent() {
+        let world = World::new();
+        let [a, b, c] = spawn(&world, 3)[..] else {
+            unreachable!()
+        };
+
+        world.run_system(
+            move |mut hierarchy: Hierarchy| {
+                hierarchy.set_parent(b, a);
+                hierarchy.set_parent(c, a);
+                assert_eq!(hierarchy.children(a), [b, c]);
+
+                hierarchy.set_parent(c, b);
+                assert_eq!(hierarchy.children(a), [b]);
+                assert_eq!(hierarchy.parent(c), Some(b));
+                assert_eq!(hierarchy.descendants(a), [b, c]);
+
+                assert_eq!(hierarchy.remove_parent(b), Some(a));
+                assert!(hierarchy.children(a).is_empty());
+            },
+            (),
+        );
+        assert!(world.component::<Children>().get(a).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "descendants")]
+    fn cycles_are_not_allowed() {
+        let world = World::new();
+        let [a, b] = spawn(&world, 2)[..] else {
+            unreachable!()
+        };
+        world.run_system(
+            move |mut hierarchy: Hierarchy| {
+                hierarchy.set_parent(b, a);
+                hierarchy.set_parent(a, b);
+            },
+            (),
+        );
+    }
+
+    #[test]
+    fn killing_entities_updates_the_hierarchy() {
+        let world = World::new();
+        let [root, a, b, c, d] = spawn(&world, 5)[..] else {
+            unreachable!()
+        };
+        world.run_system(
+            move |mut hierarchy: Hierarchy| {
+                hierarchy.set_parent(a, root);
+                hierarchy.set_parent(b, a);
+                hierarchy.set_parent(c, root);
+                hierarchy.set_parent(d, c);
+            },
+            (),
+        );
+
+        // Killing `c` alone detaches it from `root` and orphans `d`.
+        world.resource_mut::<Entities>().kill(c);
+        world.maintain();
+        assert_eq!(world.component::<Children>().get(root).unwrap()[..], [a]);
+        assert!(world.component::<Parent>().get(d).is_none());
+
+        // Killing `a` recursively kills `b` too.
+        {
+            let children = world.component::<Children>();
+            world
+                .resource_mut::<Entities>()
+                .kill_recursive(a, &children);
+        }
+        world.maintain();
+        let entities = world.resource::<Entities>();
+        assert!(!entities.is_alive(a) && !entities.is_alive(b));
+        assert!(entities.is_alive(root) && entities.is_alive(d));
+        assert!(world.component::<Children>().get(root).is_none());
+    }
+}
@@ -12,8 +12,14 @@ pub mod atomic {
     pub use atomicell::*;
 }
 pub mod bitset;
+pub mod change_detection;
+pub mod checksum;
 pub mod components;
+pub mod diagnostics;
+pub mod diff;
 pub mod entities;
+pub mod events;
+pub mod hierarchy;
 pub mod resources;
 pub mod stage;
 pub mod system;
@@ -34,8 +40,14 @@ pub mod prelude {
 
     pub use crate::{
         bitset::*,
+        change_detection::*,
+        checksum::*,
         components::*,
+        diagnostics::*,
+        diff::*,
         entities::*,
+        events::*,
+        hierarchy::*,
         resources::*,
         stage::{CoreStage::*, *},
         system::*,
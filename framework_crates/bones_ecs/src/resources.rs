@@ -9,12 +9,48 @@ use crate::prelude::*;
 /// An untyped, atomic resource cell.
 pub type AtomicUntypedResource = Arc<UntypedResource>;
 
+/// [Type data][bones_schema::alloc::TypeDatas] that keeps a resource out of snapshots of the
+/// [`World`].
+///
+/// Clones of the world share the resource with the original world, like the shared resources
+/// inserted with [`UntypedResources::insert_cell()`]. This means that the resource isn't rolled back
+/// when a snapshot is [loaded][World::load_snapshot], that it is ignored by [`World::diff()`], and
+/// that it is kept when the world's owned resources are cleared. Use this for resources that aren't
+/// part of the game's state, like [`SystemDiagnostics`].
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// #[derive(HasSchema, Clone, Default)]
+/// #[derive_type_data(ExcludeFromSnapshot)]
+/// struct FrameCount(u32);
+///
+/// let mut world = World::new();
+/// world.insert_resource(FrameCount(0));
+/// let snapshot = world.clone();
+///
+/// world.resource_mut::<FrameCount>().0 += 1;
+/// world.load_snapshot(snapshot);
+/// assert_eq!(world.resource::<FrameCount>().0, 1);
+/// ```
+#[derive(HasSchema, Clone, Copy, Default, Debug)]
+pub struct ExcludeFromSnapshot;
+
+impl<T> FromType<T> for ExcludeFromSnapshot {
+    fn from_type() -> Self {
+        ExcludeFromSnapshot
+    }
+}
+
 /// An untyped resource that may be inserted into [`UntypedResources`].
 ///
 /// This is fundamentally a [`Arc<AtomicCell<Option<SchemaBox>>>`] and thus represents
 /// a cell that may or may not contain a resource of it's schema.
+///
+/// The resource data is copy-on-write: snapshots of the [`World`] share it with the original
+/// resource, and it is only copied when either of them is mutably borrowed.
 pub struct UntypedResource {
-    cell: AtomicCell<Option<SchemaBox>>,
+    cell: AtomicCell<Option<Arc<SchemaBox>>>,
+    ticks: ResourceTicks,
     schema: &'static Schema,
 }
 
@@ -29,6 +65,7 @@ impl UntypedResource {
     pub fn empty(schema: &'static Schema) -> Self {
         Self {
             cell: AtomicCell::new(None),
+            ticks: default(),
             schema,
         }
     }
@@ -37,7 +74,8 @@ impl UntypedResource {
     pub fn new(resource: SchemaBox) -> Self {
         Self {
             schema: resource.schema(),
-            cell: AtomicCell::new(Some(resource)),
+            cell: AtomicCell::new(Some(Arc::new(resource))),
+            ticks: default(),
         }
     }
 
@@ -45,7 +83,8 @@ impl UntypedResource {
     /// value for the schema.
     pub fn from_default(schema: &'static Schema) -> Self {
         Self {
-            cell: AtomicCell::new(Some(SchemaBox::default(schema))),
+            cell: AtomicCell::new(Some(Arc::new(SchemaBox::default(schema)))),
+            ticks: default(),
             schema,
         }
     }
@@ -53,7 +92,19 @@ impl UntypedResource {
     /// Clone the inner data, creating a new copy instead of returning another handle the the same
     /// data, as the normal `clone()` implementation does.
     pub fn clone_data(&self) -> Option<SchemaBox> {
-        (*self.cell.borrow()).clone()
+        self.cell.borrow().as_deref().cloned()
+    }
+
+    /// Create a new [`UntypedResource`] with the same ticks, that shares the resource data with
+    /// this one until either of them is mutably borrowed.
+    fn snapshot(&self) -> Self {
+        let snapshot = Self {
+            cell: AtomicCell::new(self.cell.borrow().clone()),
+            ticks: default(),
+            schema: self.schema,
+        };
+        snapshot.ticks.set(self.ticks.get());
+        snapshot
     }
 
     /// Insert resource data into the cell, returning the previous data.
@@ -61,34 +112,67 @@ impl UntypedResource {
     /// Errors if the schema of the data does not match that of this cell.
     pub fn insert(&self, data: SchemaBox) -> Result<Option<SchemaBox>, SchemaMismatchError> {
         self.schema.ensure_match(data.schema())?;
-        let mut data = Some(data);
+        let mut data = Some(Arc::new(data));
         std::mem::swap(&mut data, &mut *self.cell.borrow_mut());
-        Ok(data)
+        Ok(data.map(Arc::unwrap_or_clone))
+    }
+
+    /// Insert the data returned by `f` if the cell is empty.
+    ///
+    /// Returns whether the data was inserted.
+    pub fn init_with(&self, f: impl FnOnce() -> SchemaBox) -> bool {
+        let mut borrow = self.cell.borrow_mut();
+        if borrow.is_none() {
+            *borrow = Some(Arc::new(f()));
+            true
+        } else {
+            false
+        }
     }
 
     /// Remove the resource data, returning what was stored in it.
     pub fn remove(&self) -> Option<SchemaBox> {
         let mut data = None;
         std::mem::swap(&mut data, &mut *self.cell.borrow_mut());
-        data
+        data.map(Arc::unwrap_or_clone)
     }
 
-    /// Borrow the resource.
+    /// Borrow the resource, if it is present.
     #[track_caller]
-    pub fn borrow(&self) -> Ref<'_, Option<SchemaBox>> {
-        self.cell.borrow()
+    pub fn borrow(&self) -> Option<Ref<'_, SchemaBox>> {
+        let borrow = self.cell.borrow();
+        if borrow.is_some() {
+            Some(Ref::map(borrow, |b| b.as_deref().unwrap()))
+        } else {
+            None
+        }
     }
 
-    /// Mutably borrow the resource.
+    /// Mutably borrow the resource, if it is present.
+    ///
+    /// If the resource data is shared with a snapshot, it is copied first.
     #[track_caller]
-    pub fn borrow_mut(&self) -> RefMut<'_, Option<SchemaBox>> {
-        self.cell.borrow_mut()
+    pub fn borrow_mut(&self) -> Option<RefMut<'_, SchemaBox>> {
+        let borrow = self.cell.borrow_mut();
+        if borrow.is_some() {
+            Some(RefMut::map(borrow, |b| Arc::make_mut(b.as_mut().unwrap())))
+        } else {
+            None
+        }
     }
 
     /// Get the schema of the resource.
     pub fn schema(&self) -> &'static Schema {
         self.schema
     }
+
+    /// Get the [change detection][crate::change_detection] ticks of the resource.
+    ///
+    /// The ticks are updated when the resource is inserted through the [`World`], or mutably
+    /// accessed through a [`ResMut`] system parameter.
+    pub fn ticks(&self) -> &ResourceTicks {
+        &self.ticks
+    }
 }
 
 /// Storage for un-typed resources.
@@ -111,19 +195,8 @@ impl Clone for UntypedResources {
         let new_resources = OnceMap::default();
         let new_shared_resources = OnceMap::default();
         for (schema, resource_cell) in resources {
-            let is_shared = self.shared_resources.contains_key(&schema.id());
-
-            if !is_shared {
-                let resource = resource_cell.clone_data();
-                new_resources.map_insert(
-                    schema.id(),
-                    |_| Arc::new(UntypedResource::empty(schema)),
-                    |_, cell| {
-                        if let Some(resource) = resource {
-                            cell.insert(resource).unwrap();
-                        }
-                    },
-                );
+            if !self.is_shared(schema) {
+                new_resources.insert(schema.id(), |_| Arc::new(resource_cell.snapshot()));
             } else {
                 new_shared_resources.insert(schema.id(), |_| Box::new(()));
                 new_resources.insert(schema.id(), |_| resource_cell.clone());
@@ -152,6 +225,14 @@ impl UntypedResources {
         Self::default()
     }
 
+    /// Check whether the resource is shared between the world and its clones, either because its
+    /// cell was [inserted][Self::insert_cell] or because it is [excluded from
+    /// snapshots][ExcludeFromSnapshot].
+    fn is_shared(&self, schema: &Schema) -> bool {
+        self.shared_resources.contains_key(&schema.id())
+            || schema.type_data.get::<ExcludeFromSnapshot>().is_some()
+    }
+
     /// Check whether or not a cell for the given resource has been initialized yet.
     pub fn contains_cell(&self, id: SchemaId) -> bool {
         self.resources.contains_key(&id)
@@ -201,11 +282,20 @@ impl UntypedResources {
         )
     }
 
+    /// Get the cells of all of the resources that are not shared resources.
+    pub(crate) fn owned_cells(&self) -> Vec<AtomicUntypedResource> {
+        self.resources
+            .read_only_view()
+            .iter()
+            .filter(|(_, cell)| !self.is_shared(cell.schema))
+            .map(|(_, cell)| cell.clone())
+            .collect()
+    }
+
     /// Removes all resourcse that are not shared resources.
     pub fn clear_owned_resources(&mut self) {
-        for (schema_id, resource_cell) in self.resources.iter_mut() {
-            let is_shared = self.shared_resources.contains_key(schema_id);
-            if !is_shared {
+        for resource_cell in self.resources.read_only_view().values() {
+            if !self.is_shared(resource_cell.schema) {
                 resource_cell.remove();
             }
         }
@@ -254,27 +344,17 @@ impl Resources {
     /// Borrow a resource.
     #[track_caller]
     pub fn get<T: HasSchema>(&self) -> Option<Ref<'_, T>> {
-        let b = self.untyped.get(T::schema()).borrow();
-        if b.is_some() {
-            Some(Ref::map(b, |b| unsafe {
-                b.as_ref().unwrap().as_ref().cast_into_unchecked()
-            }))
-        } else {
-            None
-        }
+        let b = self.untyped.get(T::schema()).borrow()?;
+        Some(Ref::map(b, |b| unsafe { b.as_ref().cast_into_unchecked() }))
     }
 
     /// Borrow a resource.
     #[track_caller]
     pub fn get_mut<T: HasSchema>(&self) -> Option<RefMut<'_, T>> {
-        let b = self.untyped.get(T::schema()).borrow_mut();
-        if b.is_some() {
-            Some(RefMut::map(b, |b| unsafe {
-                b.as_mut().unwrap().as_mut().cast_into_mut_unchecked()
-            }))
-        } else {
-            None
-        }
+        let b = self.untyped.get(T::schema()).borrow_mut()?;
+        Some(RefMut::map(b, |b| unsafe {
+            b.as_mut().cast_into_mut_unchecked()
+        }))
     }
 
     /// Gets a clone of the resource cell for the resource of the given type.
@@ -317,9 +397,8 @@ impl<T: HasSchema + Debug> Debug for AtomicResource<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("AtomicResource(")?;
         self.untyped
-            .cell
             .borrow()
-            .as_ref()
+            .as_deref()
             .map(|x| x.cast_ref::<T>())
             .fmt(f)?;
         f.write_str(")")?;
@@ -374,28 +453,25 @@ impl<T: HasSchema> AtomicResource<T> {
     ///
     /// This returns a read guard, very similar to an [`RwLock`][std::sync::RwLock].
     pub fn borrow(&self) -> Option<Ref<'_, T>> {
-        let borrow = self.untyped.borrow();
-        if borrow.is_some() {
-            Some(Ref::map(borrow, |r| unsafe {
-                r.as_ref().unwrap().as_ref().cast_into_unchecked()
-            }))
-        } else {
-            None
-        }
+        let borrow = self.untyped.borrow()?;
+        Some(Ref::map(borrow, |r| unsafe {
+            r.as_ref().cast_into_unchecked()
+        }))
     }
 
     /// Lock the resource for read-writing.
     ///
     /// This returns a write guard, very similar to an [`RwLock`][std::sync::RwLock].
     pub fn borrow_mut(&self) -> Option<RefMut<'_, T>> {
-        let borrow = self.untyped.borrow_mut();
-        if borrow.is_some() {
-            Some(RefMut::map(borrow, |r| unsafe {
-                r.as_mut().unwrap().as_mut().cast_into_mut_unchecked()
-            }))
-        } else {
-            None
-        }
+        let borrow = self.untyped.borrow_mut()?;
+        Some(RefMut::map(borrow, |r| unsafe {
+            r.as_mut().cast_into_mut_unchecked()
+        }))
+    }
+
+    /// Get the [change detection][crate::change_detection] ticks of the resource.
+    pub fn ticks(&self) -> &ResourceTicks {
+        &self.untyped.ticks
     }
 
     /// Convert into an untyped resource.
@@ -407,45 +483,26 @@ impl<T: HasSchema> AtomicResource<T> {
 impl<T: HasSchema + FromWorld> AtomicResource<T> {
     /// Initialize the resource using it's [`FromWorld`] implementation, if it is not present.
     pub fn init(&self, world: &World) {
-        let mut borrow = self.untyped.borrow_mut();
-        if unlikely(borrow.is_none()) {
-            *borrow = Some(SchemaBox::new(T::from_world(world)))
+        if unlikely(
+            self.untyped
+                .init_with(|| SchemaBox::new(T::from_world(world))),
+        ) {
+            self.untyped.ticks.set_added(world.change_tick());
         }
     }
 
     /// Borrow the resource, initializing it if it doesn't exist.
     #[track_caller]
     pub fn init_borrow(&self, world: &World) -> Ref<'_, T> {
-        let map_borrow = |borrow| {
-            // SOUND: we know the schema matches.
-            Ref::map(borrow, |b: &Option<SchemaBox>| unsafe {
-                b.as_ref().unwrap().as_ref().cast_into_unchecked()
-            })
-        };
-        let borrow = self.untyped.borrow();
-        if unlikely(borrow.is_none()) {
-            drop(borrow);
-            {
-                let mut borrow_mut = self.untyped.borrow_mut();
-                *borrow_mut = Some(SchemaBox::new(T::from_world(world)));
-            }
-
-            map_borrow(self.untyped.borrow())
-        } else {
-            map_borrow(borrow)
-        }
+        self.init(world);
+        self.borrow().unwrap()
     }
 
     /// Borrow the resource, initializing it if it doesn't exist.
     #[track_caller]
     pub fn init_borrow_mut(&self, world: &World) -> RefMut<'_, T> {
-        let mut borrow = self.untyped.borrow_mut();
-        if unlikely(borrow.is_none()) {
-            *borrow = Some(SchemaBox::new(T::from_world(world)));
-        }
-        RefMut::map(borrow, |b| unsafe {
-            b.as_mut().unwrap().as_mut().cast_into_mut_unchecked()
-        })
+        self.init(world);
+        self.borrow_mut().unwrap()
     }
 }
 
@@ -482,13 +539,7 @@ impl UntypedResourceSet {
         // Update an existing resource of the same type.
         for r in &mut self.resources {
             if r.schema() == T::schema() {
-                let mut borrow = r.borrow_mut();
-
-                if let Some(b) = borrow.as_mut() {
-                    *b.cast_mut() = resource;
-                } else {
-                    *borrow = Some(SchemaBox::new(resource))
-                }
+                r.insert(SchemaBox::new(resource)).unwrap();
                 return;
             }
         }
@@ -511,16 +562,12 @@ impl UntypedResourceSet {
     #[track_caller]
     pub fn resource_mut<T: HasSchema>(&self) -> Option<RefMut<'_, T>> {
         let res = self.resources.iter().find(|x| x.schema() == T::schema())?;
-        let borrow = res.borrow_mut();
+        let borrow = res.borrow_mut()?;
 
-        if borrow.is_some() {
-            // SOUND: We know the type matches T
-            Some(RefMut::map(borrow, |b| unsafe {
-                b.as_mut().unwrap().as_mut().cast_into_mut_unchecked()
-            }))
-        } else {
-            None
-        }
+        // SOUND: We know the type matches T
+        Some(RefMut::map(borrow, |b| unsafe {
+            b.as_mut().cast_into_mut_unchecked()
+        }))
     }
 
     /// Insert an [`UntypedResource`] with empty cell for [`Schema`] of `T`.
@@ -528,8 +575,7 @@ impl UntypedResourceSet {
     pub fn insert_empty<T: HasSchema>(&mut self) {
         for r in &mut self.resources {
             if r.schema() == T::schema() {
-                let mut borrow = r.borrow_mut();
-                *borrow = None;
+                r.remove();
                 return;
             }
         }
@@ -625,4 +671,68 @@ mod test {
         let res_b = r.get::<B>().unwrap();
         assert_eq!(res_b.0, 1);
     }
+
+    #[test]
+    fn snapshots_share_resource_data() {
+        let world = World::new();
+        world.insert_resource(A(String::from("hi")));
+        world.insert_resource(B(1));
+        let snapshot = world.clone();
+        let data = |world: &World, schema| {
+            world
+                .resources
+                .untyped()
+                .get(schema)
+                .cell
+                .borrow()
+                .clone()
+                .unwrap()
+        };
+        assert!(Arc::ptr_eq(
+            &data(&world, A::schema()),
+            &data(&snapshot, A::schema())
+        ));
+        assert!(Arc::ptr_eq(
+            &data(&world, B::schema()),
+            &data(&snapshot, B::schema())
+        ));
+
+        // Mutably borrowing a resource copies it, without affecting the snapshot.
+        world.resource_mut::<B>().0 = 2;
+        assert!(Arc::ptr_eq(
+            &data(&world, A::schema()),
+            &data(&snapshot, A::schema())
+        ));
+        assert!(!Arc::ptr_eq(
+            &data(&world, B::schema()),
+            &data(&snapshot, B::schema())
+        ));
+        assert_eq!(world.resource::<B>().0, 2);
+        assert_eq!(snapshot.resource::<B>().0, 1);
+    }
+
+    #[test]
+    fn excluded_resources_are_not_snapshotted() {
+        #[derive(HasSchema, Clone, Debug, Default)]
+        #[derive_type_data(ExcludeFromSnapshot)]
+        #[repr(C)]
+        struct FrameTime(u32);
+
+        let mut world = World::new();
+        world.insert_resource(FrameTime(1));
+        world.insert_resource(B(1));
+        let snapshot = world.clone();
+        assert!(world.diff(&snapshot).is_empty());
+
+        world.resource_mut::<FrameTime>().0 = 2;
+        world.resource_mut::<B>().0 = 2;
+        assert_eq!(world.diff(&snapshot).resources.len(), 1);
+
+        world.load_snapshot(snapshot);
+        assert_eq!(world.resource::<FrameTime>().0, 2);
+        assert_eq!(world.resource::<B>().0, 1);
+
+        world.resources.clear_owned_resources();
+        assert_eq!(world.resource::<FrameTime>().0, 2);
+    }
 }
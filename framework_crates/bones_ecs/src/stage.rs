@@ -2,8 +2,15 @@
 
 use std::collections::VecDeque;
 
+use instant::Instant;
+
 use crate::prelude::*;
 
+mod ordering;
+mod parallel;
+pub use ordering::*;
+pub use parallel::*;
+
 /// Resource that is automatically added to the world while a system stage is being run
 /// that specifies the unique ID of the stage that being run.
 ///
@@ -24,6 +31,13 @@ pub struct SystemStagesBuilder {
 
     /// Systems that are continously run until they succeed(return Some). These run before all stages. Uses Option to allow for easy usage of `?`.
     single_success_systems: Vec<StaticSystem<(), Option<()>>>,
+
+    /// Systems that apply state transitions. These run after the single success systems, before all stages.
+    state_transition_systems: Vec<StaticSystem<(), ()>>,
+
+    /// Systems that have been added to stages, along with the ID of their stage. They are sorted
+    /// and added to their stages when the builder is finished.
+    systems: Vec<(Ulid, SystemConfig)>,
 }
 
 impl Default for SystemStagesBuilder {
@@ -46,17 +60,45 @@ impl SystemStagesBuilder {
             startup_resources: default(),
             startup_systems: default(),
             single_success_systems: Vec::new(),
+            state_transition_systems: Vec::new(),
+            systems: Vec::new(),
         }
     }
 
     /// Finish building and convert to [`SystemStages`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ordering constraints of the systems in a stage form a cycle. Use
+    /// [`try_finish()`][Self::try_finish] to handle the error instead.
+    #[track_caller]
     pub fn finish(self) -> SystemStages {
-        SystemStages {
+        self.try_finish().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Finish building and convert to [`SystemStages`], sorting the systems in each stage according
+    /// to their [ordering constraints][IntoSystemConfig].
+    pub fn try_finish(mut self) -> Result<SystemStages, SystemOrderError> {
+        for stage in &mut self.stages {
+            let id = stage.id();
+            let (configs, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.systems)
+                .into_iter()
+                .partition(|(stage_id, _)| *stage_id == id);
+            self.systems = rest;
+
+            let configs = configs.into_iter().map(|(_, config)| config).collect();
+            for (system, dependencies) in sort_systems(&stage.name(), configs)? {
+                stage.add_system_with_dependencies(system, &dependencies);
+            }
+        }
+
+        Ok(SystemStages {
             stages: self.stages,
             startup_systems: self.startup_systems,
             startup_resources: self.startup_resources,
             single_success_systems: self.single_success_systems,
-        }
+            state_transition_systems: self.state_transition_systems,
+        })
     }
 
     /// Create [`SystemStagesBuilder`] by taking existing [`SystemStages`].
@@ -66,6 +108,8 @@ impl SystemStagesBuilder {
             startup_systems: stages.startup_systems,
             startup_resources: stages.startup_resources,
             single_success_systems: stages.single_success_systems,
+            state_transition_systems: stages.state_transition_systems,
+            systems: Vec::new(),
         }
     }
 
@@ -88,26 +132,36 @@ impl SystemStagesBuilder {
         self
     }
 
+    /// Add a system that applies state transitions.
+    ///
+    /// State transition systems are run every time the stages are run, after the startup and
+    /// single success systems, but before all of the stages. This makes sure that all of the stages
+    /// in a frame see the same state, and that transitions are part of the simulated frame, so they
+    /// are replayed correctly when a world snapshot is restored.
+    pub fn add_state_transition_system<Args, S>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+    {
+        self.state_transition_systems.push(system.system());
+        self
+    }
+
     /// Add a [`System`] to the stage with the given label.
+    ///
+    /// The system may be given a label and ordering constraints relative to other labelled systems
+    /// in the same stage using the methods of [`IntoSystemConfig`].
     pub fn add_system_to_stage<Args, S>(&mut self, label: impl StageLabel, system: S) -> &mut Self
     where
-        S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+        S: IntoSystemConfig<Args>,
     {
         let name = label.name();
         let id = label.id();
-        let mut stage = None;
 
-        for st in &mut self.stages {
-            if st.id() == id {
-                stage = Some(st);
-            }
-        }
-
-        let Some(stage) = stage else {
+        if !self.stages.iter().any(|st| st.id() == id) {
             panic!("Stage with label `{}` ( {} ) doesn't exist.", name, id);
-        };
+        }
 
-        stage.add_system(system.system());
+        self.systems.push((id, system.config()));
 
         self
     }
@@ -180,6 +234,9 @@ pub struct SystemStages {
 
     /// Systems that are continously run until they succeed(return Some). These run before all stages. Uses Option to allow for easy usage of `?`.
     single_success_systems: Vec<StaticSystem<(), Option<()>>>,
+
+    /// Systems that apply state transitions. These run after the single success systems, before all stages.
+    state_transition_systems: Vec<StaticSystem<(), ()>>,
 }
 
 impl std::fmt::Debug for SystemStages {
@@ -205,6 +262,9 @@ impl SystemStages {
     }
 
     /// Execute the systems on the given `world`.
+    ///
+    /// Every system that is run advances the world's [change tick][World::change_tick], and after
+    /// all of the stages have run, the [`Events`] buffers are updated.
     pub fn run(&mut self, world: &mut World) {
         // If we haven't run startup systems and setup resources yet, do so
         self.handle_startup(world);
@@ -218,6 +278,12 @@ impl SystemStages {
             }
         }
 
+        // Apply state transitions
+        for system in &mut self.state_transition_systems {
+            system.run(world, ());
+        }
+        apply_commands(world);
+
         // Run each stage
         for stage in &mut self.stages {
             // Set the current stage resource
@@ -227,6 +293,9 @@ impl SystemStages {
             stage.run(world);
         }
 
+        // Swap the event buffers, dropping events that have been around for two frames
+        EventRegistry::update_events(world);
+
         // Cleanup killed entities
         world.maintain();
 
@@ -349,6 +418,7 @@ impl SystemStages {
             let resource_copy = resource.clone_data().unwrap();
             let resource_cell = world.resources.untyped().get_cell(resource.schema());
             let prev_val = resource_cell.insert(resource_copy).unwrap();
+            resource_cell.ticks().set_added(world.change_tick());
 
             // Warn on already existing resource
             if prev_val.is_some() {
@@ -371,6 +441,20 @@ pub trait SystemStage: Sync + Send {
 
     /// Add a system to this stage.
     fn add_system(&mut self, system: StaticSystem<(), ()>);
+    /// Add a system to this stage, that must run after the systems with the given IDs, which have
+    /// already been added to the stage.
+    ///
+    /// This is used for the [ordering constraints][IntoSystemConfig] of the system. The default
+    /// implementation calls [`add_system()`][Self::add_system], which is enough for stages that run
+    /// their systems one after another in the order they were added.
+    fn add_system_with_dependencies(
+        &mut self,
+        system: StaticSystem<(), ()>,
+        dependencies: &[SystemId],
+    ) {
+        let _ = dependencies;
+        self.add_system(system);
+    }
     /// Remove all systems from this stage.
     fn remove_all_systems(&mut self);
 }
@@ -408,16 +492,27 @@ impl SystemStage for SimpleSystemStage {
     }
 
     fn run(&mut self, world: &World) {
+        // Only time the systems when something is collecting the diagnostics.
+        let record_diagnostics = world.resources.contains::<SystemDiagnostics>();
+        let stage_start = record_diagnostics.then(Instant::now);
+        let mut timings = Vec::new();
+
         // Run the systems
         for system in &mut self.systems {
-            system.run(world, ());
+            if system.check_conditions(world) {
+                let start = record_diagnostics.then(Instant::now);
+                system.run(world, ());
+                if let Some(start) = start {
+                    timings.push((system.id, system.name, start.elapsed()));
+                }
+            }
         }
 
-        // Drain the command queue
-        let queue = world.resources.get_mut::<CommandQueue>();
-        if let Some(mut command_queue) = queue {
-            for mut system in command_queue.queue.drain(..) {
-                system.run(world, ());
+        apply_commands(world);
+
+        if let Some(stage_start) = stage_start {
+            if let Some(mut diagnostics) = world.resources.get_mut::<SystemDiagnostics>() {
+                diagnostics.record_stage(self.id, &self.name, stage_start.elapsed(), timings);
             }
         }
     }
@@ -431,6 +526,31 @@ impl SystemStage for SimpleSystemStage {
     }
 }
 
+/// Run all of the systems in the [`CommandQueue`], draining it, along with the commands added by
+/// [`ComponentHooks`].
+///
+/// This is repeated until there are no commands left, so that the commands added while running
+/// other commands are run too.
+pub(crate) fn apply_commands(world: &World) {
+    loop {
+        let mut commands = world
+            .resources
+            .get_mut::<CommandQueue>()
+            .map(|mut command_queue| std::mem::take(&mut command_queue.queue))
+            .unwrap_or_default();
+        commands.extend(world.components.take_hook_commands());
+        if commands.is_empty() {
+            break;
+        }
+
+        for mut system in commands {
+            system.run(world, ());
+            // Commands only run once, so we don't need to remember when they last ran.
+            world.forget_system(system.id);
+        }
+    }
+}
+
 /// Trait for things that may be used to identify a system stage.
 pub trait StageLabel {
     /// Returns the human-readable name of the label, used in error messages.
@@ -502,27 +622,155 @@ impl CommandQueue {
     {
         self.queue.push_back(system.system());
     }
+
+    /// Spawn a new entity with the components in the bundle at the end of the stage.
+    ///
+    /// Use [`Commands::spawn()`] to also get the spawned entity.
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) {
+        let mut bundle = Some(bundle);
+        self.add(move |world: &World| {
+            let entity = world.resource_mut::<Entities>().create();
+            if let Some(bundle) = bundle.take() {
+                bundle.insert(world, entity);
+            }
+        });
+    }
+
+    /// Get an [`EntityCommands`] that can be used to change an entity at the end of the stage.
+    pub fn entity(&mut self, entity: Entity) -> EntityCommands<'_> {
+        EntityCommands {
+            entity,
+            commands: self,
+        }
+    }
+}
+
+/// Queues changes to an entity that will be applied at the end of the current [`SystemStage`].
+///
+/// Returned by [`CommandQueue::entity()`]. Changes to an entity that has been killed by the time
+/// they are applied are ignored.
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Health(u32);
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Dead;
+/// fn die(entities: Res<Entities>, healths: Comp<Health>, mut commands: Commands) {
+///     for (entity, health) in entities.iter_with(&healths) {
+///         if health.0 == 0 {
+///             commands.entity(entity).remove::<Health>().insert(Dead);
+///         }
+///     }
+/// }
+/// ```
+pub struct EntityCommands<'a> {
+    entity: Entity,
+    commands: &'a mut CommandQueue,
+}
+
+impl<'a> EntityCommands<'a> {
+    /// Get the entity that is being changed.
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    /// Insert a component on the entity, replacing the existing one if there is one.
+    pub fn insert<T: HasSchema>(&mut self, component: T) -> &mut Self {
+        self.insert_bundle((component,))
+    }
+
+    /// Insert the components in the bundle on the entity, replacing existing ones.
+    pub fn insert_bundle<B: Bundle>(&mut self, bundle: B) -> &mut Self {
+        let entity = self.entity;
+        let mut bundle = Some(bundle);
+        self.commands.add(move |world: &World| {
+            if !world.resource::<Entities>().is_alive(entity) {
+                return;
+            }
+            if let Some(bundle) = bundle.take() {
+                bundle.insert(world, entity);
+            }
+        });
+        self
+    }
+
+    /// Remove a component from the entity.
+    pub fn remove<T: HasSchema>(&mut self) -> &mut Self {
+        let entity = self.entity;
+        self.commands.add(move |world: &World| {
+            if world.resource::<Entities>().is_alive(entity) {
+                world.component_mut::<T>().remove(entity);
+            }
+        });
+        self
+    }
+
+    /// Kill the entity.
+    ///
+    /// Like [`Entities::kill()`], the components of the entity are removed when the world is
+    /// [maintained][World::maintain].
+    pub fn kill(&mut self) {
+        let entity = self.entity;
+        self.commands.add(move |mut entities: ResMut<Entities>| {
+            if entities.is_alive(entity) {
+                entities.kill(entity);
+            }
+        });
+    }
 }
 
 /// A [`SystemParam`] that can be used to schedule systems that will be run at the end of the
 /// current [`SystemStage`].
 ///
+/// It can also be used to spawn entities and to [change existing entities][EntityCommands], which
+/// is useful in systems that are borrowing the component stores that would be changed.
+///
 /// This is a shortcut for [`ResMut<CommandQueue>`].
 #[derive(Deref, DerefMut)]
-pub struct Commands<'a>(RefMut<'a, CommandQueue>);
+pub struct Commands<'a> {
+    #[deref]
+    queue: RefMut<'a, CommandQueue>,
+    reserver: EntityReserver,
+}
+
+impl<'a> Commands<'a> {
+    /// Spawn a new entity with the components in the bundle at the end of the stage, and get an
+    /// [`EntityCommands`] for it.
+    ///
+    /// The entity is [reserved][Entities::reserve] right away, so that it can be referenced before
+    /// it is spawned.
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
+        let entity = self.reserver.reserve();
+        self.queue
+            .add(|mut entities: ResMut<Entities>| entities.flush_reserved());
+        let mut entity_commands = self.queue.entity(entity);
+        entity_commands.insert_bundle(bundle);
+        entity_commands
+    }
+}
 
 impl<'a> SystemParam for Commands<'a> {
-    type State = AtomicResource<CommandQueue>;
+    type State = (AtomicResource<CommandQueue>, EntityReserver);
     type Param<'s> = Commands<'s>;
 
     fn get_state(world: &World) -> Self::State {
         let cell = world.resources.get_cell::<CommandQueue>();
         cell.init(world);
-        cell
+        (cell, world.resource::<Entities>().reserver())
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_write(CommandQueue::schema());
+        // The entities are read to get their reserver when the state is created.
+        access.add_resource_read(Entities::schema());
     }
 
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        Commands(state.borrow_mut().unwrap())
+        Commands {
+            queue: state.0.borrow_mut().unwrap(),
+            reserver: state.1.clone(),
+        }
     }
 }
 
@@ -563,3 +811,123 @@ impl SingleSuccessSystems {
         self.systems_succeeded.insert(index);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[repr(C)]
+    struct A(u32);
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[repr(C)]
+    struct B(u32);
+
+    #[test]
+    fn entity_commands() {
+        let world = World::new();
+        let [e1, e2] = world.run_system(
+            |mut entities: ResMut<Entities>, mut a: CompMut<A>| {
+                let e1 = entities.create();
+                let e2 = entities.create();
+                a.insert(e1, A(1));
+                a.insert(e2, A(2));
+                [e1, e2]
+            },
+            (),
+        );
+
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        // The commands are queued while the component stores are borrowed by the system.
+        stage.add_system(
+            (move |entities: Res<Entities>, a: Comp<A>, mut commands: Commands| {
+                for (entity, a) in entities.iter_with(&a) {
+                    commands.spawn((A(a.0 * 10), B(a.0)));
+                    commands.entity(entity).insert(B(0)).remove::<A>();
+                }
+                commands.entity(e2).kill();
+                // Changes to killed entities are ignored.
+                commands.entity(e2).insert(A(5));
+            })
+            .system(),
+        );
+        stage.run(&world);
+
+        world.run_system(
+            move |entities: Res<Entities>, a: Comp<A>, b: Comp<B>| {
+                assert!(entities.is_alive(e1));
+                assert!(!entities.is_alive(e2));
+                assert_eq!(a.get(e1), None);
+                assert_eq!(b.get(e1), Some(&B(0)));
+                assert_eq!(a.get(e2), None);
+
+                let mut spawned = entities
+                    .iter_with((&a, &b))
+                    .map(|(_, (a, b))| (a.clone(), b.clone()))
+                    .collect::<Vec<_>>();
+                spawned.sort_by_key(|(a, _)| a.0);
+                assert_eq!(spawned, [(A(10), B(1)), (A(20), B(2))]);
+            },
+            (),
+        );
+    }
+
+    #[test]
+    fn spawned_entities_can_be_referenced() {
+        #[derive(HasSchema, Clone, Default)]
+        #[repr(C)]
+        struct Target(Entity);
+
+        let world = World::new();
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system(
+            (|entities: Res<Entities>, mut commands: Commands| {
+                let target = commands.spawn((A(1),)).insert(B(2)).id();
+                commands.spawn((Target(target),));
+                // The entity is reserved, but isn't spawned until the commands are applied.
+                assert!(!entities.is_alive(target));
+            })
+            .system(),
+        );
+        stage.run(&world);
+
+        world.run_system(
+            |entities: Res<Entities>, a: Comp<A>, b: Comp<B>, targets: Comp<Target>| {
+                let (_, target) = entities.iter_with(&targets).next().unwrap();
+                assert!(entities.is_alive(target.0));
+                assert_eq!(a.get(target.0), Some(&A(1)));
+                assert_eq!(b.get(target.0), Some(&B(2)));
+                assert_eq!(entities.iter().count(), 2);
+            },
+            (),
+        );
+    }
+
+    #[test]
+    fn spawn_while_entities_are_borrowed() {
+        let world = World::new();
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system(
+            (|mut entities: ResMut<Entities>, mut commands: Commands| {
+                let created = entities.create();
+                let spawned = commands.spawn((A(1),)).id();
+                assert_ne!(created, spawned);
+                // Creating an entity also creates the reserved ones.
+                let created = entities.create();
+                assert!(entities.is_alive(spawned));
+                assert_ne!(created, spawned);
+            })
+            .system(),
+        );
+        stage.run(&world);
+
+        world.run_system(
+            |entities: Res<Entities>, a: Comp<A>| {
+                assert_eq!(entities.iter().count(), 3);
+                assert_eq!(entities.iter_with(&a).count(), 1);
+            },
+            (),
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Ordering constraints between the systems in a stage.
+
+use std::borrow::Cow;
+
+use crate::prelude::*;
+
+/// A label that can be given to systems, so that other systems may be ordered relative to them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SystemLabel(Cow<'static, str>);
+
+impl From<&'static str> for SystemLabel {
+    fn from(label: &'static str) -> Self {
+        Self(Cow::Borrowed(label))
+    }
+}
+
+impl From<String> for SystemLabel {
+    fn from(label: String) -> Self {
+        Self(Cow::Owned(label))
+    }
+}
+
+impl std::fmt::Display for SystemLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A system along with the labels and ordering constraints that it was added to a stage with.
+///
+/// Usually created with the methods on [`IntoSystemConfig`].
+pub struct SystemConfig {
+    /// The system.
+    pub system: StaticSystem<(), ()>,
+    /// The labels of the system.
+    pub labels: Vec<SystemLabel>,
+    /// The labels of the systems that this system must run before.
+    pub before: Vec<SystemLabel>,
+    /// The labels of the systems that this system must run after.
+    pub after: Vec<SystemLabel>,
+}
+
+impl SystemConfig {
+    /// Create a config for a system without any labels or ordering constraints.
+    pub fn new(system: StaticSystem<(), ()>) -> Self {
+        Self {
+            system,
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+}
+
+/// Trait for things that can be added to a stage with
+/// [`SystemStagesBuilder::add_system_to_stage()`].
+///
+/// This is implemented for all systems, and lets you give them labels and ordering constraints:
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # fn read_input() {}
+/// # fn move_player() {}
+/// # fn move_camera() {}
+/// let mut builder = SystemStagesBuilder::default();
+/// builder
+///     .add_system_to_stage(CoreStage::Update, move_camera.after("movement"))
+///     .add_system_to_stage(CoreStage::Update, move_player.label("movement"))
+///     .add_system_to_stage(CoreStage::Update, read_input.before("movement"));
+///
+/// // The systems will run in the order `read_input`, `move_player`, `move_camera`.
+/// let stages = builder.finish();
+/// ```
+///
+/// Systems in a stage are sorted when the [`SystemStagesBuilder`] is finished. Systems that don't
+/// have any constraints between them keep the order that they were added in, and constraints that
+/// refer to labels that no system in the stage has are ignored.
+pub trait IntoSystemConfig<Args> {
+    /// Convert into a [`SystemConfig`].
+    fn config(self) -> SystemConfig;
+
+    /// Give the system a label, so that other systems can be ordered relative to it.
+    ///
+    /// Multiple systems may have the same label.
+    fn label(self, label: impl Into<SystemLabel>) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.config();
+        config.labels.push(label.into());
+        config
+    }
+
+    /// Run the system before all of the systems in the same stage with the given label.
+    fn before(self, label: impl Into<SystemLabel>) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.config();
+        config.before.push(label.into());
+        config
+    }
+
+    /// Run the system after all of the systems in the same stage with the given label.
+    fn after(self, label: impl Into<SystemLabel>) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.config();
+        config.after.push(label.into());
+        config
+    }
+}
+
+impl IntoSystemConfig<SystemConfig> for SystemConfig {
+    fn config(self) -> SystemConfig {
+        self
+    }
+}
+
+impl<Args, S> IntoSystemConfig<(Args,)> for S
+where
+    S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+{
+    fn config(self) -> SystemConfig {
+        SystemConfig::new(self.system())
+    }
+}
+
+/// Error returned when the systems in a stage cannot be sorted.
+#[derive(Debug, thiserror::Error)]
+pub enum SystemOrderError {
+    /// The ordering constraints of the systems form a cycle.
+    #[error(
+        "The ordering constraints of the systems in stage `{stage}` form a cycle: {}",
+        .systems.join(" -> ")
+    )]
+    Cycle {
+        /// The name of the stage that contains the systems.
+        stage: String,
+        /// The names of the systems in the cycle, starting and ending with the same system.
+        systems: Vec<String>,
+    },
+}
+
+/// Sort the systems so that all of their ordering constraints are met, keeping the systems in the
+/// order they were added in where there are no constraints.
+///
+/// Each system is returned with the IDs of the systems that it must run after, which always come
+/// before it in the sorted list.
+pub(super) fn sort_systems(
+    stage: &str,
+    configs: Vec<SystemConfig>,
+) -> Result<Vec<(StaticSystem<(), ()>, Vec<SystemId>)>, SystemOrderError> {
+    // Build the graph, with an edge from each system to the systems that must run after it.
+    let mut labelled: HashMap<&SystemLabel, Vec<usize>> = HashMap::default();
+    for (i, config) in configs.iter().enumerate() {
+        for label in &config.labels {
+            labelled.entry(label).or_default().push(i);
+        }
+    }
+    let mut edges = vec![Vec::new(); configs.len()];
+    for (i, config) in configs.iter().enumerate() {
+        for label in &config.before {
+            for &j in labelled.get(label).into_iter().flatten() {
+                edges[i].push(j);
+            }
+        }
+        for label in &config.after {
+            for &j in labelled.get(label).into_iter().flatten() {
+                edges[j].push(i);
+            }
+        }
+    }
+
+    let mut dependencies = vec![0usize; configs.len()];
+    for &j in edges.iter().flatten() {
+        dependencies[j] += 1;
+    }
+
+    // Repeatedly take the first system, in insertion order, that has no remaining dependencies.
+    let mut order = Vec::with_capacity(configs.len());
+    let mut sorted = vec![false; configs.len()];
+    while let Some(i) = (0..configs.len()).find(|&i| !sorted[i] && dependencies[i] == 0) {
+        sorted[i] = true;
+        order.push(i);
+        for &j in &edges[i] {
+            dependencies[j] -= 1;
+        }
+    }
+
+    if order.len() < configs.len() {
+        let cycle = find_cycle(&edges, &sorted);
+        return Err(SystemOrderError::Cycle {
+            stage: stage.into(),
+            systems: cycle
+                .into_iter()
+                .map(|i| configs[i].system.name.to_string())
+                .collect(),
+        });
+    }
+
+    let mut dependencies = vec![Vec::new(); configs.len()];
+    for (i, targets) in edges.iter().enumerate() {
+        for &j in targets {
+            dependencies[j].push(configs[i].system.id);
+        }
+    }
+
+    let mut systems = configs.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(order
+        .into_iter()
+        .map(|i| {
+            let system = systems[i].take().unwrap().system;
+            (system, std::mem::take(&mut dependencies[i]))
+        })
+        .collect())
+}
+
+/// Find a cycle among the systems that could not be sorted.
+///
+/// Every unsorted system depends on another unsorted system, so walking backwards from any of them
+/// must eventually revisit a system.
+fn find_cycle(edges: &[Vec<usize>], sorted: &[bool]) -> Vec<usize> {
+    let mut dependency_of = vec![None; edges.len()];
+    for (i, targets) in edges.iter().enumerate() {
+        if sorted[i] {
+            continue;
+        }
+        for &j in targets {
+            dependency_of[j].get_or_insert(i);
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut current = sorted.iter().position(|&done| !done).unwrap();
+    while !path.contains(&current) {
+        path.push(current);
+        current = dependency_of[current].unwrap();
+    }
+
+    // Keep only the cycle, and put it in the order that the systems would need to run in.
+    let start = path.iter().position(|&i| i == current).unwrap();
+    let mut cycle = path.split_off(start);
+    cycle.reverse();
+    cycle.push(cycle[0]);
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+
+    type Log = Arc<Mutex<Vec<&'static str>>>;
+
+    fn logger(log: &Log, name: &'static str) -> StaticSystem<(), ()> {
+        let log = log.clone();
+        (move || log.lock().unwrap().push(name)).system()
+    }
+
+    #[test]
+    fn systems_are_sorted() {
+        let log = Log::default();
+        let mut builder = SystemStagesBuilder::default();
+        builder
+            .add_system_to_stage(CoreStage::Update, logger(&log, "camera").after("movement"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "unordered"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "player").label("movement"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "enemy").label("movement"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "input").before("movement"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "missing").after("missing"));
+
+        let mut stages = builder.finish();
+        stages.run(&mut World::new());
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["unordered", "input", "player", "enemy", "camera", "missing"]
+        );
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let log = Log::default();
+        let mut builder = SystemStagesBuilder::default();
+        builder
+            .add_system_to_stage(CoreStage::Update, logger(&log, "a").label("a").after("c"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "b").label("b").after("a"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "c").label("c").after("b"))
+            .add_system_to_stage(CoreStage::Update, logger(&log, "d").after("a"));
+
+        let Err(SystemOrderError::Cycle { stage, systems }) = builder.try_finish() else {
+            panic!("expected a cycle error");
+        };
+        assert_eq!(stage, "Update");
+        assert_eq!(systems.len(), 4);
+        assert_eq!(systems.first(), systems.last());
+    }
+}
@@ -0,0 +1,307 @@
+//! A system stage that runs systems in parallel.
+
+use bevy_tasks::{ComputeTaskPool, TaskPool};
+
+use crate::prelude::*;
+
+use super::apply_commands;
+
+/// A [`SystemStage`] that runs systems which don't access the same data at the same time.
+///
+/// The systems are split into batches using their [`SystemAccess`]. A system is put in the first
+/// batch that comes after every earlier system in the stage that it is not
+/// [compatible][SystemAccess::is_compatible] with, and after every system that it has to run
+/// after because of its [ordering constraints][IntoSystemConfig]. The systems in each batch are run
+/// concurrently on the [`ComputeTaskPool`]. Since conflicting systems always run in the order they
+/// were added, running the stage has the same effect as running the systems one after another
+/// like [`SimpleSystemStage`] does.
+///
+/// The [run conditions][IntoConditionalSystem::run_if] of the systems in a batch are checked one
+/// after another, in the order the systems were added, before any of the systems in the batch run.
+///
+/// ## Determinism
+///
+/// Systems that run at the same time race to advance the world's
+/// [change tick][World::change_tick], so the ticks they are given may differ from one run to the
+/// next. This doesn't affect [change detection][crate::change_detection], but the ticks are part of
+/// the [`World`], which matters for networked sessions that need every peer to produce exactly the
+/// same world.
+///
+/// In [deterministic][ParallelSystemStage::deterministic] mode, the ticks for the systems in a
+/// batch are reserved in order before the batch is run, so the result of running the stage is
+/// identical no matter how the systems are scheduled on the thread pool.
+pub struct ParallelSystemStage {
+    /// The unique identifier for the stage.
+    pub id: Ulid,
+    /// The human-readable name for the stage, used for error messages when something goes wrong.
+    pub name: String,
+    /// Whether or not the stage runs in deterministic mode.
+    pub deterministic: bool,
+    /// The list of systems in the stage.
+    systems: Vec<StaticSystem<(), ()>>,
+    /// The IDs of the systems that each system must run after.
+    dependencies: Vec<Vec<SystemId>>,
+    /// The indices of the systems in each batch, computed the first time the stage is run after
+    /// its systems are changed.
+    batches: Option<Vec<Vec<usize>>>,
+}
+
+impl ParallelSystemStage {
+    /// Create a new, empty stage, for the given label.
+    pub fn new<L: StageLabel>(label: L) -> Self {
+        Self {
+            id: label.id(),
+            name: label.name(),
+            deterministic: false,
+            systems: Default::default(),
+            dependencies: Default::default(),
+            batches: None,
+        }
+    }
+
+    /// Set whether or not the stage runs in deterministic mode.
+    ///
+    /// Deterministic mode should be used for stages that run as part of a networked session.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Get the list of systems in the stage.
+    pub fn systems(&self) -> &[StaticSystem<(), ()>] {
+        &self.systems
+    }
+
+    /// Get the indices of the systems in each batch of systems that run at the same time.
+    pub fn batches(&mut self) -> &[Vec<usize>] {
+        self.batches
+            .get_or_insert_with(|| compute_batches(&self.systems, &self.dependencies))
+    }
+}
+
+/// Split the systems into batches of systems that may run at the same time, without changing the
+/// order of systems that conflict with each other or that depend on each other.
+fn compute_batches(
+    systems: &[StaticSystem<(), ()>],
+    dependencies: &[Vec<SystemId>],
+) -> Vec<Vec<usize>> {
+    let access = systems.iter().map(|x| x.access()).collect::<Vec<_>>();
+    let index_of_system = systems
+        .iter()
+        .enumerate()
+        .map(|(i, system)| (system.id, i))
+        .collect::<HashMap<_, _>>();
+    let mut batch_of_system = Vec::with_capacity(systems.len());
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+
+    for (i, system_access) in access.iter().enumerate() {
+        // The system must run after every earlier system that it conflicts with.
+        let batch = access[..i]
+            .iter()
+            .zip(&batch_of_system)
+            .filter(|(other, _)| !system_access.is_compatible(other))
+            .map(|(_, batch)| batch + 1)
+            .max()
+            .unwrap_or(0);
+        // And after every system that it depends on.
+        let batch = dependencies[i]
+            .iter()
+            .filter_map(|id| index_of_system.get(id))
+            .filter(|&&j| j < i)
+            .map(|&j| batch_of_system[j] + 1)
+            .fold(batch, usize::max);
+
+        if batch == batches.len() {
+            batches.push(Vec::new());
+        }
+        batches[batch].push(i);
+        batch_of_system.push(batch);
+    }
+
+    batches
+}
+
+impl SystemStage for ParallelSystemStage {
+    fn id(&self) -> Ulid {
+        self.id
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn run(&mut self, world: &World) {
+        let batches = self
+            .batches
+            .get_or_insert_with(|| compute_batches(&self.systems, &self.dependencies));
+        let pool = ComputeTaskPool::init(TaskPool::new);
+
+        for batch in batches.iter() {
+            // Check the run conditions of the systems in order, before any of them run.
+            let batch = batch
+                .iter()
+                .copied()
+                .filter(|&index| self.systems[index].check_conditions(world))
+                .collect::<Vec<_>>();
+
+            // Don't bother with the thread pool if there is nothing to run concurrently.
+            match batch[..] {
+                [] => continue,
+                [index] => {
+                    self.systems[index].run(world, ());
+                    continue;
+                }
+                _ => (),
+            }
+
+            if self.deterministic {
+                for &index in &batch {
+                    world.reserve_system_run(self.systems[index].id);
+                }
+            }
+
+            pool.scope(|scope| {
+                let mut batch = batch.iter().copied().peekable();
+                for (index, system) in self.systems.iter_mut().enumerate() {
+                    if batch.next_if_eq(&index).is_some() {
+                        scope.spawn(async move { system.run(world, ()) });
+                    }
+                }
+            });
+        }
+
+        apply_commands(world);
+    }
+
+    fn add_system(&mut self, system: StaticSystem<(), ()>) {
+        self.add_system_with_dependencies(system, &[]);
+    }
+
+    fn add_system_with_dependencies(
+        &mut self,
+        system: StaticSystem<(), ()>,
+        dependencies: &[SystemId],
+    ) {
+        self.systems.push(system);
+        self.dependencies.push(dependencies.to_vec());
+        self.batches = None;
+    }
+
+    fn remove_all_systems(&mut self) {
+        self.systems.clear();
+        self.dependencies.clear();
+        self.batches = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct A(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct B(u32);
+
+    #[test]
+    fn conflicting_systems_keep_their_order() {
+        let mut stage = ParallelSystemStage::new(CoreStage::Update);
+        stage.add_system((|mut a: ResMutInit<A>| a.0 += 1).system());
+        stage.add_system((|mut b: ResMutInit<B>| b.0 += 1).system());
+        stage.add_system((|a: ResInit<A>, mut b: ResMutInit<B>| b.0 *= a.0 + 1).system());
+        stage.add_system((|_a: Comp<A>, _b: Comp<B>| ()).system());
+        stage.add_system((|_world: &World| ()).system());
+        stage.add_system((|_a: CompMut<A>| ()).system());
+
+        assert_eq!(stage.batches(), &[vec![0, 1, 3], vec![2], vec![4], vec![5]]);
+
+        let world = World::new();
+        stage.run(&world);
+        stage.run(&world);
+        assert_eq!(world.resource::<A>().0, 2);
+        assert_eq!(world.resource::<B>().0, 9);
+    }
+
+    struct Parallel;
+    impl StageLabel for Parallel {
+        fn name(&self) -> String {
+            "Parallel".into()
+        }
+
+        fn id(&self) -> Ulid {
+            Ulid(2166639640220712354794447635636077113)
+        }
+    }
+
+    #[test]
+    fn ordering_constraints_are_kept() {
+        type Log = Arc<Mutex<Vec<&'static str>>>;
+        fn logger(log: &Log, name: &'static str) -> StaticSystem<(), ()> {
+            let log = log.clone();
+            (move || log.lock().unwrap().push(name)).system()
+        }
+
+        let log = Log::default();
+        let mut builder = SystemStagesBuilder::default();
+        builder
+            .insert_stage_after(CoreStage::Update, ParallelSystemStage::new(Parallel))
+            .add_system_to_stage(Parallel, logger(&log, "camera").after("movement"))
+            .add_system_to_stage(Parallel, logger(&log, "player").label("movement"))
+            .add_system_to_stage(Parallel, logger(&log, "unordered"))
+            .add_system_to_stage(Parallel, logger(&log, "input").before("movement"));
+        let mut stages = builder.finish();
+
+        let mut world = World::new();
+        for _ in 0..16 {
+            stages.run(&mut world);
+            let log = std::mem::take(&mut *log.lock().unwrap());
+            let position = |name| log.iter().position(|x| *x == name).unwrap();
+            assert_eq!(log.len(), 4);
+            assert!(position("input") < position("player"));
+            assert!(position("player") < position("camera"));
+        }
+
+        // The systems don't conflict, so only their ordering constraints keep them apart.
+        let mut stage = ParallelSystemStage::new(Parallel);
+        let input = logger(&log, "input");
+        let player = logger(&log, "player");
+        let dependencies = [input.id];
+        stage.add_system(input);
+        stage.add_system(logger(&log, "unordered"));
+        stage.add_system_with_dependencies(player, &dependencies);
+        assert_eq!(stage.batches(), &[vec![0, 1], vec![2]]);
+    }
+
+    #[test]
+    fn deterministic_ticks() {
+        let world = World::new();
+        let start = world.change_tick();
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stage = ParallelSystemStage::new(CoreStage::Update).deterministic(true);
+        for i in 0..8 {
+            let ticks = ticks.clone();
+            stage.add_system(
+                (move |system_ticks: SystemTicks| {
+                    ticks.lock().unwrap().push((i, system_ticks.this_run));
+                })
+                .system(),
+            );
+        }
+        assert_eq!(stage.batches().len(), 1);
+
+        stage.run(&world);
+
+        // Every system gets the tick it would have gotten if the systems were run in order.
+        let mut ticks = ticks.lock().unwrap().clone();
+        ticks.sort();
+        for (i, tick) in ticks {
+            assert_eq!(tick, Tick(start.0 + i));
+        }
+    }
+}
@@ -1,15 +1,156 @@
 //! Implements the system API for the ECS.
 
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 use crate::prelude::*;
 
+mod condition;
+mod pipe;
+pub use condition::*;
+pub use pipe::*;
+
 /// Trait implemented by systems.
 pub trait System<In, Out> {
     /// Run the system.
     fn run(&mut self, world: &World, input: In) -> Out;
     /// Get a best-effort name for the system, used in diagnostics.
     fn name(&self) -> &str;
+    /// Get the unique ID of the system, if it has one.
+    ///
+    /// Systems with an ID have the tick that they last ran at tracked in the [`World`], for
+    /// [change detection][crate::change_detection].
+    fn id(&self) -> Option<SystemId> {
+        None
+    }
+    /// Get the data in the [`World`] that the system accesses.
+    ///
+    /// This is used to decide which systems may run at the same time. The default implementation
+    /// returns [`SystemAccess::exclusive()`], so that the system never runs alongside another.
+    fn access(&self) -> SystemAccess {
+        SystemAccess::exclusive()
+    }
+}
+
+/// A unique identifier for a system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemId(u64);
+
+impl SystemId {
+    /// Create a new, unique system ID.
+    pub fn unique() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Information about the system that a [`SystemParam`] is being created for.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemMeta {
+    /// The unique ID of the system.
+    pub id: SystemId,
+    /// A best-effort name for the system, for diagnostic purposes.
+    pub name: &'static str,
+    /// The change ticks for this run of the system.
+    pub ticks: SystemTicks,
+}
+
+impl SystemMeta {
+    /// Start a run of the system with the given ID and name, advancing the world's change tick.
+    pub fn begin_run(world: &World, id: SystemId, name: &'static str) -> Self {
+        Self {
+            id,
+            name,
+            ticks: world.begin_system_run(id),
+        }
+    }
+}
+
+/// The set of resources and components that a [`System`] or [`SystemParam`] reads and writes.
+///
+/// Two systems that have [compatible][SystemAccess::is_compatible] access sets don't touch any of
+/// the same data mutably, and may be run at the same time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemAccess {
+    /// Whether the system may access anything in the world.
+    exclusive: bool,
+    resources_read: HashSet<SchemaId>,
+    resources_written: HashSet<SchemaId>,
+    components_read: HashSet<SchemaId>,
+    components_written: HashSet<SchemaId>,
+}
+
+impl SystemAccess {
+    /// Create an access set that doesn't access anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an access set that may access anything in the world.
+    pub fn exclusive() -> Self {
+        Self {
+            exclusive: true,
+            ..default()
+        }
+    }
+
+    /// Mark this access set as possibly accessing anything in the world.
+    pub fn set_exclusive(&mut self) {
+        self.exclusive = true;
+    }
+
+    /// Returns whether this access set may access anything in the world.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Record read access to the resource with the given schema.
+    pub fn add_resource_read(&mut self, schema: &'static Schema) {
+        self.resources_read.insert(schema.id());
+    }
+
+    /// Record write access to the resource with the given schema.
+    pub fn add_resource_write(&mut self, schema: &'static Schema) {
+        self.resources_written.insert(schema.id());
+    }
+
+    /// Record read access to the components with the given schema.
+    pub fn add_component_read(&mut self, schema: &'static Schema) {
+        self.components_read.insert(schema.id());
+    }
+
+    /// Record write access to the components with the given schema.
+    pub fn add_component_write(&mut self, schema: &'static Schema) {
+        self.components_written.insert(schema.id());
+    }
+
+    /// Add all of the access in `other` to this access set.
+    pub fn extend(&mut self, other: &SystemAccess) {
+        self.exclusive |= other.exclusive;
+        self.resources_read.extend(&other.resources_read);
+        self.resources_written.extend(&other.resources_written);
+        self.components_read.extend(&other.components_read);
+        self.components_written.extend(&other.components_written);
+    }
+
+    /// Returns whether the systems with these access sets may run at the same time.
+    ///
+    /// This is the case when neither of them is exclusive, and neither of them writes to data that
+    /// the other reads or writes.
+    pub fn is_compatible(&self, other: &SystemAccess) -> bool {
+        if self.exclusive || other.exclusive {
+            return false;
+        }
+        let writes_are_disjoint = |a: &SystemAccess, b: &SystemAccess| {
+            a.resources_written.is_disjoint(&b.resources_read)
+                && a.resources_written.is_disjoint(&b.resources_written)
+                && a.components_written.is_disjoint(&b.components_read)
+                && a.components_written.is_disjoint(&b.components_written)
+        };
+        writes_are_disjoint(self, other) && writes_are_disjoint(other, self)
+    }
 }
 
 /// Struct containing a static system.
@@ -18,6 +159,31 @@ pub struct StaticSystem<In, Out> {
     pub run: Box<dyn FnMut(&World, In) -> Out + Send + Sync>,
     /// A best-effort name for the system, for diagnostic purposes.
     pub name: &'static str,
+    /// The unique ID of the system.
+    pub id: SystemId,
+    /// The data that the system accesses.
+    ///
+    /// This includes the data accessed by the system's run conditions.
+    pub access: SystemAccess,
+    /// The [run conditions][IntoConditionalSystem::run_if] of the system.
+    ///
+    /// These are checked by the [`SystemStage`] before running the system. Running the system
+    /// directly with [`System::run()`] ignores them.
+    pub conditions: Vec<StaticSystem<(), bool>>,
+}
+
+impl<In, Out> StaticSystem<In, Out> {
+    /// Run all of the system's run conditions, and return whether or not the system should run.
+    ///
+    /// Every condition is run, even if an earlier one returns `false`, so that conditions that use
+    /// [change detection][crate::change_detection] or read events stay up-to-date.
+    pub fn check_conditions(&mut self, world: &World) -> bool {
+        self.conditions
+            .iter_mut()
+            .fold(true, |should_run, condition| {
+                condition.run(world, ()) && should_run
+            })
+    }
 }
 
 impl<In, Out> System<In, Out> for StaticSystem<In, Out> {
@@ -27,6 +193,28 @@ impl<In, Out> System<In, Out> for StaticSystem<In, Out> {
     fn name(&self) -> &str {
         self.name
     }
+    fn id(&self) -> Option<SystemId> {
+        Some(self.id)
+    }
+    fn access(&self) -> SystemAccess {
+        self.access.clone()
+    }
+}
+
+/// Run a system as part of another system, giving it the ticks of the outer system's run.
+///
+/// The inner system's ticks are not tracked in the [`World`], so that the outer system's tick can
+/// be [reserved][World::reserve_system_run] and [forgotten][World::forget_system] for both of them.
+pub(crate) fn run_inner_system<In, Out>(
+    world: &World,
+    ticks: SystemTicks,
+    system: &mut StaticSystem<In, Out>,
+    input: In,
+) -> Out {
+    world.ticks.lend_system_run(system.id, ticks);
+    let output = system.run(world, input);
+    world.ticks.end_lent_system_run(system.id);
+    output
 }
 
 /// Converts a function into a [`System`].
@@ -83,6 +271,25 @@ pub trait SystemParam: Sized {
     /// This state will be created immediately before the system is run, and will kept alive until
     /// the system is done running.
     fn get_state(world: &World) -> Self::State;
+    /// This is called to produce the intermediate state of the system parameter for a specific
+    /// run of a system.
+    ///
+    /// The default implementation calls [`get_state()`][Self::get_state]. Parameters that need to
+    /// know about the running system, such as the [change detection][crate::change_detection]
+    /// parameters, can override this to make use of the system's [`SystemMeta`].
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        let _ = meta;
+        Self::get_state(world)
+    }
+    /// This is called to record the data that the parameter accesses in the system's
+    /// [`SystemAccess`].
+    ///
+    /// The default implementation marks the access as [exclusive][SystemAccess::set_exclusive], so
+    /// that systems using the parameter never run at the same time as other systems. Parameters
+    /// should override this if they know exactly what they access.
+    fn access(access: &mut SystemAccess) {
+        access.set_exclusive();
+    }
     /// This is used create an instance of the system parame, possibly borrowed from the
     /// intermediate parameter state.
     #[allow(clippy::needless_lifetimes)] // Explicit lifetimes help clarity in this case
@@ -105,11 +312,15 @@ pub struct In<T>(pub T);
 /// [`SystemParam`] for getting read access to a resource.
 ///
 /// Use [`ResInit`] if you want to automatically initialize the resource.
-pub struct Res<'a, T: HasSchema>(Ref<'a, T>);
+pub struct Res<'a, T: HasSchema> {
+    data: Ref<'a, T>,
+    ticks: ComponentTicks,
+    system_ticks: SystemTicks,
+}
 impl<'a, T: HasSchema> std::ops::Deref for Res<'a, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.data
     }
 }
 
@@ -117,122 +328,254 @@ impl<'a, T: HasSchema> std::ops::Deref for Res<'a, T> {
 /// exist.
 ///
 /// Use [`Res`] if you don't want to automatically initialize the resource.
-pub struct ResInit<'a, T: HasSchema + FromWorld>(Ref<'a, T>);
+pub struct ResInit<'a, T: HasSchema + FromWorld> {
+    data: Ref<'a, T>,
+    ticks: ComponentTicks,
+    system_ticks: SystemTicks,
+}
 impl<'a, T: HasSchema + FromWorld> std::ops::Deref for ResInit<'a, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.data
     }
 }
 
 /// [`SystemParam`] for getting mutable access to a resource.
 ///
+/// Mutably dereferencing the resource marks it as changed.
+///
 /// Use [`ResMutInit`] if you want to automatically initialize the resource.
-pub struct ResMut<'a, T: HasSchema>(RefMut<'a, T>);
+pub struct ResMut<'a, T: HasSchema> {
+    data: RefMut<'a, T>,
+    ticks: &'a ResourceTicks,
+    system_ticks: SystemTicks,
+}
 impl<'a, T: HasSchema> std::ops::Deref for ResMut<'a, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.data
     }
 }
 impl<'a, T: HasSchema> std::ops::DerefMut for ResMut<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        self.ticks.set_changed(self.system_ticks.this_run);
+        &mut self.data
     }
 }
 
 /// [`SystemParam`] for getting mutable access to a resource and initializing it if it doesn't
 /// already exist.
 ///
+/// Mutably dereferencing the resource marks it as changed.
+///
 /// Use [`ResMut`] if you don't want to automatically initialize the resource.
-pub struct ResMutInit<'a, T: HasSchema + FromWorld>(RefMut<'a, T>);
+pub struct ResMutInit<'a, T: HasSchema + FromWorld> {
+    data: RefMut<'a, T>,
+    ticks: &'a ResourceTicks,
+    system_ticks: SystemTicks,
+}
 impl<'a, T: HasSchema + FromWorld> std::ops::Deref for ResMutInit<'a, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.data
     }
 }
 impl<'a, T: HasSchema + FromWorld> std::ops::DerefMut for ResMutInit<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        self.ticks.set_changed(self.system_ticks.this_run);
+        &mut self.data
+    }
+}
+
+impl<'a, T: HasSchema> Res<'a, T> {
+    /// Returns whether or not the resource was added since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.ticks.is_added(self.system_ticks)
+    }
+
+    /// Returns whether or not the resource was added or changed since the system last ran.
+    pub fn is_changed(&self) -> bool {
+        self.ticks.is_changed(self.system_ticks)
+    }
+}
+
+impl<'a, T: HasSchema> ResMut<'a, T> {
+    /// Returns whether or not the resource was added since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.ticks.get().is_added(self.system_ticks)
+    }
+
+    /// Returns whether or not the resource was added or changed since the system last ran.
+    pub fn is_changed(&self) -> bool {
+        self.ticks.get().is_changed(self.system_ticks)
+    }
+}
+
+impl<'a, T: HasSchema + FromWorld> ResInit<'a, T> {
+    /// Returns whether or not the resource was added since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.ticks.is_added(self.system_ticks)
+    }
+
+    /// Returns whether or not the resource was added or changed since the system last ran.
+    pub fn is_changed(&self) -> bool {
+        self.ticks.is_changed(self.system_ticks)
+    }
+}
+
+impl<'a, T: HasSchema + FromWorld> ResMutInit<'a, T> {
+    /// Returns whether or not the resource was added since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.ticks.get().is_added(self.system_ticks)
+    }
+
+    /// Returns whether or not the resource was added or changed since the system last ran.
+    pub fn is_changed(&self) -> bool {
+        self.ticks.get().is_changed(self.system_ticks)
     }
 }
 
 impl<'a, T: HasSchema> SystemParam for Res<'a, T> {
-    type State = AtomicResource<T>;
+    type State = (AtomicResource<T>, SystemTicks);
     type Param<'p> = Res<'p, T>;
 
     fn get_state(world: &World) -> Self::State {
-        world.resources.get_cell::<T>()
+        (
+            world.resources.get_cell::<T>(),
+            SystemTicks::untracked(world),
+        )
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        (world.resources.get_cell::<T>(), meta.ticks)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_read(T::schema());
     }
 
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        Res(state.borrow().unwrap_or_else(|| {
-            panic!(
-                "Resource of type `{}` not in world. \
-                You may need to insert or initialize the resource or use \
-                `ResInit` instead of `Res` to automatically initialize the \
-                resource with the default value.",
-                std::any::type_name::<T>()
-            )
-        }))
+        let (cell, system_ticks) = state;
+        Res {
+            data: cell.borrow().unwrap_or_else(|| {
+                panic!(
+                    "Resource of type `{}` not in world. \
+                    You may need to insert or initialize the resource or use \
+                    `ResInit` instead of `Res` to automatically initialize the \
+                    resource with the default value.",
+                    std::any::type_name::<T>()
+                )
+            }),
+            ticks: cell.ticks().get(),
+            system_ticks: *system_ticks,
+        }
     }
 }
 
 impl<'a, T: HasSchema + FromWorld> SystemParam for ResInit<'a, T> {
-    type State = AtomicResource<T>;
+    type State = (AtomicResource<T>, SystemTicks);
     type Param<'p> = ResInit<'p, T>;
 
     fn get_state(world: &World) -> Self::State {
         let cell = world.resources.get_cell::<T>();
         cell.init(world);
-        cell
+        (cell, SystemTicks::untracked(world))
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        let cell = world.resources.get_cell::<T>();
+        cell.init(world);
+        (cell, meta.ticks)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        // The resource may be inserted when the state is created, which requires write access.
+        access.add_resource_write(T::schema());
     }
 
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        ResInit(state.borrow().unwrap())
+        let (cell, system_ticks) = state;
+        ResInit {
+            data: cell.borrow().unwrap(),
+            ticks: cell.ticks().get(),
+            system_ticks: *system_ticks,
+        }
     }
 }
 
 impl<'a, T: HasSchema> SystemParam for ResMut<'a, T> {
-    type State = AtomicResource<T>;
+    type State = (AtomicResource<T>, SystemTicks);
     type Param<'p> = ResMut<'p, T>;
 
     fn get_state(world: &World) -> Self::State {
-        world.resources.get_cell::<T>()
+        (
+            world.resources.get_cell::<T>(),
+            SystemTicks::untracked(world),
+        )
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        (world.resources.get_cell::<T>(), meta.ticks)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_write(T::schema());
     }
 
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        ResMut(state.borrow_mut().unwrap_or_else(|| {
-            panic!(
-                "Resource of type `{}` not in world. \
-                You may need to insert or initialize the resource or use \
-                `ResMutInit` instead of `ResMut` to automatically initialize the \
-                resource with the default value.",
-                std::any::type_name::<T>()
-            )
-        }))
+        let (cell, system_ticks) = state;
+        ResMut {
+            data: cell.borrow_mut().unwrap_or_else(|| {
+                panic!(
+                    "Resource of type `{}` not in world. \
+                    You may need to insert or initialize the resource or use \
+                    `ResMutInit` instead of `ResMut` to automatically initialize the \
+                    resource with the default value.",
+                    std::any::type_name::<T>()
+                )
+            }),
+            ticks: cell.ticks(),
+            system_ticks: *system_ticks,
+        }
     }
 }
 
 impl<'a, T: HasSchema + FromWorld> SystemParam for ResMutInit<'a, T> {
-    type State = AtomicResource<T>;
+    type State = (AtomicResource<T>, SystemTicks);
     type Param<'p> = ResMutInit<'p, T>;
 
     fn get_state(world: &World) -> Self::State {
         let cell = world.resources.get_cell::<T>();
         cell.init(world);
-        cell
+        (cell, SystemTicks::untracked(world))
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        let cell = world.resources.get_cell::<T>();
+        cell.init(world);
+        (cell, meta.ticks)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_write(T::schema());
     }
 
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        ResMutInit(state.borrow_mut().unwrap())
+        let (cell, system_ticks) = state;
+        ResMutInit {
+            data: cell.borrow_mut().unwrap(),
+            ticks: cell.ticks(),
+            system_ticks: *system_ticks,
+        }
     }
 }
 
 /// [`SystemParam`] for getting read access to a [`ComponentStore`].
 pub type Comp<'a, T> = Ref<'a, ComponentStore<T>>;
 /// [`SystemParam`] for getting mutable access to a [`ComponentStore`].
+///
+/// Components that are inserted or mutably accessed through the store are marked as added or
+/// changed for [change detection][crate::change_detection].
 pub type CompMut<'a, T> = RefMut<'a, ComponentStore<T>>;
 
 impl<'a, T: HasSchema> SystemParam for Comp<'a, T> {
@@ -243,21 +586,35 @@ impl<'a, T: HasSchema> SystemParam for Comp<'a, T> {
         world.components.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.add_component_read(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         state.borrow()
     }
 }
 
 impl<'a, T: HasSchema> SystemParam for CompMut<'a, T> {
-    type State = Arc<AtomicCell<ComponentStore<T>>>;
+    type State = (Arc<AtomicCell<ComponentStore<T>>>, Tick);
     type Param<'p> = CompMut<'p, T>;
 
     fn get_state(world: &World) -> Self::State {
-        world.components.get_cell::<T>()
+        (world.components.get_cell::<T>(), world.change_tick())
+    }
+
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        (world.components.get_cell::<T>(), meta.ticks.this_run)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.add_component_write(T::schema());
     }
 
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        state.borrow_mut()
+        let mut store = state.0.borrow_mut();
+        store.set_change_tick(state.1);
+        store
     }
 }
 
@@ -279,6 +636,14 @@ pub trait OptionalSystemParam: SystemParam {
     /// This state will be created immediately before the system is run, and will kept alive until
     /// the system is done running.
     fn try_get_state(world: &World) -> Option<Self::State>;
+    /// This is called to produce the intermediate state of the system parameter for a specific
+    /// run of a system.
+    ///
+    /// The default implementation calls [`try_get_state()`][Self::try_get_state].
+    fn try_get_state_for_system(world: &World, meta: &SystemMeta) -> Option<Self::State> {
+        let _ = meta;
+        Self::try_get_state(world)
+    }
     /// This is used create an instance of the system parame, possibly borrowed from the
     /// intermediate parameter state.
     #[allow(clippy::needless_lifetimes)] // Explicit lifetimes help clarity in this case
@@ -291,6 +656,13 @@ impl<T: OptionalSystemParam> SystemParam for Option<T> {
     fn get_state(world: &World) -> Self::State {
         T::try_get_state(world)
     }
+    fn get_state_for_system(world: &World, meta: &SystemMeta) -> Self::State {
+        T::try_get_state_for_system(world, meta)
+    }
+    fn access(access: &mut SystemAccess) {
+        T::access(access)
+    }
+
     fn borrow<'s>(world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         state.as_mut().and_then(|state| T::try_borrow(world, state))
     }
@@ -298,19 +670,36 @@ impl<T: OptionalSystemParam> SystemParam for Option<T> {
 
 impl<'a, T: HasSchema> OptionalSystemParam for Res<'a, T> {
     fn try_get_state(world: &World) -> Option<Self::State> {
-        Some(world.resources.get_cell::<T>())
+        Some(Self::get_state(world))
+    }
+    fn try_get_state_for_system(world: &World, meta: &SystemMeta) -> Option<Self::State> {
+        Some(Self::get_state_for_system(world, meta))
     }
     fn try_borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Option<Self::Param<'s>> {
-        state.borrow().map(|x| Res(x))
+        let (cell, system_ticks) = state;
+        let ticks = cell.ticks().get();
+        cell.borrow().map(|data| Res {
+            data,
+            ticks,
+            system_ticks: *system_ticks,
+        })
     }
 }
 
 impl<'a, T: HasSchema> OptionalSystemParam for ResMut<'a, T> {
     fn try_get_state(world: &World) -> Option<Self::State> {
-        Some(world.resources.get_cell::<T>())
+        Some(Self::get_state(world))
+    }
+    fn try_get_state_for_system(world: &World, meta: &SystemMeta) -> Option<Self::State> {
+        Some(Self::get_state_for_system(world, meta))
     }
     fn try_borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Option<Self::Param<'s>> {
-        state.borrow_mut().map(|x| ResMut(x))
+        let (cell, system_ticks) = state;
+        cell.borrow_mut().map(|data| ResMut {
+            data,
+            ticks: cell.ticks(),
+            system_ticks: *system_ticks,
+        })
     }
 }
 
@@ -338,12 +727,23 @@ macro_rules! impl_system {
         {
             type Sys = StaticSystem<(), Out>;
             fn system(mut self) -> Self::Sys {
+                let id = SystemId::unique();
+                let name = std::any::type_name::<F>();
+                #[allow(unused_mut)]
+                let mut access = SystemAccess::new();
+                $(
+                    $args::access(&mut access);
+                )*
                 StaticSystem {
-                    name: std::any::type_name::<F>(),
+                    name,
+                    id,
+                    access,
+                    conditions: Vec::new(),
                     run: Box::new(move |_world, _input| {
+                        let _meta = SystemMeta::begin_run(_world, id, name);
                         $(
                             #[allow(non_snake_case)]
-                            let mut $args = $args::get_state(_world);
+                            let mut $args = $args::get_state_for_system(_world, &_meta);
                         )*
 
                         self(
@@ -386,12 +786,23 @@ macro_rules! impl_system_with_input {
         {
             type Sys = StaticSystem<InT, Out>;
             fn system(mut self) -> Self::Sys {
+                let id = SystemId::unique();
+                let name = std::any::type_name::<F>();
+                #[allow(unused_mut)]
+                let mut access = SystemAccess::new();
+                $(
+                    $args::access(&mut access);
+                )*
                 StaticSystem {
-                    name: std::any::type_name::<F>(),
+                    name,
+                    id,
+                    access,
+                    conditions: Vec::new(),
                     run: Box::new(move |_world, input| {
+                        let _meta = SystemMeta::begin_run(_world, id, name);
                         $(
                             #[allow(non_snake_case)]
-                            let mut $args = $args::get_state(_world);
+                            let mut $args = $args::get_state_for_system(_world, &_meta);
                         )*
 
                         self(
@@ -0,0 +1,264 @@
+//! Run conditions for systems.
+
+use std::any::type_name;
+
+use crate::{prelude::*, system::run_inner_system};
+
+/// Trait for systems that may be used as a [run condition][IntoConditionalSystem::run_if].
+///
+/// This is implemented for all systems that return a `bool`, and allows conditions to be combined.
+/// Combined conditions run the conditions that they are made of with the change ticks of the
+/// combined condition.
+///
+/// [`and()`][Self::and] and [`or()`][Self::or] short-circuit, so the second condition can rely on
+/// the first one, for example to check that a resource exists before reading it. Use
+/// [`and_eager()`][Self::and_eager] and [`or_eager()`][Self::or_eager] when both conditions must
+/// always run, like conditions that use [change detection][crate::change_detection] or read events
+/// and need to stay up-to-date.
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # #[repr(C)]
+/// # struct Paused(bool);
+/// # #[derive(HasSchema, Clone, Default)]
+/// # #[repr(C)]
+/// # struct Online(bool);
+/// fn paused(paused: Res<Paused>) -> bool {
+///     paused.0
+/// }
+///
+/// fn online(online: Res<Online>) -> bool {
+///     online.0
+/// }
+///
+/// fn update_menu() {}
+///
+/// let system = update_menu.run_if(paused.not().or(online));
+/// ```
+pub trait IntoCondition<Args>:
+    IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>> + Sized
+{
+    /// Combine with another condition, creating a condition that is only `true` if both of the
+    /// conditions are `true`.
+    ///
+    /// The other condition is only run if this one returns `true`.
+    fn and<A, C: IntoCondition<A>>(self, other: C) -> StaticSystem<(), bool> {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        combine(type_name::<(Self, C)>(), access, move |world, ticks| {
+            run_inner_system(world, ticks, &mut a, ()) && run_inner_system(world, ticks, &mut b, ())
+        })
+    }
+
+    /// Combine with another condition, creating a condition that is `true` if either of the
+    /// conditions is `true`.
+    ///
+    /// The other condition is only run if this one returns `false`.
+    fn or<A, C: IntoCondition<A>>(self, other: C) -> StaticSystem<(), bool> {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        combine(type_name::<(Self, C)>(), access, move |world, ticks| {
+            run_inner_system(world, ticks, &mut a, ()) || run_inner_system(world, ticks, &mut b, ())
+        })
+    }
+
+    /// Like [`and()`][Self::and], but both conditions are always run, even if this one returns
+    /// `false`.
+    fn and_eager<A, C: IntoCondition<A>>(self, other: C) -> StaticSystem<(), bool> {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        combine(type_name::<(Self, C)>(), access, move |world, ticks| {
+            let a = run_inner_system(world, ticks, &mut a, ());
+            let b = run_inner_system(world, ticks, &mut b, ());
+            a && b
+        })
+    }
+
+    /// Like [`or()`][Self::or], but both conditions are always run, even if this one returns
+    /// `true`.
+    fn or_eager<A, C: IntoCondition<A>>(self, other: C) -> StaticSystem<(), bool> {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        combine(type_name::<(Self, C)>(), access, move |world, ticks| {
+            let a = run_inner_system(world, ticks, &mut a, ());
+            let b = run_inner_system(world, ticks, &mut b, ());
+            a || b
+        })
+    }
+
+    /// Create a condition that is `true` when this condition is `false`.
+    fn not(self) -> StaticSystem<(), bool> {
+        let mut a = self.system();
+        let access = a.access.clone();
+        combine(type_name::<Self>(), access, move |world, ticks| {
+            !run_inner_system(world, ticks, &mut a, ())
+        })
+    }
+}
+
+impl<Args, S> IntoCondition<Args> for S where
+    S: IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>>
+{
+}
+
+/// Create a condition system from a function that runs other condition systems with the ticks of
+/// the created system.
+fn combine(
+    name: &'static str,
+    access: SystemAccess,
+    mut run: impl FnMut(&World, SystemTicks) -> bool + Send + Sync + 'static,
+) -> StaticSystem<(), bool> {
+    let id = SystemId::unique();
+    StaticSystem {
+        run: Box::new(move |world, ()| run(world, world.begin_system_run(id))),
+        name,
+        id,
+        access,
+        conditions: Vec::new(),
+    }
+}
+
+/// Extension trait for adding run conditions to systems.
+pub trait IntoConditionalSystem<Args>:
+    IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>> + Sized
+{
+    /// Only run the system if the given [condition][IntoCondition] returns `true`.
+    ///
+    /// The condition is checked by the [`SystemStage`] every time before it would run the system,
+    /// before any of the system's parameters are borrowed. If this is called multiple times, the
+    /// system only runs if all of its conditions return `true`.
+    fn run_if<A, C: IntoCondition<A>>(self, condition: C) -> StaticSystem<(), ()> {
+        let mut system = self.system();
+        let condition = condition.system();
+        system.access.extend(&condition.access);
+        system.conditions.push(condition);
+        system
+    }
+}
+
+impl<Args, S> IntoConditionalSystem<Args> for S where
+    S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>
+{
+}
+
+impl SystemConfig {
+    /// Only run the system if the given [condition][IntoCondition] returns `true`.
+    ///
+    /// See [`IntoConditionalSystem::run_if()`].
+    pub fn run_if<A, C: IntoCondition<A>>(mut self, condition: C) -> Self {
+        self.system = self.system.run_if(condition);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Counter(u32);
+
+    fn is_even(counter: Res<Counter>) -> bool {
+        counter.0 % 2 == 0
+    }
+
+    fn is_small(counter: Res<Counter>) -> bool {
+        counter.0 < 4
+    }
+
+    #[test]
+    fn run_conditions() {
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system((|mut counter: ResMut<Counter>| counter.0 += 1).system());
+        stage.add_system(
+            (|mut log: ResMutInit<SVec<u32>>, counter: Res<Counter>| log.push(counter.0)).run_if(
+                is_even
+                    .and(is_small.not())
+                    .or(|counter: Res<Counter>| counter.0 == 1),
+            ),
+        );
+
+        let world = World::new();
+        world.insert_resource(Counter(0));
+        for _ in 0..8 {
+            stage.run(&world);
+        }
+
+        let log = world.resource::<SVec<u32>>();
+        assert_eq!(log.iter().copied().collect::<Vec<_>>(), [1, 4, 6, 8]);
+    }
+
+    #[test]
+    fn combined_conditions_short_circuit() {
+        #[derive(HasSchema, Clone, Default)]
+        #[repr(C)]
+        struct Calls(u32);
+
+        fn counted(mut calls: ResMutInit<Calls>) -> bool {
+            calls.0 += 1;
+            true
+        }
+
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system((|| ()).run_if((|| false).and(counted)));
+        stage.add_system((|| ()).run_if((|| true).or(counted)));
+        stage.add_system((|| ()).run_if((|| true).and(counted)));
+
+        let world = World::new();
+        stage.run(&world);
+        stage.run(&world);
+        assert_eq!(world.resource::<Calls>().0, 2);
+
+        // The eager combinators always run both sides.
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system((|| ()).run_if((|| false).and_eager(counted)));
+        stage.add_system((|| ()).run_if((|| true).or_eager(counted)));
+        stage.run(&world);
+        assert_eq!(world.resource::<Calls>().0, 4);
+    }
+
+    #[test]
+    fn guard_conditions() {
+        #[derive(HasSchema, Clone, Default)]
+        #[repr(C)]
+        struct Runs(u32);
+
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system(
+            (|mut runs: ResMutInit<Runs>| runs.0 += 1).run_if(
+                (|counter: Option<Res<Counter>>| counter.is_some())
+                    .and(|counter: Res<Counter>| counter.0 > 0),
+            ),
+        );
+
+        // The second condition would panic if it was run without the counter.
+        let world = World::new();
+        stage.run(&world);
+        assert!(world.get_resource::<Runs>().is_none());
+
+        world.insert_resource(Counter(1));
+        stage.run(&world);
+        assert_eq!(world.resource::<Runs>().0, 1);
+    }
+
+    #[test]
+    fn conditions_are_checked_before_borrowing() {
+        fn needs_counter(_counter: Res<Counter>) {
+            unreachable!("the counter resource doesn't exist");
+        }
+
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system(needs_counter.run_if(|counter: Option<Res<Counter>>| counter.is_some()));
+        stage.run(&World::new());
+    }
+}
@@ -0,0 +1,245 @@
+//! Piping the output of a system into another system, and handling systems that return errors.
+
+use std::{any::type_name, fmt::Debug};
+
+use crate::{prelude::*, system::run_inner_system};
+
+/// Extension trait for [piping][IntoPipeSystem::pipe] the output of a system into another system.
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # #[repr(C)]
+/// # struct Score(u32);
+/// fn score(score: Res<Score>) -> u32 {
+///     score.0
+/// }
+///
+/// fn double(In(score): In<u32>) -> u32 {
+///     score * 2
+/// }
+///
+/// let world = World::new();
+/// world.insert_resource(Score(3));
+/// assert_eq!(world.run_system(score.pipe(double), ()), 6);
+/// ```
+pub trait IntoPipeSystem<Args, In, Out>:
+    IntoSystem<Args, In, Out, Sys = StaticSystem<In, Out>> + Sized
+{
+    /// Create a system that runs this system, and then runs the other system with the output of
+    /// this system as its [`In`][crate::system::In] input.
+    ///
+    /// The created system accesses the data of both systems, and has the
+    /// [run conditions][IntoConditionalSystem::run_if] of both of them. Both systems run with the
+    /// change ticks of the created system.
+    fn pipe<A, Out2, S>(self, other: S) -> StaticSystem<In, Out2>
+    where
+        S: IntoSystem<A, Out, Out2, Sys = StaticSystem<Out, Out2>>,
+        In: 'static,
+        Out: 'static,
+        Out2: 'static,
+    {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        let mut conditions = std::mem::take(&mut a.conditions);
+        conditions.append(&mut b.conditions);
+        let id = SystemId::unique();
+        StaticSystem {
+            run: Box::new(move |world, input| {
+                let ticks = world.begin_system_run(id);
+                let output = run_inner_system(world, ticks, &mut a, input);
+                run_inner_system(world, ticks, &mut b, output)
+            }),
+            name: type_name::<(Self, S)>(),
+            id,
+            access,
+            conditions,
+        }
+    }
+}
+
+impl<Args, In, Out, S> IntoPipeSystem<Args, In, Out> for S where
+    S: IntoSystem<Args, In, Out, Sys = StaticSystem<In, Out>>
+{
+}
+
+/// Extension trait for systems that return a [`Result`], so that they can use the `?` operator.
+///
+/// Stages only run systems that return `()`, so the errors must be handled by converting the
+/// system with one of these methods.
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Player;
+/// fn find_player(entities: Res<Entities>, players: Comp<Player>) -> anyhow::Result<()> {
+///     let _player = entities
+///         .iter_with(&players)
+///         .next()
+///         .ok_or_else(|| anyhow::format_err!("Player not found"))?;
+///     // ...
+///     Ok(())
+/// }
+///
+/// let mut stage = SimpleSystemStage::new(CoreStage::Update);
+/// stage.add_system(find_player.log_errors());
+/// ```
+pub trait IntoFallibleSystem<Args, In, E>:
+    IntoSystem<Args, In, Result<(), E>, Sys = StaticSystem<In, Result<(), E>>> + Sized
+where
+    In: 'static,
+    E: Debug + 'static,
+{
+    /// Create a system that logs the errors returned by this system, along with the name of the
+    /// system.
+    fn log_errors(self) -> StaticSystem<In, ()> {
+        map_result(self.system(), |name, error| {
+            tracing::error!("System `{name}` failed: {error:?}");
+        })
+    }
+
+    /// Create a system that panics if this system returns an error.
+    fn unwrap_errors(self) -> StaticSystem<In, ()> {
+        map_result(self.system(), |name, error| {
+            panic!("System `{name}` failed: {error:?}");
+        })
+    }
+}
+
+impl<Args, In, E, S> IntoFallibleSystem<Args, In, E> for S
+where
+    S: IntoSystem<Args, In, Result<(), E>, Sys = StaticSystem<In, Result<(), E>>>,
+    In: 'static,
+    E: Debug + 'static,
+{
+}
+
+/// Create a system that runs a system that returns a [`Result`], and calls `on_error` with the
+/// system's name and the error if it fails.
+fn map_result<In: 'static, E: 'static>(
+    mut system: StaticSystem<In, Result<(), E>>,
+    on_error: fn(&str, E),
+) -> StaticSystem<In, ()> {
+    let name = system.name;
+    let access = system.access.clone();
+    let conditions = std::mem::take(&mut system.conditions);
+    let id = SystemId::unique();
+    StaticSystem {
+        run: Box::new(move |world, input| {
+            let ticks = world.begin_system_run(id);
+            if let Err(error) = run_inner_system(world, ticks, &mut system, input) {
+                on_error(name, error);
+            }
+        }),
+        name,
+        id,
+        access,
+        conditions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Counter(u32);
+
+    fn count(mut counter: ResMutInit<Counter>) -> u32 {
+        counter.0 += 1;
+        counter.0
+    }
+
+    fn check_even(In(count): In<u32>) -> Result<(), String> {
+        if count % 2 == 0 {
+            Ok(())
+        } else {
+            Err(format!("{count} is odd"))
+        }
+    }
+
+    #[test]
+    fn pipe() {
+        let world = World::new();
+        let mut system = count
+            .pipe(|In(count): In<u32>| count * 10)
+            .pipe(|In(count): In<u32>, counter: Res<Counter>| (count, counter.0));
+        assert_eq!(system.run(&world, ()), (10, 1));
+        assert_eq!(system.run(&world, ()), (20, 2));
+        assert!(system.access.is_compatible(&SystemAccess::new()));
+        assert!(!system.access.is_compatible(&{
+            let mut access = SystemAccess::new();
+            access.add_resource_read(Counter::schema());
+            access
+        }));
+    }
+
+    #[test]
+    fn log_errors() {
+        let world = World::new();
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system(count.pipe(check_even).log_errors());
+        stage.run(&world);
+        stage.run(&world);
+        assert_eq!(world.resource::<Counter>().0, 2);
+    }
+
+    #[test]
+    fn deterministic_piped_ticks() {
+        let world = World::new();
+        let start = world.change_tick();
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stage = ParallelSystemStage::new(CoreStage::Update).deterministic(true);
+        for i in 0..8 {
+            let ticks = ticks.clone();
+            stage.add_system(
+                (|system_ticks: SystemTicks| system_ticks.this_run)
+                    .pipe(move |In(first): In<Tick>, system_ticks: SystemTicks| {
+                        ticks
+                            .lock()
+                            .unwrap()
+                            .push((i, first, system_ticks.this_run));
+                    })
+                    .system(),
+            );
+        }
+        assert_eq!(stage.batches().len(), 1);
+
+        stage.run(&world);
+        stage.run(&world);
+
+        // Both piped systems get the tick that the pipe would have gotten if the systems were run
+        // in order.
+        let mut ticks = ticks.lock().unwrap().clone();
+        ticks.sort();
+        for (j, (i, first, second)) in ticks.into_iter().enumerate() {
+            let run = j as u64 % 2;
+            assert_eq!(first, Tick(start.0 + run * 8 + i));
+            assert_eq!(second, first);
+        }
+        assert_eq!(world.change_tick(), Tick(start.0 + 16));
+
+        // Only the pipes' ticks are tracked.
+        assert_eq!(world.ticks.tracked_systems(), 8);
+    }
+
+    #[test]
+    fn run_system_forgets_piped_systems() {
+        let world = World::new();
+        world.run_system(count.pipe(check_even).log_errors(), ());
+        assert_eq!(world.ticks.tracked_systems(), 0);
+    }
+
+    #[test]
+    #[should_panic = "1 is odd"]
+    fn unwrap_errors() {
+        let world = World::new();
+        world.run_system(count.pipe(check_even).unwrap_errors(), ());
+    }
+}
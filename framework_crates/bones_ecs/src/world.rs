@@ -1,6 +1,6 @@
 //! Contains the ECS [`World`].
 
-use crate::prelude::*;
+use crate::{change_detection::WorldTicks, prelude::*};
 
 /// The [`World`] is simply a collection of [`Resources`], and [`ComponentStores`].
 ///
@@ -9,12 +9,17 @@ use crate::prelude::*;
 /// [`World`] is designed to be trivially [`Clone`]ed to allow for snapshotting the world state. The
 /// is especially useful in the context of rollback networking, which requires the ability to
 /// snapshot and restore state.
+///
+/// The world also keeps track of its change tick, and the tick that each system last ran at, which
+/// are used for [change detection][crate::change_detection].
 #[derive(Clone)]
 pub struct World {
     /// Stores the world resources.
     pub resources: Resources,
     /// Stores the world components.
     pub components: ComponentStores,
+    /// Stores the world change ticks.
+    pub(crate) ticks: WorldTicks,
 }
 impl std::fmt::Debug for World {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -32,6 +37,7 @@ impl Default for World {
         Self {
             resources,
             components: Default::default(),
+            ticks: Default::default(),
         }
     }
 }
@@ -54,6 +60,7 @@ impl World {
         World {
             resources,
             components: default(),
+            ticks: default(),
         }
     }
 
@@ -62,21 +69,29 @@ impl World {
     /// This should be called every game frame to cleanup entities that have been killed.
     ///
     /// This will remove the component storage for all killed entities, and allow their slots to be
-    /// re-used for any new entities.
+    /// re-used for any new entities. The [`on_kill`][ComponentHooks::on_kill] hooks of the removed
+    /// components are run, and the commands they add are applied.
     pub fn maintain(&self) {
         let mut entities = self.resource_mut::<Entities>();
+        crate::hierarchy::remove_killed_from_hierarchy(self, entities.killed());
+        // The components of killed entities are removed in the frame that is ending, and the
+        // change tick is advanced so that removals in the next frame can be told apart from them.
+        let change_tick = self.change_tick();
+        let next_frame = self.ticks.advance_change_tick();
         for components in self.components.components.read_only_view().values() {
             let mut components = components.borrow_mut();
+            components.set_change_tick(change_tick);
             let killed = entities.killed();
             for &entity in killed {
-                // Safe: We don't provide an out pointer, so it doesn't overlap the component's
-                // internal storage.
-                unsafe {
-                    components.remove_raw(entity, None);
-                }
+                components.remove_killed(entity);
             }
+            components.update_removed(next_frame);
         }
         entities.clear_killed();
+        drop(entities);
+
+        // Run the commands added by the `on_kill` component hooks.
+        crate::stage::apply_commands(self);
     }
 
     /// Run a system once.
@@ -90,7 +105,47 @@ impl World {
         S::Sys: 'system,
     {
         let mut s = system.system();
-        s.run(self, input)
+        let out = s.run(self, input);
+
+        // The system will never run again, so we don't need to remember when it last ran.
+        if let Some(id) = s.id() {
+            self.forget_system(id);
+        }
+
+        out
+    }
+
+    /// Get the world's current change tick.
+    ///
+    /// This is the tick that will be given to the next system that runs, and that changes made
+    /// outside of systems are recorded at.
+    pub fn change_tick(&self) -> Tick {
+        self.ticks.change_tick()
+    }
+
+    /// Advance the world's change tick for a run of the system with the given ID, and get the
+    /// [`SystemTicks`] for that run.
+    ///
+    /// This is called automatically when running a [`StaticSystem`], and only needs to be called
+    /// when implementing [`System`] manually.
+    pub fn begin_system_run(&self, id: SystemId) -> SystemTicks {
+        self.ticks.begin_system_run(id)
+    }
+
+    /// Reserve the change tick for the next run of the system with the given ID.
+    ///
+    /// When the system next calls [`begin_system_run()`][Self::begin_system_run], it will be given
+    /// the reserved tick instead of the world's current change tick. This is used to give systems
+    /// that run at the same time the same ticks no matter which of them starts first.
+    pub fn reserve_system_run(&self, id: SystemId) {
+        self.ticks.reserve_system_run(id)
+    }
+
+    /// Forget the tick that the system with the given ID last ran at.
+    ///
+    /// If the system is run again, it will behave as if it was running for the first time.
+    pub fn forget_system(&self, id: SystemId) {
+        self.ticks.forget_system(id)
     }
 
     /// Get an entity's components.
@@ -129,18 +184,57 @@ impl World {
         }
     }
 
+    /// Create a new entity with a copy of every component of `entity`.
+    ///
+    /// The components are copied using their schema's `clone_fn`, so the types of the components
+    /// don't need to be known. The [`on_insert`][ComponentHooks::on_insert] hooks of the copies
+    /// are run like they would be for any other insertion.
+    ///
+    /// The [`Children`] of the entity are not copied, since each entity may only have one parent.
+    /// If the entity has a [`Parent`], the clone is added to the parent's children instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the component stores are borrowed, or if one of the entity's components
+    /// has a schema without a `clone_fn`.
+    pub fn clone_entity(&self, entity: Entity) -> Entity {
+        let clone = self.resource_mut::<Entities>().create();
+        let change_tick = self.change_tick();
+        let hierarchy = [Parent::schema().id(), Children::schema().id()];
+        for (id, store) in self.components.components.read_only_view().iter() {
+            if hierarchy.contains(id) {
+                continue;
+            }
+            let mut store = store.borrow_mut();
+            let Some(component) = store.get_ref(entity).map(|c| c.clone_into_box()) else {
+                continue;
+            };
+            store.set_change_tick(change_tick);
+            store.insert_box(clone, component);
+        }
+
+        crate::hierarchy::clone_parent(self, entity, clone);
+        clone
+    }
+
     /// Initialize a resource of type `T` by inserting it's default value.
     pub fn init_resource<R: HasSchema + FromWorld>(&mut self) -> RefMut<'_, R> {
         if unlikely(!self.resources.contains::<R>()) {
             let value = R::from_world(self);
-            self.resources.insert(value);
+            self.insert_resource(value);
         }
         self.resource_mut()
     }
 
     /// Insert a resource.
     pub fn insert_resource<R: HasSchema>(&self, resource: R) -> Option<R> {
-        self.resources.insert(resource)
+        let previous = self.resources.insert(resource);
+        self.resources
+            .untyped()
+            .get(R::schema())
+            .ticks()
+            .set_added(self.change_tick());
+        previous
     }
 
     /// Borrow a resource from the world.
@@ -158,12 +252,14 @@ impl World {
         }
     }
 
-    /// Borrow a resource from the world.
+    /// Mutably borrow a resource from the world.
+    ///
+    /// The resource is marked as changed at the world's current [change tick][Self::change_tick].
     /// # Panics
     /// Panics if the resource does not exist in the store.
     #[track_caller]
     pub fn resource_mut<T: HasSchema>(&self) -> RefMut<'_, T> {
-        match self.resources.get_mut::<T>() {
+        match self.get_resource_mut::<T>() {
             Some(r) => r,
             None => panic!(
                 "Requested resource {} does not exist in the `World`. \
@@ -178,9 +274,17 @@ impl World {
         self.resources.get()
     }
 
-    /// Borrow a resource from the world, if it exists.
+    /// Mutably borrow a resource from the world, if it exists.
+    ///
+    /// The resource is marked as changed at the world's current [change tick][Self::change_tick].
     pub fn get_resource_mut<T: HasSchema>(&self) -> Option<RefMut<'_, T>> {
-        self.resources.get_mut()
+        let resource = self.resources.get_mut()?;
+        self.resources
+            .untyped()
+            .get(T::schema())
+            .ticks()
+            .set_changed(self.change_tick());
+        Some(resource)
     }
 
     /// Borrow a component store from the world.
@@ -192,17 +296,23 @@ impl World {
     }
 
     /// Mutably borrow a component store from the world.
+    ///
+    /// Changes made through the store are recorded at the world's current
+    /// [change tick][Self::change_tick].
     /// # Panics
     /// Panics if the component store does not exist in the world.
     #[track_caller]
     pub fn component_mut<T: HasSchema>(&self) -> RefMut<'_, ComponentStore<T>> {
-        self.components.get::<T>().borrow_mut()
+        let mut store = self.components.get::<T>().borrow_mut();
+        store.set_change_tick(self.change_tick());
+        store
     }
 
     /// Load snapshot of [`World`] into self.
     pub fn load_snapshot(&mut self, snapshot: World) {
         self.components = snapshot.components;
         self.resources = snapshot.resources;
+        self.ticks = snapshot.ticks;
     }
 }
 
@@ -306,6 +416,52 @@ mod tests {
         assert_eq!(i, 2);
     }
 
+    #[test]
+    fn clone_entity() {
+        let world = World::new();
+        world.run_system(setup_world, ());
+        let entities = world.resource::<Entities>().iter().collect::<Vec<_>>();
+
+        let clone = world.clone_entity(entities[1]);
+        assert_ne!(clone, entities[1]);
+        assert_eq!(world.component::<Pos>().get(clone), Some(&Pos(0, 0)));
+        assert_eq!(world.component::<Vel>().get(clone), Some(&Vel(1, 1)));
+        assert_eq!(world.component::<Marker>().get(clone), Some(&Marker));
+
+        // The clone doesn't share its components with the original.
+        world.component_mut::<Pos>().get_mut(clone).unwrap().0 = 5;
+        assert_eq!(world.component::<Pos>().get(entities[1]), Some(&Pos(0, 0)));
+    }
+
+    #[test]
+    fn clone_entity_in_hierarchy() {
+        let world = World::new();
+        let (parent, child, grandchild) = {
+            let mut entities = world.resource_mut::<Entities>();
+            (entities.create(), entities.create(), entities.create())
+        };
+        world.run_system(
+            move |mut hierarchy: Hierarchy| {
+                hierarchy.set_parent(child, parent);
+                hierarchy.set_parent(grandchild, child);
+            },
+            (),
+        );
+
+        let clone = world.clone_entity(child);
+        world.run_system(
+            move |hierarchy: Hierarchy| {
+                // The clone is a sibling of the original, without any children of its own.
+                assert_eq!(hierarchy.parent(clone), Some(parent));
+                assert_eq!(hierarchy.children(parent), &[child, clone]);
+                assert_eq!(hierarchy.children(clone), &[]);
+                assert_eq!(hierarchy.children(child), &[grandchild]);
+                assert_eq!(hierarchy.parent(grandchild), Some(child));
+            },
+            (),
+        );
+    }
+
     #[test]
     fn sanity_check() {
         let world = World::new();
@@ -1,5 +1,7 @@
 //! Implements bones egui debug windows and tools. Requires 'ui' feature flag.
 
+use std::time::Duration;
+
 use crate::prelude::*;
 
 /// Track frame time state synced from bevy frame time diagnostics for bones app.
@@ -51,7 +53,11 @@ pub struct FrameTimeWindowState {
 
 /// If installed, allows opening egui window with [`FrameTimeWindowState`] in [`EguiCtx`] state
 /// to get frame time information.
+///
+/// This also enables the [`SystemDiagnostics`] of the session, so that the window can show the
+/// time taken by each of the session's systems.
 pub fn frame_time_diagnostics_plugin(core: &mut SessionBuilder) {
+    core.init_resource::<SystemDiagnostics>();
     core.stages
         .add_system_to_stage(CoreStage::Last, frame_diagnostic_window);
 }
@@ -60,6 +66,7 @@ pub fn frame_time_diagnostics_plugin(core: &mut SessionBuilder) {
 /// stored in [`EguiCtx`] state.
 pub fn frame_diagnostic_window(
     mut state: Option<ResMut<FrameDiagState>>,
+    mut system_diagnostics: Option<ResMut<SystemDiagnostics>>,
     // localization: Res<Localization>,
     egui_ctx: ResMut<EguiCtx>,
 ) {
@@ -106,8 +113,53 @@ pub fn frame_diagnostic_window(
                         "No frame time data from Bevy diagnostics.",
                     );
                 }
+
+                if let Some(diagnostics) = system_diagnostics.as_mut() {
+                    ui.separator();
+                    if ui.button("Reset System Max").clicked() {
+                        diagnostics.reset();
+                    }
+                    system_diagnostics_table(ui, diagnostics);
+                }
             });
     }
 
     egui_ctx.set_state(window_state);
 }
+
+/// Renders a table with the time taken by each stage and system in the [`SystemDiagnostics`].
+fn system_diagnostics_table(ui: &mut egui::Ui, diagnostics: &SystemDiagnostics) {
+    let ms = |duration: Duration| format!("{:.3}ms", duration.as_secs_f64() * 1000.0);
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for stage in diagnostics.stages() {
+            egui::CollapsingHeader::new(format!(
+                "{} ( {} avg, {} max )",
+                stage.name,
+                ms(stage.timing.average),
+                ms(stage.timing.max),
+            ))
+            .id_source(stage.id)
+            .show(ui, |ui| {
+                egui::Grid::new(stage.id)
+                    .striped(true)
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        ui.strong("System");
+                        ui.strong("Last");
+                        ui.strong("Average");
+                        ui.strong("Max");
+                        ui.end_row();
+
+                        for system in &stage.systems {
+                            ui.monospace(system.name);
+                            ui.monospace(ms(system.timing.last));
+                            ui.monospace(ms(system.timing.average));
+                            ui.monospace(ms(system.timing.max));
+                            ui.end_row();
+                        }
+                    });
+            });
+        }
+    });
+}
@@ -20,8 +20,9 @@ pub use glam;
 /// The prelude.
 pub mod prelude {
     pub use crate::{
-        animation::*, input::prelude::*, params::*, render::prelude::*, storage::*, time::*,
-        utils::*, AssetServerExt, DefaultGamePlugin, DefaultSessionPlugin, ExitBones,
+        animation::*, input::prelude::*, params::*, prefab::*, render::prelude::*, scene::*,
+        storage::*, time::*, utils::*, AssetServerExt, DefaultGamePlugin, DefaultSessionPlugin,
+        ExitBones,
     };
 
     pub use futures_lite::future::Boxed as BoxedFuture;
@@ -52,7 +53,9 @@ pub mod prelude {
 pub mod animation;
 pub mod input;
 pub mod params;
+pub mod prefab;
 pub mod render;
+pub mod scene;
 pub mod storage;
 pub mod time;
 pub mod utils;
@@ -123,6 +126,7 @@ impl AssetServerExt for &mut bones_asset::AssetServer {
         // Register asset schemas
         Image::register_schema();
         Atlas::register_schema();
+        Prefab::register_schema();
 
         #[cfg(feature = "localization")]
         {
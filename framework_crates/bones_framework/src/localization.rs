@@ -111,6 +111,11 @@ impl<T: HasSchema> SystemParam for Localization<'_, T> {
             world.resources.get_cell::<RootLocalizationFieldIdx>(),
         )
     }
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_read(AssetServer::schema());
+        // The field index resource is initialized on first use.
+        access.add_resource_write(RootLocalizationFieldIdx::schema());
+    }
     fn borrow<'s>(
         world: &'s World,
         (asset_server, field_idx): &'s mut Self::State,
@@ -87,6 +87,11 @@ pub const NETWORK_MAX_PREDICTION_WINDOW_DEFAULT: usize = 7;
 /// Amount of frames GGRS will delay local input.
 pub const NETWORK_LOCAL_INPUT_DELAY_DEFAULT: usize = 2;
 
+/// Number of frames between the checksum comparisons GGRS uses to detect de-syncs.
+///
+/// Checksums are computed with [`World::checksum()`].
+pub const NETWORK_DESYNC_DETECTION_INTERVAL_DEFAULT: u32 = 10;
+
 /// Possible errors returned by network loop.
 pub enum NetworkError {
     /// The session was disconnected.
@@ -486,6 +491,9 @@ pub struct GgrsSessionRunner<'a, InputTypes: DenseInputConfig<'a>> {
 
     /// The random seed used for this session
     pub random_seed: u64,
+
+    /// Whether we have already warned about types that were skipped by the world checksum.
+    checksum_skipped_warned: bool,
 }
 
 /// The info required to create a [`GgrsSessionRunner`].
@@ -604,7 +612,10 @@ where
             .with_fps(network_fps)
             .unwrap()
             .with_max_prediction_window(max_prediction)
-            .unwrap();
+            .unwrap()
+            .with_desync_detection_mode(ggrs::DesyncDetection::On {
+                interval: NETWORK_DESYNC_DETECTION_INTERVAL_DEFAULT,
+            });
 
         let local_player_idx = info.player_idx;
         for i in 0..info.player_count {
@@ -636,6 +647,7 @@ where
             local_input_delay,
             local_input_disabled: false,
             random_seed: info.random_seed,
+            checksum_skipped_warned: false,
         }
     }
 }
@@ -779,7 +791,26 @@ where
                         for request in requests {
                             match request {
                                 ggrs::GgrsRequest::SaveGameState { cell, frame } => {
-                                    cell.save(frame, Some(world.clone()), None)
+                                    let checksum = world.checksum();
+                                    if !checksum.skipped.is_empty() && !self.checksum_skipped_warned
+                                    {
+                                        let skipped = checksum
+                                            .skipped
+                                            .iter()
+                                            .map(|schema| schema.full_name.as_str())
+                                            .collect::<Vec<_>>();
+                                        warn!(
+                                            ?skipped,
+                                            "Some types are included in the world checksum, but \
+                                            cannot be hashed and will not be checked for de-syncs"
+                                        );
+                                        self.checksum_skipped_warned = true;
+                                    }
+                                    cell.save(
+                                        frame,
+                                        Some(world.clone()),
+                                        Some(checksum.checksum as u128),
+                                    )
                                 }
                                 ggrs::GgrsRequest::LoadGameState { cell, .. } => {
                                     world.load_snapshot(cell.load().unwrap_or_default());
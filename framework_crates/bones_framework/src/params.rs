@@ -25,6 +25,9 @@ impl<'a, T: HasSchema> SystemParam for Root<'a, T> {
     fn get_state(world: &World) -> Self::State {
         (*world.resources.get::<AssetServer>().unwrap()).clone()
     }
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_read(AssetServer::schema());
+    }
     fn borrow<'s>(_world: &'s World, asset_server: &'s mut Self::State) -> Self::Param<'s> {
         Root(asset_server.root())
     }
@@ -75,6 +78,10 @@ impl<'a, T: HasSchema> SystemParam for Packs<'a, T> {
         (*world.resource::<AssetServer>()).clone()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.add_resource_read(AssetServer::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         Packs {
             asset_server: state.clone(),
@@ -188,6 +195,11 @@ where
         )
     }
 
+    fn access(access: &mut SystemAccess) {
+        Root::<'a, Core>::access(access);
+        Packs::<'a, Pack>::access(access);
+    }
+
     fn borrow<'s>(world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         AllPacksData {
             core_root: Root::<'s, Core>::borrow(world, &mut state.0),
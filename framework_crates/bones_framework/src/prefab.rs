@@ -0,0 +1,149 @@
+//! Prefabs, which are metadata assets describing a set of components to spawn an entity with.
+//!
+//! Prefabs are loaded from `.prefab.yaml` or `.prefab.json` files, containing a map from the names
+//! of the components' schemas to the values of the components:
+//!
+//! ```yaml
+//! Transform:
+//!   translation: [0, 10, 0]
+//! Sprite:
+//!   image: ./player.png
+//! my_game::Health: 100
+//! ```
+//!
+//! Schemas may be referred to by their name or their full name, which includes the module path.
+//! Component fields that are left out use their default values.
+
+use std::fmt;
+
+use serde::de::{Error, MapAccess, Visitor};
+
+use crate::prelude::*;
+
+/// A metadata asset containing a list of components to spawn an entity with.
+///
+/// See the [module documentation][self] for the format of the asset.
+#[derive(HasSchema, Clone, Default)]
+#[type_data(metadata_asset("prefab"))]
+#[type_data(SchemaMetaAssetLoader(prefab_loader))]
+pub struct Prefab {
+    /// The components of the prefab.
+    pub components: Vec<SchemaBox>,
+}
+
+impl Prefab {
+    /// Insert a copy of each of the prefab's components on `entity`.
+    ///
+    /// Components that the entity already has are replaced.
+    pub fn insert(&self, world: &World, entity: Entity) {
+        for component in &self.components {
+            let mut store = world
+                .components
+                .get_by_schema(component.schema())
+                .borrow_mut();
+            store.set_change_tick(world.change_tick());
+            store.insert_box(entity, component.clone());
+        }
+    }
+
+    /// Spawn a new entity with a copy of each of the prefab's components.
+    pub fn spawn(&self, world: &World) -> Entity {
+        let entity = world.resource_mut::<Entities>().create();
+        self.insert(world, entity);
+        entity
+    }
+}
+
+/// Extension trait for spawning [`Prefab`]s with [`Commands`].
+pub trait PrefabCommandsExt {
+    /// Spawn a new entity from the prefab at the end of the current stage.
+    fn spawn_prefab(&mut self, prefab: Handle<Prefab>);
+
+    /// Insert the components of the prefab on `entity` at the end of the current stage.
+    ///
+    /// This may be used with an entity that was just created, to spawn a prefab while knowing
+    /// which entity it is spawned as.
+    fn insert_prefab(&mut self, entity: Entity, prefab: Handle<Prefab>);
+}
+
+impl PrefabCommandsExt for CommandQueue {
+    fn spawn_prefab(&mut self, prefab: Handle<Prefab>) {
+        self.add(move |world: &World| {
+            let prefab = world.resource::<AssetServer>().get(prefab).clone();
+            prefab.spawn(world);
+        });
+    }
+
+    fn insert_prefab(&mut self, entity: Entity, prefab: Handle<Prefab>) {
+        self.add(move |world: &World| {
+            let prefab = world.resource::<AssetServer>().get(prefab).clone();
+            prefab.insert(world, entity);
+        });
+    }
+}
+
+/// Find the registered schema with the given name or full name.
+fn find_schema(name: &str) -> anyhow::Result<&'static Schema> {
+    let mut matches = SCHEMA_REGISTRY
+        .schemas
+        .iter()
+        .filter(|schema| schema.name.as_str() == name || schema.full_name.as_str() == name);
+    let schema = matches
+        .next()
+        .ok_or_else(|| anyhow::format_err!("Schema not found: `{name}`"))?;
+    if matches.next().is_some() {
+        anyhow::bail!("Found multiple schemas matching name `{name}`, use the full name instead");
+    }
+    Ok(schema)
+}
+
+fn prefab_loader(
+    ctx: &mut MetaAssetLoadCtx,
+    ptr: SchemaRefMut<'_>,
+    deserialzer: &mut dyn erased_serde::Deserializer,
+) -> anyhow::Result<()> {
+    deserialzer.deserialize_map(PrefabVisitor {
+        ctx,
+        prefab: ptr.cast_into_mut(),
+    })?;
+
+    Ok(())
+}
+
+struct PrefabVisitor<'a, 'srv> {
+    ctx: &'a mut MetaAssetLoadCtx<'srv>,
+    prefab: &'a mut Prefab,
+}
+
+impl<'a, 'srv, 'de> Visitor<'de> for PrefabVisitor<'a, 'srv> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "a map of component schema names to component values"
+        )
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some(name) = map.next_key::<String>()? {
+            let schema = find_schema(&name).map_err(A::Error::custom)?;
+            if schema.default_fn.is_none() {
+                return Err(A::Error::custom(format!(
+                    "Component `{name}` cannot be used in a prefab because it has no default value"
+                )));
+            }
+            let mut component = SchemaBox::default(schema);
+            map.next_value_seed(SchemaPtrLoadCtx {
+                ctx: &mut *self.ctx,
+                ptr: component.as_mut(),
+            })?;
+            self.prefab.components.push(component);
+        }
+
+        Ok(())
+    }
+}
@@ -27,6 +27,7 @@ pub mod ui;
 pub fn render_plugin(session: &mut SessionBuilder) {
     session
         .install_plugin(sprite::sprite_plugin)
+        .install_plugin(transform::transform_plugin)
         .install_plugin(camera::plugin);
 
     #[cfg(feature = "ui")]
@@ -8,9 +8,6 @@ use crate::prelude::*;
 ///
 /// The entity must also have a [`Transform`] component for the camera to render anything.
 #[derive(Clone, Debug, HasSchema)]
-// TODO: make repr(C) when `Option`s are supported.
-// We don't have `Option` support in `bones_schema` right now.
-// Once we do, we can make this type `#[repr(C)]` instead of `#[schema(opaque)]`.
 #[repr(C)]
 pub struct Camera {
     /// The height of the camera in in-game pixels.
@@ -24,7 +21,7 @@ pub struct Camera {
     /// a portion of the window.
     ///
     /// This can be used, for example, for split screen functionality.
-    pub viewport: Maybe<Viewport>,
+    pub viewport: Option<Viewport>,
     /// Cameras with a higher priority will be rendered on top of cameras with a lower priority.
     pub priority: i32,
 }
@@ -98,7 +95,7 @@ impl Default for Camera {
     fn default() -> Self {
         Self {
             active: true,
-            viewport: Unset,
+            viewport: None,
             priority: 0,
             size: default(),
         }
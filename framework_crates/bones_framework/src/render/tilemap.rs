@@ -16,7 +16,10 @@ pub struct TileLayer {
 }
 
 /// A tilemap tile component.
+///
+/// When a tile entity is killed, it is removed from any [`TileLayer`]s that contain it.
 #[derive(Clone, Debug, HasSchema, Default)]
+#[type_data(ComponentHooks::new().on_kill(remove_killed_tile))]
 #[repr(C)]
 pub struct Tile {
     /// The tile index in the tilemap texture.
@@ -27,6 +30,20 @@ pub struct Tile {
     pub flip_y: bool,
 }
 
+/// [`ComponentHook`] that removes a killed tile from the [`TileLayer`]s.
+fn remove_killed_tile(ctx: ComponentHookCtx) {
+    let tile = ctx.entity;
+    ctx.commands.add(move |mut layers: CompMut<TileLayer>| {
+        for layer in layers.iter_mut() {
+            for slot in &mut layer.tiles {
+                if *slot == Some(tile) {
+                    *slot = None;
+                }
+            }
+        }
+    });
+}
+
 impl TileLayer {
     /// Create a new tile layer
     pub fn new(grid_size: UVec2, tile_size: Vec2, atlas: Handle<Atlas>) -> Self {
@@ -2,10 +2,17 @@
 
 use crate::prelude::*;
 
+/// Transform session plugin.
+pub fn transform_plugin(session: &mut SessionBuilder) {
+    session.add_system_to_stage(CoreStage::Last, propagate_transforms.label("transform"));
+}
+
 /// The main transform component.
 ///
-/// Currently we don't have a hierarchy, and this is therefore a global transform.
-#[derive(Clone, Copy, Debug, HasSchema)]
+/// This is the transform of the entity relative to its [`Parent`], or relative to the world if it
+/// doesn't have a parent. The resulting transform relative to the world is stored in the entity's
+/// [`GlobalTransform`].
+#[derive(Clone, Copy, Debug, PartialEq, HasSchema)]
 #[repr(C)]
 pub struct Transform {
     /// The position of the entity in the world.
@@ -47,4 +54,76 @@ impl Transform {
     pub fn from_scale(scale: Vec3) -> Self {
         Self { scale, ..default() }
     }
+
+    /// Apply this transform to `other`, as if `other` was a child of this transform.
+    pub fn mul_transform(&self, other: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_point(other.translation),
+            rotation: self.rotation * other.rotation,
+            scale: self.scale * other.scale,
+        }
+    }
+
+    /// Transform a point from the local space of this transform.
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.translation + self.rotation * (self.scale * point)
+    }
+}
+
+/// The transform of an entity relative to the world.
+///
+/// This is computed from the [`Transform`] of the entity and all of its ancestors by the
+/// [`propagate_transforms`] system, which is added to the [`CoreStage::Last`] stage with the
+/// `"transform"` label. It is inserted automatically for all entities with a [`Transform`], and
+/// removed from entities that no longer have one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, HasSchema, Deref, DerefMut)]
+#[repr(C)]
+pub struct GlobalTransform(pub Transform);
+
+/// System that updates the [`GlobalTransform`] of every entity with a [`Transform`].
+///
+/// A [`GlobalTransform`] is only modified when its value changes, so that change detection can be
+/// used to find the entities that moved.
+pub fn propagate_transforms(
+    entities: Res<Entities>,
+    transforms: Comp<Transform>,
+    parents: Comp<Parent>,
+    children: Comp<Children>,
+    mut global_transforms: CompMut<GlobalTransform>,
+) {
+    let mut stack = Vec::new();
+    for (root, transform) in entities.iter_with(&transforms) {
+        // Entities whose parent has a transform are updated along with their parent.
+        if let Some(parent) = parents.get(root) {
+            if transforms.get(parent.get()).is_some() {
+                continue;
+            }
+        }
+
+        stack.push((root, *transform));
+        while let Some((entity, global)) = stack.pop() {
+            match global_transforms.get(entity).map(|x| x.0) {
+                Some(current) if current == global => (),
+                Some(_) => global_transforms.get_mut(entity).unwrap().0 = global,
+                None => {
+                    global_transforms.insert(entity, GlobalTransform(global));
+                }
+            }
+            for &child in children.get(entity).map(|x| &x[..]).unwrap_or_default() {
+                if let Some(transform) = transforms.get(child) {
+                    stack.push((child, global.mul_transform(transform)));
+                }
+            }
+        }
+    }
+
+    // Remove the global transforms of entities that lost their transform.
+    let stale = entities
+        .iter_with(&global_transforms)
+        .map(|(entity, _)| entity)
+        .filter(|&entity| transforms.get(entity).is_none())
+        .collect::<Vec<_>>();
+    for entity in stale {
+        global_transforms.remove(entity);
+    }
 }
@@ -0,0 +1,304 @@
+//! Scenes, which are serializable copies of the entities and resources in a [`World`].
+//!
+//! A [`Scene`] may be captured from a world with [`Scene::from_world()`], written to a file with
+//! any [`serde`] format, and loaded back into a world with [`Scene::spawn()`]. This may be used to
+//! author levels, to make save games, or to capture the state of a world for a bug report.
+//!
+//! In YAML, a scene looks like this:
+//!
+//! ```yaml
+//! entities:
+//!   - id: [0, 0]
+//!     components:
+//!       my_game::Position: { x: 1.0, y: 2.0 }
+//!   - id: [1, 0]
+//!     components:
+//!       my_game::Follow: [0, 0]
+//! resources:
+//!   my_game::Score: 10
+//! ```
+//!
+//! Components and resources are keyed by the [full name][SchemaData::full_name] of their schema,
+//! which must be registered before the scene is deserialized. The `id` of each entity is only used
+//! to find the [`Entity`] fields that refer to it. When the scene is spawned, those fields are
+//! remapped to the entities that were created for the scene.
+
+use std::fmt;
+
+use serde::{
+    de::{DeserializeSeed, Error, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserializer, Serializer,
+};
+use tracing::warn;
+
+use crate::prelude::*;
+
+/// A serializable copy of a set of entities and resources.
+///
+/// See the [module documentation][self].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Scene {
+    /// The entities in the scene.
+    #[serde(default)]
+    pub entities: Vec<SceneEntity>,
+    /// The resources in the scene.
+    #[serde(default, with = "schema_boxes")]
+    pub resources: Vec<SchemaBox>,
+}
+
+/// An entity in a [`Scene`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SceneEntity {
+    /// The ID of the entity in the scene, which is used to remap [`Entity`] fields that refer to
+    /// it when the scene is spawned.
+    #[serde(with = "entity")]
+    pub id: Entity,
+    /// The components of the entity.
+    #[serde(default, with = "schema_boxes")]
+    pub components: Vec<SchemaBox>,
+}
+
+impl Scene {
+    /// Capture the alive entities in the world, with all of their serializable components, and the
+    /// given resources.
+    ///
+    /// Components with a schema that cannot be serialized or deserialized, because it has opaque
+    /// fields or no default value, are left out. Resources that are not present in the world are
+    /// left out as well.
+    pub fn from_world(world: &World, resources: &[&'static Schema]) -> Self {
+        let mut stores = world
+            .components
+            .all_cells()
+            .into_iter()
+            .filter(|store| is_serializable(store.borrow().schema()))
+            .collect::<Vec<_>>();
+        // Sort the components so that the same world is always serialized the same way.
+        stores.sort_by(|a, b| {
+            let (a, b) = (a.borrow().schema(), b.borrow().schema());
+            a.full_name.as_str().cmp(b.full_name.as_str())
+        });
+        let stores = stores
+            .iter()
+            .map(|store| store.borrow())
+            .collect::<Vec<_>>();
+
+        let entities = world
+            .resource::<Entities>()
+            .iter()
+            .map(|entity| SceneEntity {
+                id: entity,
+                components: stores
+                    .iter()
+                    .filter_map(|store| store.get_ref(entity))
+                    .map(|component| component.clone_into_box())
+                    .collect(),
+            })
+            .collect();
+
+        let resources = resources
+            .iter()
+            .copied()
+            .filter(|schema| {
+                let serializable = is_serializable(schema);
+                if !serializable {
+                    warn!(
+                        "Resource `{}` cannot be saved in a scene because it cannot be serialized",
+                        schema.full_name
+                    );
+                }
+                serializable
+            })
+            .filter_map(|schema| world.resources.untyped().get(schema).clone_data())
+            .collect();
+
+        Scene {
+            entities,
+            resources,
+        }
+    }
+
+    /// Spawn the entities in the scene into the world and insert its resources.
+    ///
+    /// Returns a map from the IDs of the entities in the scene to the entities that were created for
+    /// them. [`Entity`] fields in the components and resources are remapped using this map, and
+    /// fields that refer to entities that are not in the scene are set to [`Entity::default()`].
+    /// Entities used as map keys are not remapped.
+    pub fn spawn(&self, world: &World) -> HashMap<Entity, Entity> {
+        let entity_map = {
+            let mut entities = world.resource_mut::<Entities>();
+            self.entities
+                .iter()
+                .map(|scene_entity| (scene_entity.id, entities.create()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        for scene_entity in &self.entities {
+            let entity = entity_map[&scene_entity.id];
+            for component in &scene_entity.components {
+                let mut component = component.clone();
+                map_entities(component.as_mut(), &entity_map);
+                let mut store = world
+                    .components
+                    .get_by_schema(component.schema())
+                    .borrow_mut();
+                store.set_change_tick(world.change_tick());
+                store.insert_box(entity, component);
+            }
+        }
+
+        for resource in &self.resources {
+            let mut resource = resource.clone();
+            map_entities(resource.as_mut(), &entity_map);
+            let cell = world.resources.untyped().get(resource.schema());
+            // The cell was retrieved with the resource's schema, so the schema matches.
+            cell.insert(resource).unwrap();
+            cell.ticks().set_added(world.change_tick());
+        }
+
+        entity_map
+    }
+}
+
+/// Whether values of the schema can be serialized and deserialized with the schema serializer.
+fn is_serializable(schema: &Schema) -> bool {
+    if schema.default_fn.is_none() {
+        return false;
+    }
+    fn has_serializable_kind(schema: &Schema) -> bool {
+        // `Ustr`s are opaque, but the schema serializer handles them specially.
+        if schema == Ustr::schema() {
+            return true;
+        }
+        match &schema.kind {
+            SchemaKind::Struct(s) => s.fields.iter().all(|f| has_serializable_kind(f.schema)),
+            SchemaKind::Vec(item) => has_serializable_kind(item),
+            SchemaKind::Map { key, value } => {
+                has_serializable_kind(key) && has_serializable_kind(value)
+            }
+            SchemaKind::Enum(e) => e.variants.iter().all(|v| has_serializable_kind(v.schema)),
+            SchemaKind::Box(inner) => has_serializable_kind(inner),
+            SchemaKind::Option(o) => has_serializable_kind(o.value),
+            SchemaKind::Primitive(p) => !matches!(p, Primitive::Opaque { .. }),
+        }
+    }
+    has_serializable_kind(schema)
+}
+
+/// Recursively remap the [`Entity`] fields of a value with the entity map.
+fn map_entities(mut value: SchemaRefMut, entity_map: &HashMap<Entity, Entity>) {
+    // We compare the schemas instead of using `try_cast_mut()`, because other structs that contain
+    // two `u32`s would also be cast to entities.
+    if value.schema() == Entity::schema() {
+        let entity = value.cast_mut::<Entity>();
+        *entity = entity_map.get(entity).copied().unwrap_or_default();
+        return;
+    }
+
+    match value.into_access_mut() {
+        SchemaRefMutAccess::Struct(s) => {
+            for field in s.into_fields() {
+                map_entities(field.value, entity_map);
+            }
+        }
+        SchemaRefMutAccess::Vec(mut v) => {
+            for item in v.iter_mut() {
+                map_entities(item, entity_map);
+            }
+        }
+        SchemaRefMutAccess::Map(mut m) => {
+            for value in m.values_mut() {
+                map_entities(value, entity_map);
+            }
+        }
+        SchemaRefMutAccess::Enum(e) => {
+            for field in e.value().into_fields() {
+                map_entities(field.value, entity_map);
+            }
+        }
+        SchemaRefMutAccess::Option(o) => {
+            if let Ok(value) = o.into_value() {
+                map_entities(value, entity_map);
+            }
+        }
+        SchemaRefMutAccess::Primitive(_) => (),
+    }
+}
+
+/// Find the registered schema with the given full name.
+fn find_schema(full_name: &str) -> Option<&'static Schema> {
+    SCHEMA_REGISTRY
+        .schemas
+        .iter()
+        .find(|schema| schema.full_name.as_str() == full_name)
+}
+
+/// Serde helpers for lists of [`SchemaBox`]es, serialized as a map from the full name of their
+/// schemas to their values.
+mod schema_boxes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(boxes: &[SchemaBox], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(boxes.len()))?;
+        for schema_box in boxes {
+            map.serialize_entry(
+                schema_box.schema().full_name.as_str(),
+                &SchemaSerializer(schema_box.as_ref()),
+            )?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<SchemaBox>, D::Error> {
+        deserializer.deserialize_map(SchemaBoxesVisitor)
+    }
+
+    struct SchemaBoxesVisitor;
+    impl<'de> Visitor<'de> for SchemaBoxesVisitor {
+        type Value = Vec<SchemaBox>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "a map of schema full names to values")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut boxes = Vec::new();
+            while let Some(full_name) = map.next_key::<String>()? {
+                let schema = find_schema(&full_name).ok_or_else(|| {
+                    A::Error::custom(format!(
+                        "Schema not found: `{full_name}`. Make sure that the schema has been \
+                        registered before loading the scene."
+                    ))
+                })?;
+                if schema.default_fn.is_none() {
+                    return Err(A::Error::custom(format!(
+                        "`{full_name}` cannot be loaded from a scene because it has no default \
+                        value"
+                    )));
+                }
+                boxes.push(map.next_value_seed(SchemaDeserializer(schema))?);
+            }
+            Ok(boxes)
+        }
+    }
+}
+
+/// Serde helpers for [`Entity`], which is serialized with its schema.
+mod entity {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(entity: &Entity, serializer: S) -> Result<S::Ok, S::Error> {
+        SchemaSerializer(entity.as_schema_ref()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Entity, D::Error> {
+        Ok(SchemaDeserializer(Entity::schema())
+            .deserialize(deserializer)?
+            .cast_into())
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use bones_framework::prelude::*;
+use futures_lite::{
+    future::{block_on, yield_now},
+    FutureExt,
+};
+
+#[derive(Clone, Default, HasSchema)]
+#[type_data(metadata_asset("game"))]
+#[repr(C)]
+struct GameMeta {
+    player: Handle<Prefab>,
+}
+
+#[derive(Clone, Default, HasSchema, Debug, PartialEq)]
+#[repr(C)]
+struct Health(u32);
+
+#[derive(Clone, Default, HasSchema, Debug, PartialEq)]
+#[repr(C)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+fn init() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        GameMeta::register_schema();
+        Health::register_schema();
+        Position::register_schema();
+
+        bevy_tasks::IoTaskPool::init(|| bevy_tasks::TaskPoolBuilder::new().num_threads(1).build());
+    });
+}
+
+fn create_world() -> World {
+    let mut world = World::default();
+    let core_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("prefab_assets");
+    let io = FileAssetIo::new(&core_dir, &core_dir.join("packs"));
+
+    let mut asset_server = world.init_resource::<AssetServer>();
+    asset_server.set_io(io);
+    asset_server.register_default_assets();
+
+    let scope = async move {
+        asset_server.load_assets().await.expect("load test assets");
+        while !asset_server.load_progress.is_finished() {
+            yield_now().await;
+        }
+    };
+    block_on(scope.boxed());
+
+    world
+}
+
+#[test]
+#[cfg(not(miri))]
+fn spawn_prefab() {
+    init();
+    let world = create_world();
+    let player = world.run_system(|root: Root<GameMeta>| root.player, ());
+
+    let spawned = world.run_system(
+        move |mut entities: ResMut<Entities>, mut commands: Commands| {
+            let entity = entities.create();
+            commands.insert_prefab(entity, player);
+            commands.spawn_prefab(player);
+            entity
+        },
+        (),
+    );
+    let mut stage = SimpleSystemStage::new(CoreStage::Update);
+    stage.run(&world);
+
+    let entities = world.resource::<Entities>().iter().collect::<Vec<_>>();
+    assert_eq!(entities.len(), 2);
+    assert_eq!(entities[0], spawned);
+    for entity in entities {
+        assert_eq!(world.component::<Health>().get(entity), Some(&Health(100)));
+        assert_eq!(
+            world.component::<Position>().get(entity),
+            Some(&Position { x: 1.5, y: 0.0 })
+        );
+    }
+}
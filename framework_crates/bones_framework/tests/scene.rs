@@ -0,0 +1,137 @@
+use bones_framework::prelude::*;
+
+#[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+#[repr(C)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+#[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+#[repr(C)]
+struct Follow(Entity);
+
+#[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+#[repr(C)]
+struct Score(u32);
+
+#[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+#[repr(C)]
+struct Player(Entity);
+
+/// A component that can't be serialized, and should be left out of scenes.
+#[derive(HasSchema, Clone, Default)]
+struct NotSerializable;
+
+fn register_schemas() {
+    Position::register_schema();
+    Follow::register_schema();
+    Score::register_schema();
+    Player::register_schema();
+}
+
+fn create_world() -> World {
+    let world = World::new();
+    world.insert_resource(Score(7));
+    world.run_system(
+        |mut entities: ResMut<Entities>,
+         mut positions: CompMut<Position>,
+         mut follows: CompMut<Follow>,
+         mut not_serializable: CompMut<NotSerializable>| {
+            // Kill an entity so that entity indexes and generations don't match the new world.
+            let dead = entities.create();
+            entities.kill(dead);
+
+            let leader = entities.create();
+            positions.insert(leader, Position { x: 1.0, y: 2.0 });
+            not_serializable.insert(leader, NotSerializable);
+
+            let follower = entities.create();
+            positions.insert(follower, Position { x: 3.0, y: 4.0 });
+            follows.insert(follower, Follow(leader));
+        },
+        (),
+    );
+    let leader = world.resource::<Entities>().iter().next().unwrap();
+    world.insert_resource(Player(leader));
+    world
+}
+
+#[test]
+fn scene_round_trip() {
+    register_schemas();
+    let world = create_world();
+
+    let scene = Scene::from_world(&world, &[Score::schema(), Player::schema()]);
+    assert_eq!(scene.entities.len(), 2);
+    assert!(scene.entities.iter().all(|e| e
+        .components
+        .iter()
+        .all(|c| c.schema() != NotSerializable::schema())));
+
+    let yaml = serde_yaml::to_string(&scene).unwrap();
+    let loaded: Scene = serde_yaml::from_str(&yaml).unwrap();
+
+    let new_world = World::new();
+    // Spawn some entities first, so that the scene's entities are created with different IDs.
+    for _ in 0..2 {
+        new_world.resource_mut::<Entities>().create();
+    }
+    let entity_map = loaded.spawn(&new_world);
+    assert_eq!(entity_map.len(), 2);
+
+    let [leader, follower] = [scene.entities[0].id, scene.entities[1].id].map(|e| entity_map[&e]);
+    assert_eq!(leader.index(), 2);
+    assert_eq!(*new_world.resource::<Score>(), Score(7));
+    assert_eq!(*new_world.resource::<Player>(), Player(leader));
+    new_world.run_system(
+        move |positions: Comp<Position>, follows: Comp<Follow>| {
+            assert_eq!(positions.get(leader), Some(&Position { x: 1.0, y: 2.0 }));
+            assert_eq!(positions.get(follower), Some(&Position { x: 3.0, y: 4.0 }));
+            assert_eq!(follows.get(leader), None);
+            assert_eq!(follows.get(follower), Some(&Follow(leader)));
+        },
+        (),
+    );
+}
+
+#[test]
+fn load_authored_scene() {
+    register_schemas();
+    let scene: Scene = serde_yaml::from_str(
+        "
+entities:
+  - id: [10, 0]
+    components:
+      scene::Position: { x: 5.0 }
+  - id: [11, 0]
+    components:
+      scene::Follow: [10, 0]
+  - id: [12, 0]
+    components:
+      scene::Follow: [99, 0]
+resources:
+  scene::Score: 3
+",
+    )
+    .unwrap();
+
+    let world = World::new();
+    let entity_map = scene.spawn(&world);
+    let [a, b, c] = [10, 11, 12].map(|i| entity_map[&Entity::new(i, 0)]);
+    assert_eq!(*world.resource::<Score>(), Score(3));
+    world.run_system(
+        move |positions: Comp<Position>, follows: Comp<Follow>| {
+            assert_eq!(positions.get(a), Some(&Position { x: 5.0, y: 0.0 }));
+            assert_eq!(follows.get(b), Some(&Follow(a)));
+            // Entities that aren't in the scene are mapped to the invalid entity.
+            assert_eq!(follows.get(c), Some(&Follow(Entity::default())));
+        },
+        (),
+    );
+
+    let error = serde_yaml::from_str::<Scene>("resources: { scene::Unknown: 1 }").unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Schema not found: `scene::Unknown`"));
+}
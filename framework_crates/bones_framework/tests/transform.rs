@@ -0,0 +1,80 @@
+use bones_framework::prelude::*;
+
+fn spawn_hierarchy(world: &World) -> (Entity, Entity) {
+    let (parent, child) = {
+        let mut entities = world.resource_mut::<Entities>();
+        (entities.create(), entities.create())
+    };
+    world.run_system(
+        move |mut transforms: CompMut<Transform>, mut hierarchy: Hierarchy| {
+            transforms.insert(parent, Transform::from_translation(vec3(1.0, 2.0, 0.0)));
+            transforms.insert(child, Transform::from_translation(vec3(3.0, 0.0, 0.0)));
+            hierarchy.set_parent(child, parent);
+        },
+        (),
+    );
+    (parent, child)
+}
+
+/// Verify that global transforms combine the transforms of the entity's ancestors.
+#[test]
+fn transforms_are_propagated() {
+    let world = World::new();
+    let (parent, child) = spawn_hierarchy(&world);
+
+    world.run_system(propagate_transforms, ());
+    let global = |entity| {
+        world
+            .component::<GlobalTransform>()
+            .get(entity)
+            .unwrap()
+            .translation
+    };
+    assert_eq!(global(parent), vec3(1.0, 2.0, 0.0));
+    assert_eq!(global(child), vec3(4.0, 2.0, 0.0));
+
+    world
+        .component_mut::<Transform>()
+        .get_mut(parent)
+        .unwrap()
+        .scale = Vec3::splat(2.0);
+    world.run_system(propagate_transforms, ());
+    assert_eq!(global(child), vec3(7.0, 2.0, 0.0));
+}
+
+/// Verify that global transforms are only changed when their value changes.
+#[test]
+fn unchanged_transforms_are_not_modified() {
+    let world = World::new();
+    let (parent, child) = spawn_hierarchy(&world);
+    world.run_system(propagate_transforms, ());
+    let ticks = |entity| world.component::<GlobalTransform>().ticks(entity).unwrap();
+    let (parent_ticks, child_ticks) = (ticks(parent), ticks(child));
+
+    world.run_system(propagate_transforms, ());
+    assert_eq!(ticks(parent), parent_ticks);
+    assert_eq!(ticks(child), child_ticks);
+
+    world
+        .component_mut::<Transform>()
+        .get_mut(child)
+        .unwrap()
+        .translation
+        .x = 5.0;
+    world.run_system(propagate_transforms, ());
+    assert_eq!(ticks(parent), parent_ticks);
+    assert_ne!(ticks(child), child_ticks);
+}
+
+/// Verify that the global transform is removed along with the transform.
+#[test]
+fn stale_global_transforms_are_removed() {
+    let world = World::new();
+    let (parent, child) = spawn_hierarchy(&world);
+    world.run_system(propagate_transforms, ());
+
+    world.component_mut::<Transform>().remove(child);
+    world.run_system(propagate_transforms, ());
+    assert!(world.component::<GlobalTransform>().get(child).is_none());
+    assert!(world.component::<GlobalTransform>().get(parent).is_some());
+}
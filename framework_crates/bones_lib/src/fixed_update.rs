@@ -0,0 +1,310 @@
+//! Fixed timestep updates.
+//!
+//! The [`FixedUpdateStage`] runs its systems a fixed number of times per second, no matter the
+//! frame rate, by accumulating the [`Time`] delta of every frame and running once for every
+//! [`FixedTime::step()`] that has been accumulated.
+//!
+//! ```
+//! # use bones_lib::{prelude::*, instant::Duration};
+//! # let mut session = SessionBuilder::new("example");
+//! fn physics(fixed_time: Res<FixedTime>) {
+//!     let dt = fixed_time.step_seconds();
+//!     // ...
+//! }
+//!
+//! fn render(fixed_time: Res<FixedTime>) {
+//!     // Interpolate between the last two physics states.
+//!     let alpha = fixed_time.alpha();
+//!     // ...
+//! }
+//!
+//! session
+//!     .insert_stage_after(
+//!         CoreStage::PreUpdate,
+//!         FixedUpdateStage::new(Duration::from_secs_f64(1.0 / 60.0)),
+//!     )
+//!     .add_system_to_stage(FixedUpdate, physics)
+//!     .add_system_to_stage(CoreStage::PostUpdate, render);
+//! ```
+
+use instant::Duration;
+
+use crate::prelude::*;
+
+/// The default maximum number of fixed updates that are run in a single frame.
+pub const DEFAULT_MAX_FIXED_STEPS: u32 = 5;
+
+/// The label for the [`FixedUpdateStage`].
+#[derive(Clone, Copy, Debug)]
+pub struct FixedUpdate;
+
+impl StageLabel for FixedUpdate {
+    fn name(&self) -> String {
+        "FixedUpdate".into()
+    }
+
+    fn id(&self) -> Ulid {
+        Ulid(2166639640220712354794447635636077112)
+    }
+}
+
+/// Resource containing the timing information for the [`FixedUpdateStage`].
+///
+/// The resource is added to the world by the stage the first time it is run, using the stage's
+/// step and maximum number of steps. It may be modified afterwards to change them.
+#[derive(Clone, Copy, Debug, HasSchema)]
+#[schema(no_default)]
+#[repr(C)]
+pub struct FixedTime {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+    elapsed: Duration,
+    alpha: f32,
+}
+
+impl FixedTime {
+    /// Create a new [`FixedTime`] with the given step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step is zero.
+    #[track_caller]
+    pub fn new(step: Duration) -> Self {
+        assert!(!step.is_zero(), "The fixed timestep must not be zero.");
+        Self {
+            step,
+            max_steps: DEFAULT_MAX_FIXED_STEPS,
+            accumulator: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            alpha: 0.0,
+        }
+    }
+
+    /// Get the amount of time that is simulated by each fixed update.
+    #[inline]
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Get the amount of time that is simulated by each fixed update, as [`prim@f32`] seconds.
+    #[inline]
+    pub fn step_seconds(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /// Set the amount of time that is simulated by each fixed update.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step is zero.
+    #[track_caller]
+    pub fn set_step(&mut self, step: Duration) {
+        assert!(!step.is_zero(), "The fixed timestep must not be zero.");
+        self.step = step;
+    }
+
+    /// Get the maximum number of fixed updates that will be run in a single frame.
+    #[inline]
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    /// Set the maximum number of fixed updates that will be run in a single frame.
+    ///
+    /// If a frame takes so long that more updates than this would be needed to catch up, the extra
+    /// time is dropped. This keeps slow updates from making the frames longer and longer, which
+    /// would require even more updates to catch up.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Get the amount of time that has been accumulated, but not simulated by a fixed update yet.
+    #[inline]
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// Get the total amount of time that has been simulated by fixed updates.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Get how far the current frame is between the last fixed update and the next one, from `0.0`
+    /// to `1.0`.
+    ///
+    /// This may be used to interpolate between the states of the last two fixed updates when
+    /// rendering.
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+}
+
+/// A [`SystemStage`] that runs a set of stages at a fixed timestep.
+///
+/// Every time the stage is run, the [`Time::delta()`] of the frame is added to the
+/// [`FixedTime`] resource, and the inner stages are run once for every [`FixedTime::step()`] that
+/// has been accumulated, up to [`FixedTime::max_steps()`] times.
+///
+/// Systems added to the stage with the [`FixedUpdate`] label are added to its first inner stage.
+/// More stages, along with their systems, may be added with
+/// [`with_stage()`][FixedUpdateStage::with_stage].
+pub struct FixedUpdateStage {
+    step: Duration,
+    max_steps: u32,
+    stages: Vec<Box<dyn SystemStage>>,
+}
+
+impl FixedUpdateStage {
+    /// Create a new fixed update stage with the given step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the step is zero.
+    #[track_caller]
+    pub fn new(step: Duration) -> Self {
+        assert!(!step.is_zero(), "The fixed timestep must not be zero.");
+        Self {
+            step,
+            max_steps: DEFAULT_MAX_FIXED_STEPS,
+            stages: vec![Box::new(SimpleSystemStage::new(FixedUpdate))],
+        }
+    }
+
+    /// Set the maximum number of fixed updates that will be run in a single frame.
+    ///
+    /// See [`FixedTime::set_max_steps()`].
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Add another stage that will be run for every fixed update, after the stages that were
+    /// already added.
+    pub fn with_stage<S: SystemStage + 'static>(mut self, stage: S) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+}
+
+impl SystemStage for FixedUpdateStage {
+    fn id(&self) -> Ulid {
+        FixedUpdate.id()
+    }
+
+    fn name(&self) -> String {
+        FixedUpdate.name()
+    }
+
+    fn run(&mut self, world: &World) {
+        if !world.resources.contains::<FixedTime>() {
+            let mut fixed_time = FixedTime::new(self.step);
+            fixed_time.max_steps = self.max_steps;
+            world.insert_resource(fixed_time);
+        }
+
+        let delta = world
+            .get_resource::<Time>()
+            .map(|time| time.delta())
+            .unwrap_or_default();
+        world.resource_mut::<FixedTime>().accumulator += delta;
+
+        let mut steps = 0;
+        loop {
+            {
+                let mut fixed_time = world.resource_mut::<FixedTime>();
+                let step = fixed_time.step;
+                if fixed_time.accumulator < step {
+                    break;
+                }
+                if steps >= fixed_time.max_steps {
+                    // Drop the time that we can't catch up on.
+                    let remainder = fixed_time.accumulator.as_nanos() % step.as_nanos();
+                    fixed_time.accumulator = Duration::from_nanos(remainder as u64);
+                    break;
+                }
+                fixed_time.accumulator -= step;
+                fixed_time.elapsed += step;
+            }
+
+            for stage in &mut self.stages {
+                stage.run(world);
+            }
+            steps += 1;
+        }
+
+        let mut fixed_time = world.resource_mut::<FixedTime>();
+        fixed_time.alpha = fixed_time.accumulator.as_secs_f32() / fixed_time.step.as_secs_f32();
+    }
+
+    fn add_system(&mut self, system: StaticSystem<(), ()>) {
+        self.stages[0].add_system(system);
+    }
+
+    fn add_system_with_dependencies(
+        &mut self,
+        system: StaticSystem<(), ()>,
+        dependencies: &[SystemId],
+    ) {
+        self.stages[0].add_system_with_dependencies(system, dependencies);
+    }
+
+    fn remove_all_systems(&mut self) {
+        for stage in &mut self.stages {
+            stage.remove_all_systems();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use instant::{Duration, Instant};
+
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[repr(C)]
+    struct Steps(u32);
+
+    fn run_frame(stage: &mut FixedUpdateStage, world: &World, now: &mut Instant, delta: Duration) {
+        *now += delta;
+        world.resource_mut::<Time>().update_with_instant(*now);
+        stage.run(world);
+    }
+
+    #[test]
+    fn fixed_update() {
+        let world = World::new();
+        let mut now = Instant::now();
+        let mut time = Time::new(now);
+        time.update_with_instant(now);
+        world.insert_resource(time);
+
+        let mut stage = FixedUpdateStage::new(Duration::from_millis(10)).with_max_steps(4);
+        stage.add_system((|mut steps: ResMutInit<Steps>| steps.0 += 1).system());
+
+        run_frame(&mut stage, &world, &mut now, Duration::from_millis(35));
+        assert_eq!(world.resource::<Steps>().0, 3);
+        {
+            let fixed_time = world.resource::<FixedTime>();
+            assert_eq!(fixed_time.accumulator(), Duration::from_millis(5));
+            assert!((fixed_time.alpha() - 0.5).abs() < 1e-4);
+        }
+
+        // The remaining time is carried over to the next frame.
+        run_frame(&mut stage, &world, &mut now, Duration::from_millis(5));
+        assert_eq!(world.resource::<Steps>().0, 4);
+        assert_eq!(world.resource::<FixedTime>().alpha(), 0.0);
+
+        // Long frames only run up to the maximum number of steps.
+        run_frame(&mut stage, &world, &mut now, Duration::from_millis(1003));
+        assert_eq!(world.resource::<Steps>().0, 8);
+        assert_eq!(
+            world.resource::<FixedTime>().accumulator(),
+            Duration::from_millis(3)
+        );
+    }
+}
@@ -11,14 +11,17 @@ pub use bones_ecs as ecs;
 /// Bones lib prelude
 pub mod prelude {
     pub use crate::{
-        ecs::prelude::*, instant::Instant, reset::*, time::*, Game, GamePlugin, Session,
-        SessionBuilder, SessionCommand, SessionOptions, SessionPlugin, SessionRunner, Sessions,
+        ecs::prelude::*, fixed_update::*, instant::Instant, reset::*, state::*, time::*, Game,
+        GamePlugin, Session, SessionBuilder, SessionCommand, SessionOptions, SessionPlugin,
+        SessionRunner, Sessions,
     };
     pub use ustr::{ustr, Ustr, UstrMap, UstrSet};
 }
 
 pub use instant;
+pub mod fixed_update;
 pub mod reset;
+pub mod state;
 pub mod time;
 
 use std::{collections::VecDeque, fmt::Debug, sync::Arc};
@@ -42,6 +45,9 @@ pub struct SessionBuilder {
     /// The session runner to use for this session.
     pub runner: Box<dyn SessionRunner>,
 
+    /// The state machines of the session.
+    states: state::StateRegistry,
+
     /// Tracks if builder has been finished, and warn on drop if not finished.
     finish_guard: FinishGuard,
 }
@@ -66,6 +72,7 @@ impl SessionBuilder {
             visible: true,
             priority: 0,
             runner: Box::new(DefaultSessionRunner),
+            states: default(),
             finish_guard: FinishGuard { finished: false },
         }
     }
@@ -87,6 +94,7 @@ impl SessionBuilder {
             visible: session.visible,
             priority: session.priority,
             runner: session.runner,
+            states: session.states,
             finish_guard: FinishGuard { finished: false },
         }
     }
@@ -156,9 +164,12 @@ impl SessionBuilder {
     }
 
     /// Add a [`System`] to the stage with the given label.
+    ///
+    /// The system may be given a label and ordering constraints relative to other labelled systems
+    /// in the same stage using the methods of [`IntoSystemConfig`].
     pub fn add_system_to_stage<Args, S>(&mut self, label: impl StageLabel, system: S) -> &mut Self
     where
-        S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+        S: IntoSystemConfig<Args>,
     {
         self.stages.add_system_to_stage(label, system);
         self
@@ -211,6 +222,7 @@ impl SessionBuilder {
             visible: self.visible,
             priority: self.priority,
             runner: self.runner,
+            states: self.states,
         };
 
         // mark guard as finished to avoid warning on drop of SessionBuilder.
@@ -258,6 +270,9 @@ pub struct Session {
     pub priority: i32,
     /// The session runner to use for this session.
     pub runner: Box<dyn SessionRunner>,
+    /// The state machines of the session, kept so that a [`SessionBuilder`] created from this
+    /// session can add systems to them.
+    states: state::StateRegistry,
 }
 
 impl std::fmt::Debug for Session {
@@ -436,16 +451,12 @@ impl Game {
             .shared_resources
             .iter()
             .find(|x| x.schema() == T::schema())?;
-        let borrow = res.borrow();
-
-        if borrow.is_some() {
-            // SOUND: We know the type matches T
-            Some(Ref::map(borrow, |b| unsafe {
-                b.as_ref().unwrap().as_ref().cast_into_unchecked()
-            }))
-        } else {
-            None
-        }
+        let borrow = res.borrow()?;
+
+        // SOUND: We know the type matches T
+        Some(Ref::map(borrow, |b| unsafe {
+            b.as_ref().cast_into_unchecked()
+        }))
     }
 
     #[track_caller]
@@ -456,16 +467,12 @@ impl Game {
             .shared_resources
             .iter()
             .find(|x| x.schema() == T::schema())?;
-        let borrow = res.borrow_mut();
-
-        if borrow.is_some() {
-            // SOUND: We know the type matches T
-            Some(RefMut::map(borrow, |b| unsafe {
-                b.as_mut().unwrap().as_mut().cast_into_mut_unchecked()
-            }))
-        } else {
-            None
-        }
+        let borrow = res.borrow_mut()?;
+
+        // SOUND: We know the type matches T
+        Some(RefMut::map(borrow, |b| unsafe {
+            b.as_mut().cast_into_mut_unchecked()
+        }))
     }
 
     #[track_caller]
@@ -514,13 +521,7 @@ impl Game {
         // Update an existing resource of the same type.
         for r in &mut self.shared_resources {
             if r.schema() == T::schema() {
-                let mut borrow = r.borrow_mut();
-
-                if let Some(b) = borrow.as_mut().as_mut() {
-                    *b.cast_mut() = resource;
-                } else {
-                    *borrow = Some(SchemaBox::new(resource))
-                }
+                r.insert(SchemaBox::new(resource)).unwrap();
                 return;
             }
         }
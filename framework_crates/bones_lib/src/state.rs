@@ -0,0 +1,428 @@
+//! Game state machines.
+//!
+//! A state machine is made of a [`State`] resource, containing the current state, and a
+//! [`NextState`] resource, that systems may use to request a transition to another state. Systems
+//! may be registered with the [`SessionBuilder`] to run when a state is entered or exited, or only
+//! while the game is in a specific state.
+//!
+//! ```
+//! # use bones_lib::prelude::*;
+//! # let mut session = SessionBuilder::new("example");
+//! #[derive(HasSchema, Clone, Copy, Debug, PartialEq, Eq, Default)]
+//! #[repr(C, u8)]
+//! enum GameState {
+//!     #[default]
+//!     Menu,
+//!     Playing,
+//!     Paused,
+//! }
+//!
+//! fn start_game(mut next_state: ResMut<NextState<GameState>>) {
+//!     next_state.set(GameState::Playing);
+//! }
+//!
+//! fn spawn_level() {}
+//! fn move_players() {}
+//! fn show_pause_menu() {}
+//!
+//! session
+//!     .add_state(GameState::Menu)
+//!     .add_system_in_state(CoreStage::Update, GameState::Menu, start_game)
+//!     .add_system_on_enter(GameState::Playing, spawn_level)
+//!     .add_system_in_state(CoreStage::Update, GameState::Playing, move_players)
+//!     .add_system_on_transition(GameState::Playing, GameState::Paused, show_pause_menu);
+//! ```
+//!
+//! Transitions are applied at the start of every [`SystemStages::run`], before any of the stages
+//! are run. Because [`State`] and [`NextState`] are resources in the [`World`], the state is
+//! captured by world snapshots, and the transitions are replayed when a snapshot is restored.
+
+use std::{
+    alloc::Layout,
+    any::{type_name, Any, TypeId},
+    sync::{Arc, Mutex, OnceLock, RwLock},
+};
+
+use bones_ecs::schema::raw_fns::*;
+
+use crate::prelude::*;
+
+/// Resource containing the current state of a state machine.
+///
+/// The state is changed by setting the [`NextState`].
+#[derive(Clone, Debug)]
+pub struct State<S>(S);
+
+impl<S> std::ops::Deref for State<S> {
+    type Target = S;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S> State<S> {
+    /// Get the current state.
+    pub fn get(&self) -> &S {
+        &self.0
+    }
+}
+
+/// Resource used to request a transition to another state.
+///
+/// The transition is applied the next time the [`SystemStages`] are run, see the
+/// [module documentation][self].
+#[derive(Clone, Debug)]
+pub struct NextState<S>(Option<S>);
+
+impl<S> Default for NextState<S> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<S> NextState<S> {
+    /// Request a transition to the given state.
+    ///
+    /// If a transition was already requested, it is replaced.
+    pub fn set(&mut self, state: S) {
+        self.0 = Some(state);
+    }
+
+    /// Get the state that a transition has been requested to, if any.
+    pub fn get(&self) -> Option<&S> {
+        self.0.as_ref()
+    }
+}
+
+macro_rules! impl_state_schema {
+    ($t:ident, $default_fn:expr) => {
+        // SAFE: We return a valid schema.
+        unsafe impl<S: HasSchema + Clone> HasSchema for $t<S> {
+            fn schema() -> &'static Schema {
+                static S: OnceLock<RwLock<HashMap<TypeId, &'static Schema>>> = OnceLock::new();
+                let schema = {
+                    S.get_or_init(default)
+                        .read()
+                        .unwrap()
+                        .get(&TypeId::of::<Self>())
+                        .copied()
+                };
+                schema.unwrap_or_else(|| {
+                    let layout = Layout::new::<Self>();
+                    let schema = SCHEMA_REGISTRY.register(SchemaData {
+                        name: type_name::<Self>().into(),
+                        full_name: format!("{}::{}", module_path!(), type_name::<Self>()).into(),
+                        kind: SchemaKind::Primitive(Primitive::Opaque {
+                            size: layout.size(),
+                            align: layout.align(),
+                        }),
+                        type_id: Some(TypeId::of::<Self>()),
+                        clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
+                        drop_fn: Some(<Self as RawDrop>::raw_drop_cb()),
+                        default_fn: $default_fn,
+                        hash_fn: None,
+                        eq_fn: None,
+                        type_data: default(),
+                    });
+
+                    S.get_or_init(default)
+                        .write()
+                        .unwrap()
+                        .insert(TypeId::of::<Self>(), schema);
+
+                    schema
+                })
+            }
+        }
+    };
+}
+impl_state_schema!(State, None);
+impl_state_schema!(NextState, Some(<Self as RawDefault>::raw_default_cb()));
+
+/// Create a [run condition][IntoConditionalSystem::run_if] that is only `true` while the game is
+/// in the given state.
+pub fn in_state<S: HasSchema + Clone + PartialEq>(state: S) -> StaticSystem<(), bool> {
+    (move |current: Option<Res<State<S>>>| current.is_some_and(|current| current.0 == state))
+        .system()
+}
+
+/// The systems that are run on the transitions of a state machine.
+struct StateSystems<S> {
+    on_exit: Vec<(S, StaticSystem<(), ()>)>,
+    on_transition: Vec<(S, S, StaticSystem<(), ()>)>,
+    on_enter: Vec<(S, StaticSystem<(), ()>)>,
+}
+
+impl<S> Default for StateSystems<S> {
+    fn default() -> Self {
+        Self {
+            on_exit: Vec::new(),
+            on_transition: Vec::new(),
+            on_enter: Vec::new(),
+        }
+    }
+}
+
+/// The [`StateSystems`] of every state machine in a session, by the [`TypeId`] of the state.
+#[derive(Default)]
+pub(crate) struct StateRegistry {
+    states: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl StateRegistry {
+    /// Get the systems of the given state machine, registering its state transition system with
+    /// the `stages` if it hasn't been registered yet.
+    fn get_or_register<S: HasSchema + Clone + PartialEq>(
+        &mut self,
+        stages: &mut SystemStagesBuilder,
+    ) -> Arc<Mutex<StateSystems<S>>> {
+        self.states
+            .entry(TypeId::of::<S>())
+            .or_insert_with(|| {
+                let systems = Arc::new(Mutex::new(StateSystems::<S>::default()));
+                let transition_systems = systems.clone();
+                stages.add_state_transition_system(move |world: &World| {
+                    apply_state_transition(world, &mut transition_systems.lock().unwrap())
+                });
+                if stages.startup_resource_mut::<NextState<S>>().is_none() {
+                    stages.insert_startup_resource(NextState::<S>::default());
+                }
+                Box::new(systems)
+            })
+            .downcast_ref::<Arc<Mutex<StateSystems<S>>>>()
+            .unwrap()
+            .clone()
+    }
+}
+
+/// Apply the transition requested by the [`NextState`], if any, running the systems that were
+/// registered for it.
+fn apply_state_transition<S: HasSchema + Clone + PartialEq>(
+    world: &World,
+    systems: &mut StateSystems<S>,
+) {
+    // Only borrow the next state mutably when a transition is pending, so that it isn't marked as
+    // changed every frame.
+    let pending = world
+        .get_resource::<NextState<S>>()
+        .is_some_and(|next| next.0.is_some());
+    if !pending {
+        return;
+    }
+    let Some(next) = world.resource_mut::<NextState<S>>().0.take() else {
+        return;
+    };
+    let current = world
+        .get_resource::<State<S>>()
+        .map(|state| state.0.clone());
+    if current.as_ref() == Some(&next) {
+        return;
+    }
+
+    if let Some(current) = &current {
+        for (state, system) in &mut systems.on_exit {
+            if state == current && system.check_conditions(world) {
+                system.run(world, ());
+            }
+        }
+        for (from, to, system) in &mut systems.on_transition {
+            if from == current && *to == next && system.check_conditions(world) {
+                system.run(world, ());
+            }
+        }
+    }
+
+    world.insert_resource(State(next.clone()));
+
+    for (state, system) in &mut systems.on_enter {
+        if *state == next && system.check_conditions(world) {
+            system.run(world, ());
+        }
+    }
+}
+
+impl SessionBuilder {
+    /// Add a state machine to the session, starting in the `initial` state.
+    ///
+    /// The initial state is entered, running its [`on_enter`][Self::add_system_on_enter] systems,
+    /// the first time the stages are run. This means that the [`State`] resource is not available
+    /// to startup systems.
+    pub fn add_state<S: HasSchema + Clone + PartialEq>(&mut self, initial: S) -> &mut Self {
+        self.states.get_or_register::<S>(&mut self.stages);
+        self.stages
+            .insert_startup_resource(NextState(Some(initial)));
+        self
+    }
+
+    /// Add a system that is run when the given state is entered.
+    pub fn add_system_on_enter<S, Args, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        S: HasSchema + Clone + PartialEq,
+        F: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+    {
+        let systems = self.states.get_or_register::<S>(&mut self.stages);
+        systems
+            .lock()
+            .unwrap()
+            .on_enter
+            .push((state, system.system()));
+        self
+    }
+
+    /// Add a system that is run when the given state is exited.
+    pub fn add_system_on_exit<S, Args, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        S: HasSchema + Clone + PartialEq,
+        F: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+    {
+        let systems = self.states.get_or_register::<S>(&mut self.stages);
+        systems
+            .lock()
+            .unwrap()
+            .on_exit
+            .push((state, system.system()));
+        self
+    }
+
+    /// Add a system that is run on the transition from the state `from` to the state `to`.
+    ///
+    /// The system is run after the [`on_exit`][Self::add_system_on_exit] systems of `from`, and
+    /// before the [`on_enter`][Self::add_system_on_enter] systems of `to`.
+    pub fn add_system_on_transition<S, Args, F>(&mut self, from: S, to: S, system: F) -> &mut Self
+    where
+        S: HasSchema + Clone + PartialEq,
+        F: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+    {
+        let systems = self.states.get_or_register::<S>(&mut self.stages);
+        systems
+            .lock()
+            .unwrap()
+            .on_transition
+            .push((from, to, system.system()));
+        self
+    }
+
+    /// Add a system to the stage with the given label, that is only run while the game is in the
+    /// given state.
+    ///
+    /// This is the same as adding the system with an [`in_state`] run condition.
+    pub fn add_system_in_state<S, Args, F>(
+        &mut self,
+        label: impl StageLabel,
+        state: S,
+        system: F,
+    ) -> &mut Self
+    where
+        S: HasSchema + Clone + PartialEq,
+        F: IntoSystemConfig<Args>,
+    {
+        self.stages
+            .add_system_to_stage(label, system.config().run_if(in_state(state)));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Copy, Debug, PartialEq, Eq, Default)]
+    #[repr(C, u8)]
+    enum GameState {
+        #[default]
+        Menu,
+        Playing,
+        Paused,
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log(event: &'static str) -> StaticSystem<(), ()> {
+        (move |mut log: ResMutInit<Log>| log.0.push(event)).system()
+    }
+
+    fn set_state(world: &World, state: GameState) {
+        world.resource_mut::<NextState<GameState>>().set(state);
+    }
+
+    #[test]
+    fn state_transitions() {
+        let mut sessions = Sessions::default();
+        let mut builder = SessionBuilder::new("test");
+        builder
+            .add_state(GameState::Menu)
+            .add_system_on_enter(GameState::Menu, log("enter menu"))
+            .add_system_on_exit(GameState::Menu, log("exit menu"))
+            .add_system_on_enter(GameState::Playing, log("enter playing"))
+            .add_system_on_transition(GameState::Playing, GameState::Paused, log("pause"))
+            .add_system_in_state(CoreStage::Update, GameState::Playing, log("playing"));
+        let session = builder.finish_and_add(&mut sessions);
+
+        session.stages.run(&mut session.world);
+        assert_eq!(session.world.resource::<Log>().0, ["enter menu"]);
+        assert_eq!(
+            *session.world.resource::<State<GameState>>().get(),
+            GameState::Menu
+        );
+
+        set_state(&session.world, GameState::Playing);
+        let snapshot = session.snapshot();
+        session.stages.run(&mut session.world);
+        assert_eq!(
+            session.world.resource::<Log>().0,
+            ["enter menu", "exit menu", "enter playing", "playing"]
+        );
+
+        // Setting the state that we are already in doesn't transition.
+        set_state(&session.world, GameState::Playing);
+        session.stages.run(&mut session.world);
+        set_state(&session.world, GameState::Paused);
+        session.stages.run(&mut session.world);
+        assert_eq!(
+            session.world.resource::<Log>().0,
+            [
+                "enter menu",
+                "exit menu",
+                "enter playing",
+                "playing",
+                "playing",
+                "pause"
+            ]
+        );
+
+        // Restoring a snapshot replays the transitions that happened after it.
+        session.world.load_snapshot(snapshot);
+        session.stages.run(&mut session.world);
+        assert_eq!(
+            session.world.resource::<Log>().0,
+            ["enter menu", "exit menu", "enter playing", "playing"]
+        );
+    }
+
+    #[test]
+    fn next_state_is_only_changed_by_transitions() {
+        let mut sessions = Sessions::default();
+        let mut builder = SessionBuilder::new("test");
+        builder.add_state(GameState::Menu);
+        let session = builder.finish_and_add(&mut sessions);
+        let changed = |world: &World| {
+            world
+                .resources
+                .untyped()
+                .get(NextState::<GameState>::schema())
+                .ticks()
+                .get()
+                .changed
+        };
+
+        session.stages.run(&mut session.world);
+        let before = changed(&session.world);
+        session.stages.run(&mut session.world);
+        assert_eq!(changed(&session.world), before);
+
+        set_state(&session.world, GameState::Playing);
+        session.stages.run(&mut session.world);
+        assert!(changed(&session.world) > before);
+    }
+}
@@ -1,5 +1,7 @@
 use proc_macro::TokenStream;
-use proc_macro2::{Punct, Spacing, TokenStream as TokenStream2, TokenTree as TokenTree2};
+use proc_macro2::{
+    Delimiter, Punct, Spacing, TokenStream as TokenStream2, TokenTree as TokenTree2,
+};
 use quote::{format_ident, quote, quote_spanned, spanned::Spanned};
 use venial::{GenericBound, StructFields};
 
@@ -58,6 +60,22 @@ macro_rules! throw {
 /// This is critical in the case of bones' networking which will panic if your type is in the world
 /// and does not implement clone during the network rollback.
 ///
+/// ## version attribute
+/// The `version` schema attribute sets the version of a struct's schema, by adding `SchemaVersion`
+/// type data to it. Migrations for data that was serialized with older versions can be added with
+/// `SchemaMigrations` type data:
+/// ```ignore
+/// #[derive(HasSchema, Clone, Default)]
+/// #[repr(C)]
+/// #[schema(version = 1)]
+/// #[type_data(SchemaMigrations::default().migration(0, migrate_v0))]
+/// struct Data {
+///     value: u32,
+/// }
+/// ```
+/// Only structs with named fields may have a version, because the version is stored as a field.
+/// This requires the `serde` feature of `bones_schema`.
+///
 /// ## type_data attribute
 /// This attribute takes an expression and stores that value in what is basically
 /// a type keyed map accessible from your type's Schema.
@@ -76,6 +94,25 @@ macro_rules! throw {
 /// #[derive_type_data(OtherType)] // OtherType implements FromType<Data>
 /// struct Data;
 /// ```
+/// ## schema_attr attribute
+/// This attribute may be put on struct fields to add custom attributes to the field's
+/// `StructFieldInfo`, which can be read at runtime through the schema. Each attribute is either a
+/// flag, a value, or a list of values:
+/// ```ignore
+/// #[derive(HasSchema, Clone, Default)]
+/// #[repr(C)]
+/// struct Data {
+///     #[schema_attr(range(0.0, 1.0), tooltip = "The volume of the sound.")]
+///     volume: f32,
+///     #[schema_attr(skip_serialize)]
+///     cache: u32,
+/// }
+/// ```
+/// Values may be booleans, integers, floats, or strings. See `SchemaAttr` for the attributes that
+/// change how fields are serialized.
+///
+/// The doc comment of a field is added as a `doc` attribute with a string value.
+///
 /// ## Known Limitations
 ///
 /// Currently it isn't possible to construct a struct that contains itself. For example, this will
@@ -91,7 +128,7 @@ macro_rules! throw {
 /// If this is a problem for your use-case, please open an issue.
 #[proc_macro_derive(
     HasSchema,
-    attributes(schema, derive_type_data, type_data, schema_module)
+    attributes(schema, derive_type_data, type_data, schema_module, schema_attr)
 )]
 pub fn derive_has_schema(input: TokenStream) -> TokenStream {
     let input = venial::parse_declaration(input.into()).unwrap();
@@ -128,6 +165,31 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
             .filter(|x| x.path.len() == 1 && x.path[0].to_string() == "type_data")
             .map(|x| x.get_value_tokens())
             .map(|x| x.iter().cloned().collect::<TokenStream2>());
+        let has_named_fields = matches!(
+            &input,
+            venial::Declaration::Struct(venial::Struct {
+                fields: StructFields::Named(named),
+                ..
+            }) if named.fields.iter().next().is_some()
+        );
+        let add_version = get_flags_for_attr(&input, "schema")
+            .into_iter()
+            .find_map(|flag| {
+                let (key, value) = flag.split_once('=')?;
+                (key.trim() == "version").then(|| value.trim().parse::<u32>())
+            })
+            .map(|version| match version {
+                // The version is serialized as a field, so other types can't store it.
+                Ok(_) if !has_named_fields => quote! {
+                    compile_error!("Only structs with named fields may have a schema version");
+                },
+                Ok(version) => quote! {
+                    tds.insert(#schema_mod::migration::SchemaVersion(#version)).unwrap();
+                },
+                Err(_) => quote! {
+                    compile_error!("The schema version must be a `u32`");
+                },
+            });
 
         quote! {
             {
@@ -136,6 +198,7 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
                 #(
                     tds.insert(#add_type_datas).unwrap();
                 ),*
+                #add_version
                 tds
             }
         }
@@ -193,10 +256,12 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
                     .iter()
                     .map(|(field, _)| {
                         let ty = &field.ty;
+                        let attrs = get_field_attrs(&field.attributes, &schema_mod);
                         quote_spanned! {field.ty.__span() =>
                             #schema_mod::StructFieldInfo {
                                 name: None,
                                 schema: <#ty as #schema_mod::HasSchema>::schema(),
+                                attrs: #attrs,
                             }
                         }
                     })
@@ -207,6 +272,7 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
                     .map(|(field, _)| {
                         let name = &field.name;
                         let ty = &field.ty;
+                        let attrs = get_field_attrs(&field.attributes, &schema_mod);
                         let opaque = field.attributes.iter().any(|attr| {
                             &attr.path[0].to_string() == "schema"
                                 && &attr.value.get_value_tokens()[0].to_string() == "opaque"
@@ -234,6 +300,7 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
                                             drop_fn: Some(<Self as #schema_mod::raw_fns::RawDrop>::raw_drop_cb()),
                                         })
                                     },
+                                    attrs: #attrs,
                                 }
                             }
                         } else {
@@ -241,6 +308,7 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
                                 #schema_mod::StructFieldInfo {
                                     name: Some(stringify!(#name).into()),
                                     schema: <#ty as #schema_mod::HasSchema>::schema(),
+                                    attrs: #attrs,
                                 }
                             }
                         }
@@ -469,3 +537,99 @@ fn get_flags_for_attr(input: &venial::Declaration, attr_name: &str) -> Vec<Strin
             acc
         })
 }
+
+/// Get the tokens for a `Vec` of the `SchemaAttr`s that were added to a field with
+/// `#[schema_attr(...)]` attributes, and a `doc` attribute with the field's doc comment.
+///
+/// For example, `#[schema_attr(skip, tooltip = "Hi", range(0, 1))]` adds a flag, a value, and a
+/// list attribute.
+fn get_field_attrs(attributes: &[venial::Attribute], schema_mod: &TokenStream2) -> TokenStream2 {
+    let attr_value = |tokens: &[TokenTree2]| {
+        let tokens = tokens.iter().cloned().collect::<TokenStream2>();
+        quote!(#schema_mod::SchemaAttrValue::from(#tokens))
+    };
+
+    let mut attrs = Vec::new();
+    for attr in attributes
+        .iter()
+        .filter(|attr| attr.path.len() == 1 && attr.path[0].to_string() == "schema_attr")
+    {
+        for item in split_on_commas(attr.value.get_value_tokens()) {
+            let Some((TokenTree2::Ident(name), rest)) = item.split_first() else {
+                let span = attr.path[0].span();
+                attrs.push(quote_spanned!(span => compile_error!("Expected an attribute name")));
+                continue;
+            };
+            let value = match rest {
+                [] => quote!(None),
+                [TokenTree2::Punct(eq), value @ ..] if eq.as_char() == '=' && !value.is_empty() => {
+                    let value = attr_value(value);
+                    quote!(Some(#value))
+                }
+                [TokenTree2::Group(group)] if group.delimiter() == Delimiter::Parenthesis => {
+                    let tokens = group.stream().into_iter().collect::<Vec<_>>();
+                    let values = split_on_commas(&tokens)
+                        .into_iter()
+                        .map(|value| attr_value(&value));
+                    quote!(Some(#schema_mod::SchemaAttrValue::List(vec![#(#values),*])))
+                }
+                _ => {
+                    let span = name.span();
+                    attrs.push(quote_spanned!(span =>
+                        compile_error!("Expected `name`, `name = value`, or `name(values...)`")
+                    ));
+                    continue;
+                }
+            };
+            let name = name.to_string();
+            attrs.push(quote! {
+                #schema_mod::SchemaAttr {
+                    name: #name.into(),
+                    value: #value,
+                }
+            });
+        }
+    }
+
+    // Doc comments are added as a `doc` attribute, so that tooling can describe the field.
+    let docs = attributes
+        .iter()
+        .filter(|attr| attr.path.len() == 1 && attr.path[0].to_string() == "doc")
+        .map(|attr| {
+            attr.value
+                .get_value_tokens()
+                .iter()
+                .cloned()
+                .collect::<TokenStream2>()
+        })
+        .collect::<Vec<_>>();
+    if !docs.is_empty() {
+        attrs.push(quote! {
+            #schema_mod::SchemaAttr {
+                name: "doc".into(),
+                value: Some(#schema_mod::SchemaAttrValue::from(
+                    [#(#docs),*]
+                        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                        .join("\n")
+                )),
+            }
+        });
+    }
+
+    quote!(vec![#(#attrs),*])
+}
+
+/// Split a list of tokens on the commas between them, ignoring a trailing comma.
+fn split_on_commas(tokens: &[TokenTree2]) -> Vec<Vec<TokenTree2>> {
+    let mut items = vec![Vec::new()];
+    for token in tokens {
+        match token {
+            TokenTree2::Punct(x) if x.as_char() == ',' => items.push(Vec::new()),
+            x => items.last_mut().unwrap().push(x.clone()),
+        }
+    }
+    if items.last().unwrap().is_empty() {
+        items.pop();
+    }
+    items
+}
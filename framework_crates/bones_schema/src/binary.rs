@@ -0,0 +1,769 @@
+//! Compact binary encoding for schema values.
+//!
+//! Values are encoded by walking their [`SchemaKind`], without going through [`serde`][serde].
+//! This is useful for save files, network messages, and snapshots, where the data is only read by
+//! the same schemas that wrote it.
+//!
+//! The encoded data starts with a header that contains a [fingerprint][schema_fingerprint] of the
+//! value's schema. Decoding data with a schema that doesn't match the fingerprint returns
+//! [`BinaryError::SchemaMismatch`] instead of misreading the data.
+//!
+//! After the header, values are encoded as follows:
+//!
+//! - Structs are encoded as their fields, in order, without field names.
+//! - Enums are encoded as the index of the variant followed by the variant's fields.
+//! - Vecs and maps are encoded as their length followed by their items or key-value pairs. Vecs
+//!   and maps of items that are encoded without any bytes, like unit structs, may not have more
+//!   than [`MAX_ZERO_SIZED_ITEMS`] items.
+//! - Options are encoded as a `0` byte for [`None`], or a `1` byte followed by the value.
+//! - Boxes are encoded as the value that they contain.
+//! - Unsigned integers, lengths, and enum variant indexes are encoded as
+//!   [LEB128](https://en.wikipedia.org/wiki/LEB128) varints, and signed integers are zigzag encoded
+//!   before being written as varints, so that small numbers take up fewer bytes. [`u8`] and [`i8`]
+//!   are written as a single byte.
+//! - Floats are encoded as their little-endian bytes.
+//! - [`String`]s and [`Ustr`]s are encoded as their length followed by their UTF-8 bytes.
+//!
+//! Opaque types, other than [`Ustr`], cannot be encoded.
+//!
+//! ```
+//! # use bones_schema::prelude::*;
+//! #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+//! #[repr(C)]
+//! struct Player {
+//!     name: String,
+//!     score: u32,
+//! }
+//!
+//! let player = Player {
+//!     name: "Jumpy".into(),
+//!     score: 300,
+//! };
+//! let bytes = encode_binary(player.as_schema_ref()).unwrap();
+//! let loaded = decode_binary(Player::schema(), &bytes).unwrap();
+//! assert_eq!(loaded.cast_ref::<Player>(), &player);
+//!
+//! // Decoding with a different schema is an error.
+//! assert!(matches!(
+//!     decode_binary(u32::schema(), &bytes),
+//!     Err(BinaryError::SchemaMismatch { .. })
+//! ));
+//! ```
+//!
+//! [serde]: https://docs.rs/serde
+
+use ustr::{ustr, Ustr};
+
+use crate::prelude::*;
+
+/// The bytes at the start of binary encoded data.
+const MAGIC: [u8; 4] = *b"BNSB";
+/// The version of the binary format.
+const VERSION: u8 = 1;
+/// The length of the header, which is the magic bytes, the version, and the schema fingerprint.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+/// The maximum number of items in a decoded vec or map whose items are encoded without any bytes.
+///
+/// The length of other vecs and maps is limited by the size of the data, but decoding zero-sized
+/// items doesn't consume any data, so a corrupted or malicious length could make the decoder
+/// allocate forever.
+pub const MAX_ZERO_SIZED_ITEMS: usize = 1 << 16;
+
+/// Error returned when encoding or decoding binary data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+    /// The data doesn't start with a valid header.
+    InvalidHeader,
+    /// The data was encoded with an unsupported version of the binary format.
+    UnsupportedVersion(u8),
+    /// The data was encoded with a different schema than the one used to decode it.
+    SchemaMismatch {
+        /// The fingerprint of the schema used to decode the data.
+        expected: u64,
+        /// The fingerprint of the schema that the data was encoded with.
+        found: u64,
+    },
+    /// The data ended before the value was fully decoded.
+    UnexpectedEnd,
+    /// There was data left over after the value was decoded.
+    TrailingBytes(usize),
+    /// The data contains an invalid value.
+    InvalidData(String),
+    /// The value contains an opaque type, which can't be encoded or decoded.
+    Opaque(Ustr),
+}
+
+impl std::error::Error for BinaryError {}
+impl std::fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryError::InvalidHeader => write!(f, "Data doesn't have a valid binary header"),
+            BinaryError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported binary format version: {version}")
+            }
+            BinaryError::SchemaMismatch { expected, found } => write!(
+                f,
+                "Data was encoded with a different schema: expected fingerprint {expected:016x}, \
+                found {found:016x}"
+            ),
+            BinaryError::UnexpectedEnd => write!(f, "Unexpected end of data"),
+            BinaryError::TrailingBytes(count) => {
+                write!(f, "{count} bytes were left over after decoding")
+            }
+            BinaryError::InvalidData(msg) => write!(f, "Invalid data: {msg}"),
+            BinaryError::Opaque(name) => {
+                write!(f, "Cannot encode or decode opaque type `{name}`")
+            }
+        }
+    }
+}
+
+/// Encode a value in the compact binary format.
+///
+/// See the [module documentation][self].
+pub fn encode_binary(value: SchemaRef) -> Result<Vec<u8>, BinaryError> {
+    let mut bytes = Vec::new();
+    encode_binary_into(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Encode a value in the compact binary format, appending it to `bytes`.
+///
+/// See the [module documentation][self].
+pub fn encode_binary_into(value: SchemaRef, bytes: &mut Vec<u8>) -> Result<(), BinaryError> {
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&schema_fingerprint(value.schema()).to_le_bytes());
+    encode_value(value, bytes)
+}
+
+/// Decode a value with the given schema from the compact binary format.
+///
+/// Returns an error if the data was encoded with a different schema, or if there is data left over
+/// after the value was decoded.
+///
+/// # Panics
+///
+/// Panics if the schema, or the schema of any vec item, map entry, option value, or enum variant
+/// field that is decoded, doesn't have a `default_fn`.
+pub fn decode_binary(schema: &'static Schema, bytes: &[u8]) -> Result<SchemaBox, BinaryError> {
+    let mut value = SchemaBox::default(schema);
+    decode_binary_into(value.as_mut(), bytes)?;
+    Ok(value)
+}
+
+/// Decode binary data into an existing value, replacing its contents.
+///
+/// See [`decode_binary()`].
+pub fn decode_binary_into(value: SchemaRefMut, bytes: &[u8]) -> Result<(), BinaryError> {
+    if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+        return Err(BinaryError::InvalidHeader);
+    }
+    let version = bytes[MAGIC.len()];
+    if version != VERSION {
+        return Err(BinaryError::UnsupportedVersion(version));
+    }
+    let found = u64::from_le_bytes(bytes[MAGIC.len() + 1..HEADER_LEN].try_into().unwrap());
+    let expected = schema_fingerprint(value.schema());
+    if found != expected {
+        return Err(BinaryError::SchemaMismatch { expected, found });
+    }
+
+    let mut reader = Reader(&bytes[HEADER_LEN..]);
+    decode_value(value, &mut reader)?;
+    if !reader.0.is_empty() {
+        return Err(BinaryError::TrailingBytes(reader.0.len()));
+    }
+    Ok(())
+}
+
+/// Get a fingerprint of the layout of a schema, which is stored in the header of binary encoded
+/// data.
+///
+/// The fingerprint is a hash of the kinds of the schema and the schemas that it contains, along
+/// with the names of struct fields and enum variants. It doesn't depend on the name of the schema,
+/// so renaming or moving a type doesn't invalidate data that was encoded with it, but changing its
+/// fields does.
+pub fn schema_fingerprint(schema: &Schema) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hash_schema(schema, &mut hasher);
+    hasher.0
+}
+
+/// Simple FNV-1a hasher, used for schema fingerprints because it gives the same result on every
+/// platform and in every build.
+struct Fnv1a(u64);
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+}
+
+fn hash_schema(schema: &Schema, hasher: &mut Fnv1a) {
+    if schema == Ustr::schema() {
+        hasher.write(b"ustr");
+        return;
+    }
+    match &schema.kind {
+        SchemaKind::Struct(s) => {
+            hasher.write(b"struct");
+            hasher.write(&(s.fields.len() as u64).to_le_bytes());
+            for field in &s.fields {
+                hasher.write_str(field.name.as_ref().map(|x| x.as_str()).unwrap_or(""));
+                hash_schema(field.schema, hasher);
+            }
+        }
+        SchemaKind::Vec(item) => {
+            hasher.write(b"vec");
+            hash_schema(item, hasher);
+        }
+        SchemaKind::Enum(e) => {
+            hasher.write(b"enum");
+            hasher.write(&(e.variants.len() as u64).to_le_bytes());
+            for variant in &e.variants {
+                hasher.write_str(&variant.name);
+                hash_schema(variant.schema, hasher);
+            }
+        }
+        SchemaKind::Map { key, value } => {
+            hasher.write(b"map");
+            hash_schema(key, hasher);
+            hash_schema(value, hasher);
+        }
+        // Boxes are encoded the same way as the value that they contain.
+        SchemaKind::Box(inner) => hash_schema(inner, hasher),
+        SchemaKind::Option(o) => {
+            hasher.write(b"option");
+            hash_schema(o.value, hasher);
+        }
+        SchemaKind::Primitive(p) => hasher.write(match p {
+            Primitive::Bool => b"bool",
+            Primitive::U8 => b"u8",
+            Primitive::U16 => b"u16",
+            Primitive::U32 => b"u32",
+            Primitive::U64 => b"u64",
+            Primitive::U128 => b"u128",
+            Primitive::I8 => b"i8",
+            Primitive::I16 => b"i16",
+            Primitive::I32 => b"i32",
+            Primitive::I64 => b"i64",
+            Primitive::I128 => b"i128",
+            Primitive::F32 => b"f32",
+            Primitive::F64 => b"f64",
+            Primitive::String => b"string",
+            Primitive::Opaque { .. } => b"opaque",
+        }),
+    }
+}
+
+fn encode_value(value: SchemaRef, bytes: &mut Vec<u8>) -> Result<(), BinaryError> {
+    if value.schema() == Ustr::schema() {
+        write_str(value.cast::<Ustr>(), bytes);
+        return Ok(());
+    }
+
+    match value.access() {
+        SchemaRefAccess::Struct(s) => {
+            for field in s.fields() {
+                encode_value(field.value, bytes)?;
+            }
+        }
+        SchemaRefAccess::Vec(v) => {
+            write_varint(v.len() as u128, bytes);
+            for item in v.iter() {
+                encode_value(item, bytes)?;
+            }
+        }
+        SchemaRefAccess::Map(m) => {
+            write_varint(m.len() as u128, bytes);
+            for (key, value) in m.iter() {
+                encode_value(key, bytes)?;
+                encode_value(value, bytes)?;
+            }
+        }
+        SchemaRefAccess::Enum(e) => {
+            write_varint(e.variant_idx() as u128, bytes);
+            for field in e.value().fields() {
+                encode_value(field.value, bytes)?;
+            }
+        }
+        SchemaRefAccess::Option(o) => match o.value() {
+            Some(value) => {
+                bytes.push(1);
+                encode_value(value, bytes)?;
+            }
+            None => bytes.push(0),
+        },
+        SchemaRefAccess::Primitive(p) => match p {
+            PrimitiveRef::Bool(b) => bytes.push(*b as u8),
+            PrimitiveRef::U8(n) => bytes.push(*n),
+            PrimitiveRef::U16(n) => write_varint(*n as u128, bytes),
+            PrimitiveRef::U32(n) => write_varint(*n as u128, bytes),
+            PrimitiveRef::U64(n) => write_varint(*n as u128, bytes),
+            PrimitiveRef::U128(n) => write_varint(*n, bytes),
+            PrimitiveRef::I8(n) => bytes.push(*n as u8),
+            PrimitiveRef::I16(n) => write_varint(zigzag(*n as i128), bytes),
+            PrimitiveRef::I32(n) => write_varint(zigzag(*n as i128), bytes),
+            PrimitiveRef::I64(n) => write_varint(zigzag(*n as i128), bytes),
+            PrimitiveRef::I128(n) => write_varint(zigzag(*n), bytes),
+            PrimitiveRef::F32(n) => bytes.extend_from_slice(&n.to_le_bytes()),
+            PrimitiveRef::F64(n) => bytes.extend_from_slice(&n.to_le_bytes()),
+            PrimitiveRef::String(s) => write_str(s, bytes),
+            PrimitiveRef::Opaque { schema_ref, .. } => {
+                return Err(BinaryError::Opaque(schema_ref.schema().full_name))
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn decode_value(mut value: SchemaRefMut, reader: &mut Reader) -> Result<(), BinaryError> {
+    let schema = value.schema();
+    if schema == Ustr::schema() {
+        *value.cast_mut::<Ustr>() = ustr(reader.read_str()?);
+        return Ok(());
+    }
+
+    match &schema.kind {
+        SchemaKind::Struct(_) => {
+            for field in StructRefMutAccess(value).into_fields() {
+                decode_value(field.value, reader)?;
+            }
+        }
+        SchemaKind::Vec(item_schema) => {
+            // SOUND: schema asserts this is a SchemaVec.
+            let v = unsafe { &mut *(value.as_ptr() as *mut SchemaVec) };
+            v.clear();
+            for _ in 0..reader.read_items_len(min_encoded_len(item_schema))? {
+                let mut item = SchemaBox::default(item_schema);
+                decode_value(item.as_mut(), reader)?;
+                v.push_box(item);
+            }
+        }
+        SchemaKind::Map {
+            key: key_schema,
+            value: value_schema,
+        } => {
+            // SOUND: schema asserts this is a SchemaMap.
+            let m = unsafe { &mut *(value.as_ptr() as *mut SchemaMap) };
+            *m = SchemaMap::new(key_schema, value_schema);
+            let item_len =
+                min_encoded_len(key_schema).saturating_add(min_encoded_len(value_schema));
+            for _ in 0..reader.read_items_len(item_len)? {
+                let mut key = SchemaBox::default(key_schema);
+                decode_value(key.as_mut(), reader)?;
+                let mut map_value = SchemaBox::default(value_schema);
+                decode_value(map_value.as_mut(), reader)?;
+                m.insert_box(key, map_value);
+            }
+        }
+        SchemaKind::Enum(info) => {
+            let var_idx = reader.read_varint()?;
+            let variant = info
+                .variants
+                .get(var_idx as usize)
+                .ok_or_else(|| BinaryError::InvalidData(format!("invalid variant {var_idx}")))?;
+
+            // Decode the fields of the variant first, so that the enum is never left in an invalid
+            // state if decoding fails.
+            let fields = variant
+                .schema
+                .kind
+                .as_struct()
+                .unwrap()
+                .fields
+                .iter()
+                .map(|field| {
+                    let mut field = SchemaBox::default(field.schema);
+                    decode_value(field.as_mut(), reader)?;
+                    Ok(field)
+                })
+                .collect::<Result<Vec<_>, BinaryError>>()?;
+
+            let ptr = value.as_ptr();
+            let value_offset = schema.field_offsets()[0].1;
+            // SOUND: the enum is dropped before writing the new tag and variant fields, and the
+            // schema asserts that the tag and field offsets are valid.
+            unsafe {
+                if let Some(drop_fn) = &schema.drop_fn {
+                    (drop_fn.get())(ptr);
+                }
+                match info.tag_type {
+                    EnumTagType::U8 => ptr.cast::<u8>().write(var_idx as u8),
+                    EnumTagType::U16 => ptr.cast::<u16>().write(var_idx as u16),
+                    EnumTagType::U32 => ptr.cast::<u32>().write(var_idx as u32),
+                }
+                for (field, (_, offset)) in fields.into_iter().zip(variant.schema.field_offsets()) {
+                    let dst = ptr.cast::<u8>().add(value_offset + offset);
+                    dst.copy_from_nonoverlapping(
+                        field.as_ptr() as *const u8,
+                        field.schema().layout().size(),
+                    );
+                    field.forget();
+                }
+            }
+        }
+        SchemaKind::Box(_) => decode_value(value.into_box().unwrap(), reader)?,
+        SchemaKind::Option(o) => {
+            let mut option = OptionRefMutAccess(value);
+            match reader.read_byte()? {
+                0 => option.set_none(),
+                1 => {
+                    let mut inner = SchemaBox::default(o.value);
+                    decode_value(inner.as_mut(), reader)?;
+                    // The value was created with the schema of the option's value, so it matches.
+                    option.set_some(inner).unwrap();
+                }
+                n => return Err(BinaryError::InvalidData(format!("invalid option tag {n}"))),
+            }
+        }
+        SchemaKind::Primitive(p) => match p {
+            Primitive::Bool => {
+                *value.cast_mut() = match reader.read_byte()? {
+                    0 => false,
+                    1 => true,
+                    n => return Err(BinaryError::InvalidData(format!("invalid bool {n}"))),
+                }
+            }
+            Primitive::U8 => *value.cast_mut() = reader.read_byte()?,
+            Primitive::U16 => *value.cast_mut::<u16>() = reader.read_int()?,
+            Primitive::U32 => *value.cast_mut::<u32>() = reader.read_int()?,
+            Primitive::U64 => *value.cast_mut::<u64>() = reader.read_int()?,
+            Primitive::U128 => *value.cast_mut() = reader.read_varint()?,
+            Primitive::I8 => *value.cast_mut() = reader.read_byte()? as i8,
+            Primitive::I16 => *value.cast_mut::<i16>() = reader.read_signed_int()?,
+            Primitive::I32 => *value.cast_mut::<i32>() = reader.read_signed_int()?,
+            Primitive::I64 => *value.cast_mut::<i64>() = reader.read_signed_int()?,
+            Primitive::I128 => *value.cast_mut() = unzigzag(reader.read_varint()?),
+            Primitive::F32 => *value.cast_mut() = f32::from_le_bytes(reader.read_array()?),
+            Primitive::F64 => *value.cast_mut() = f64::from_le_bytes(reader.read_array()?),
+            Primitive::String => *value.cast_mut() = reader.read_str()?.to_string(),
+            Primitive::Opaque { .. } => return Err(BinaryError::Opaque(schema.full_name)),
+        },
+    }
+
+    Ok(())
+}
+
+/// Get the minimum number of bytes that a value of the schema is encoded with.
+fn min_encoded_len(schema: &Schema) -> usize {
+    if schema == Ustr::schema() {
+        return 1;
+    }
+    match &schema.kind {
+        SchemaKind::Struct(s) => s
+            .fields
+            .iter()
+            .map(|field| min_encoded_len(field.schema))
+            .fold(0, usize::saturating_add),
+        SchemaKind::Box(inner) => min_encoded_len(inner),
+        // The length, variant index, or option tag takes at least one byte.
+        SchemaKind::Vec(_) | SchemaKind::Map { .. } | SchemaKind::Enum(_) => 1,
+        SchemaKind::Option(_) => 1,
+        SchemaKind::Primitive(p) => match p {
+            Primitive::F32 => 4,
+            Primitive::F64 => 8,
+            // Opaque values can't be decoded, so decoding fails at the first item.
+            Primitive::Opaque { .. } => 0,
+            _ => 1,
+        },
+    }
+}
+
+fn zigzag(n: i128) -> u128 {
+    ((n << 1) ^ (n >> 127)) as u128
+}
+
+fn unzigzag(n: u128) -> i128 {
+    (n >> 1) as i128 ^ -((n & 1) as i128)
+}
+
+fn write_varint(mut n: u128, bytes: &mut Vec<u8>) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+fn write_str(s: &str, bytes: &mut Vec<u8>) {
+    write_varint(s.len() as u128, bytes);
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+/// Helper for reading binary data.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        if len > self.0.len() {
+            return Err(BinaryError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], BinaryError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_varint(&mut self) -> Result<u128, BinaryError> {
+        let mut n = 0u128;
+        for shift in (0..128).step_by(7) {
+            let byte = self.read_byte()?;
+            let bits = (byte & 0x7f) as u128;
+            if bits << shift >> shift != bits {
+                break;
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(BinaryError::InvalidData("varint is too large".into()))
+    }
+
+    fn read_int<T: TryFrom<u128>>(&mut self) -> Result<T, BinaryError> {
+        let n = self.read_varint()?;
+        T::try_from(n).map_err(|_| BinaryError::InvalidData(format!("integer out of range: {n}")))
+    }
+
+    fn read_signed_int<T: TryFrom<i128>>(&mut self) -> Result<T, BinaryError> {
+        let n = unzigzag(self.read_varint()?);
+        T::try_from(n).map_err(|_| BinaryError::InvalidData(format!("integer out of range: {n}")))
+    }
+
+    fn read_len(&mut self) -> Result<usize, BinaryError> {
+        self.read_int()
+    }
+
+    /// Read the length of a vec or map whose items are encoded with at least `item_len` bytes.
+    fn read_items_len(&mut self, item_len: usize) -> Result<usize, BinaryError> {
+        let len = self.read_len()?;
+        if item_len == 0 {
+            if len > MAX_ZERO_SIZED_ITEMS {
+                return Err(BinaryError::InvalidData(format!(
+                    "too many zero-sized items: {len}"
+                )));
+            }
+        } else if len > self.0.len() / item_len {
+            // Check the length before decoding any items, so the length doesn't need to be
+            // trusted.
+            return Err(BinaryError::UnexpectedEnd);
+        }
+        Ok(len)
+    }
+
+    fn read_str(&mut self) -> Result<&'a str, BinaryError> {
+        let len = self.read_len()?;
+        std::str::from_utf8(self.read_bytes(len)?)
+            .map_err(|e| BinaryError::InvalidData(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bones_schema_macros::HasSchema;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Save {
+        name: String,
+        tag: Ustr,
+        level: u8,
+        score: i64,
+        position: Position,
+        items: SVec<Item>,
+        stats: SMap<String, u32>,
+        pet: Option<String>,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Position(f32, f32);
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Size {
+        width: f32,
+        height: f32,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Point {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Unit;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C, u8)]
+    enum Item {
+        #[default]
+        Empty,
+        Sword {
+            damage: u32,
+        },
+        Potion(String),
+    }
+
+    #[test]
+    fn round_trip() {
+        let save = Save {
+            name: "Fishy".into(),
+            tag: ustr("player"),
+            level: 3,
+            score: -1200,
+            position: Position(1.5, -2.0),
+            items: [
+                Item::Sword { damage: 40 },
+                Item::Empty,
+                Item::Potion("health".into()),
+            ]
+            .into_iter()
+            .collect(),
+            stats: [("jumps".to_string(), 1000)].into_iter().collect(),
+            pet: Some("Spot".into()),
+        };
+
+        let bytes = encode_binary(save.as_schema_ref()).unwrap();
+        let loaded = decode_binary(Save::schema(), &bytes).unwrap();
+        assert_eq!(loaded.cast_ref::<Save>(), &save);
+
+        // Decoding into an existing value replaces its contents.
+        let mut existing = Save {
+            items: [Item::Potion("mana".into())].into_iter().collect(),
+            pet: Some("Rex".into()),
+            ..Default::default()
+        };
+        decode_binary_into(existing.as_schema_mut(), &bytes).unwrap();
+        assert_eq!(existing, save);
+    }
+
+    #[test]
+    fn varints() {
+        for n in [0, 1, 127, 128, 300, u64::MAX as u128, u128::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(n, &mut bytes);
+            assert_eq!(Reader(&bytes).read_varint(), Ok(n));
+        }
+        for n in [0, -1, 1, -64, 64, i128::MIN, i128::MAX] {
+            assert_eq!(unzigzag(zigzag(n)), n);
+        }
+
+        // Small numbers only take a single byte.
+        let bytes = encode_binary(100u64.as_schema_ref()).unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN + 1);
+        let bytes = encode_binary((-3i32).as_schema_ref()).unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN + 1);
+    }
+
+    #[test]
+    fn invalid_data() {
+        let bytes = encode_binary(Position(1.0, 2.0).as_schema_ref()).unwrap();
+
+        assert!(matches!(
+            decode_binary(Save::schema(), &bytes),
+            Err(BinaryError::SchemaMismatch { .. })
+        ));
+        assert_eq!(
+            decode_binary(Position::schema(), &bytes[..bytes.len() - 1]).unwrap_err(),
+            BinaryError::UnexpectedEnd
+        );
+        assert_eq!(
+            decode_binary(Position::schema(), &[&bytes[..], &[0]].concat()).unwrap_err(),
+            BinaryError::TrailingBytes(1)
+        );
+        assert_eq!(
+            decode_binary(Position::schema(), b"not binary data").unwrap_err(),
+            BinaryError::InvalidHeader
+        );
+
+        let mut bytes = encode_binary(Item::Empty.as_schema_ref()).unwrap();
+        *bytes.last_mut().unwrap() = 7;
+        assert!(matches!(
+            decode_binary(Item::schema(), &bytes),
+            Err(BinaryError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn huge_lengths() {
+        // Replace the length of an empty vec with a huge length.
+        fn with_huge_len(value: SchemaRef) -> Vec<u8> {
+            let mut bytes = encode_binary(value).unwrap();
+            assert_eq!(bytes.pop(), Some(0));
+            write_varint(u64::MAX as u128, &mut bytes);
+            bytes
+        }
+
+        let bytes = with_huge_len(SVec::<u32>::default().as_schema_ref());
+        assert_eq!(
+            decode_binary(<SVec<u32>>::schema(), &bytes).unwrap_err(),
+            BinaryError::UnexpectedEnd
+        );
+        let bytes = with_huge_len(SMap::<String, Unit>::default().as_schema_ref());
+        assert_eq!(
+            decode_binary(<SMap<String, Unit>>::schema(), &bytes).unwrap_err(),
+            BinaryError::UnexpectedEnd
+        );
+        let bytes = with_huge_len(SVec::<Unit>::default().as_schema_ref());
+        assert!(matches!(
+            decode_binary(<SVec<Unit>>::schema(), &bytes),
+            Err(BinaryError::InvalidData(_))
+        ));
+
+        // Zero-sized items up to the limit are still decoded.
+        let units = (0..MAX_ZERO_SIZED_ITEMS).map(|_| Unit).collect::<SVec<_>>();
+        let bytes = encode_binary(units.as_schema_ref()).unwrap();
+        let loaded = decode_binary(<SVec<Unit>>::schema(), &bytes).unwrap();
+        assert_eq!(loaded.cast_ref::<SVec<Unit>>().len(), MAX_ZERO_SIZED_ITEMS);
+    }
+
+    #[test]
+    fn fingerprints() {
+        assert_eq!(
+            schema_fingerprint(Save::schema()),
+            schema_fingerprint(Save::schema())
+        );
+        assert_ne!(
+            schema_fingerprint(u32::schema()),
+            schema_fingerprint(u64::schema())
+        );
+        // Structs with the same field types but different field names don't match.
+        assert_ne!(
+            schema_fingerprint(Size::schema()),
+            schema_fingerprint(Point::schema())
+        );
+    }
+}
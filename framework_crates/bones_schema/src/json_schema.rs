@@ -0,0 +1,437 @@
+//! Export schemas as [JSON Schema](https://json-schema.org/) documents.
+//!
+//! JSON Schema documents describe the data accepted by the [`SchemaDeserializer`], so that editors
+//! can validate and autocomplete hand-written JSON and YAML files, like asset metadata. For
+//! example, VS Code uses them through the `json.schemas` and `yaml.schemas` settings.
+//!
+//! ```
+//! # use bones_schema::prelude::*;
+//! #[derive(HasSchema, Clone, Default)]
+//! #[repr(C)]
+//! struct Player {
+//!     /// The name shown above the player.
+//!     name: String,
+//!     speed: f32,
+//! }
+//!
+//! let document = json_schema(Player::schema());
+//! let player = &document["definitions"][json_schema_definition_name(Player::schema())];
+//! assert_eq!(
+//!     player["properties"]["name"]["description"],
+//!     "The name shown above the player."
+//! );
+//! ```
+//!
+//! Documents use JSON Schema draft 7, which is the latest draft supported by most editors. Structs
+//! and enums are added to the `definitions` of the document, and are referenced from the places
+//! that they are used.
+//!
+//! Enum variants with fields are written with a YAML tag, like `!Circle 2.0`, which can't be
+//! described by JSON Schema, so the variants are described by the schema of their fields only.
+//! Schemas with a custom [`SchemaDeserialize`] implementation accept any value, unless they are
+//! described with a [`JsonSchemaGenerator::with_override()`].
+
+use std::sync::Arc;
+
+use bones_utils::HashMap;
+use serde_json::{json, Map, Value};
+use ustr::Ustr;
+
+use crate::prelude::*;
+
+/// The URI of the JSON Schema draft used by generated documents.
+pub const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// A function that may replace the JSON Schema generated for a schema.
+pub type JsonSchemaOverrideFn =
+    Arc<dyn Fn(&mut JsonSchemaGenerator, &'static Schema) -> Option<Value> + Send + Sync>;
+
+/// Generates [JSON Schema](https://json-schema.org/) documents for schemas.
+///
+/// See the [module documentation][self].
+#[derive(Default)]
+pub struct JsonSchemaGenerator {
+    /// The definitions of the structs and enums in the document being generated.
+    definitions: Map<String, Value>,
+    /// The definition names that have been given to schemas.
+    names: HashMap<SchemaId, String>,
+    overrides: Vec<JsonSchemaOverrideFn>,
+}
+
+impl JsonSchemaGenerator {
+    /// Create a new generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a function that may replace the JSON Schema generated for a schema, by returning
+    /// [`Some`].
+    ///
+    /// This can be used to describe schemas that are deserialized differently than their layout,
+    /// like asset handles, which are deserialized from a path. The function may use
+    /// [`JsonSchemaGenerator::schema_value()`] to describe the schemas inside of the schema.
+    /// Overrides are checked in the order that they were added.
+    pub fn with_override<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut JsonSchemaGenerator, &'static Schema) -> Option<Value> + Send + Sync + 'static,
+    {
+        self.overrides.push(Arc::new(f));
+        self
+    }
+
+    /// Generate a JSON Schema document that validates data for the given schema.
+    pub fn document(&mut self, schema: &'static Schema) -> Value {
+        self.definitions.clear();
+        self.names.clear();
+        let root = self.schema_value(schema);
+        let mut document = json!({
+            "$schema": JSON_SCHEMA_DRAFT,
+            "title": schema.name.as_str(),
+        });
+        // Other keywords next to a `$ref` are ignored, so the root is referenced with an `allOf`.
+        document["allOf"] = json!([root]);
+        document["definitions"] = Value::Object(std::mem::take(&mut self.definitions));
+        document
+    }
+
+    /// Generate a JSON Schema document for every registered struct and enum schema that can be
+    /// deserialized.
+    ///
+    /// Schemas are registered when they are first used, so [`HasSchema::register_schema()`] may
+    /// need to be called for the schemas that should be exported.
+    pub fn registered_documents(&mut self) -> Vec<(&'static Schema, Value)> {
+        SCHEMA_REGISTRY
+            .schemas
+            .iter()
+            .filter(|schema| {
+                schema.default_fn.is_some()
+                    && matches!(schema.kind, SchemaKind::Struct(_) | SchemaKind::Enum(_))
+            })
+            .map(|schema| (schema, self.document(schema)))
+            .collect()
+    }
+
+    /// Get the JSON Schema for values of the given schema.
+    ///
+    /// Structs and enums are added to the definitions of the document that is being generated, and
+    /// a `$ref` to the definition is returned, so this should only be used by override functions.
+    /// Use [`JsonSchemaGenerator::document()`] to generate a complete document.
+    pub fn schema_value(&mut self, schema: &'static Schema) -> Value {
+        for f in self.overrides.clone() {
+            if let Some(value) = f(self, schema) {
+                return value;
+            }
+        }
+        // `Ustr`s are opaque, but the schema serializer handles them specially.
+        if schema == Ustr::schema() {
+            return json!({ "type": "string" });
+        }
+        if schema.type_data.get::<SchemaDeserialize>().is_some() {
+            return json!({});
+        }
+
+        match &schema.kind {
+            SchemaKind::Struct(s) if s.fields.len() == 1 && s.fields[0].name.is_none() => {
+                // Newtype structs are deserialized as their inner type.
+                self.schema_value(s.fields[0].schema)
+            }
+            SchemaKind::Struct(_) | SchemaKind::Enum(_) => self.definition_ref(schema),
+            SchemaKind::Vec(item) => json!({
+                "type": "array",
+                "items": self.schema_value(item),
+            }),
+            SchemaKind::Map { value, .. } => json!({
+                "type": "object",
+                "additionalProperties": self.schema_value(value),
+            }),
+            SchemaKind::Box(inner) => self.schema_value(inner),
+            SchemaKind::Option(o) => json!({
+                "anyOf": [self.schema_value(o.value), { "type": "null" }],
+            }),
+            SchemaKind::Primitive(p) => primitive_value(p),
+        }
+    }
+
+    /// Add the definition of a struct or enum schema to the document if it hasn't been added yet,
+    /// and get a `$ref` to it.
+    fn definition_ref(&mut self, schema: &'static Schema) -> Value {
+        let name = match self.names.get(&schema.id()) {
+            Some(name) => name.clone(),
+            None => {
+                let mut name = json_schema_definition_name(schema);
+                // Different schemas may have the same name, like generic types.
+                if self.definitions.contains_key(&name) {
+                    name = format!("{name}_{}", self.names.len());
+                }
+                self.names.insert(schema.id(), name.clone());
+                // Insert a placeholder first, so that recursive schemas refer to the definition.
+                self.definitions.insert(name.clone(), Value::Null);
+                let definition = self.definition(schema);
+                self.definitions.insert(name.clone(), definition);
+                name
+            }
+        };
+        json!({ "$ref": format!("#/definitions/{name}") })
+    }
+
+    /// Get the definition of a struct or enum schema.
+    fn definition(&mut self, schema: &'static Schema) -> Value {
+        let mut definition = match &schema.kind {
+            SchemaKind::Struct(s) => self.struct_value(schema, s),
+            SchemaKind::Enum(e) => {
+                let mut any_of = Vec::new();
+                let unit_variants = e
+                    .variants
+                    .iter()
+                    .filter(|v| is_unit_variant(v))
+                    .map(|v| &*v.name)
+                    .collect::<Vec<&str>>();
+                if !unit_variants.is_empty() {
+                    any_of.push(json!({ "type": "string", "enum": unit_variants }));
+                }
+                for variant in e.variants.iter().filter(|v| !is_unit_variant(v)) {
+                    let s = variant.schema.kind.as_struct().unwrap();
+                    let mut value = if s.fields.len() == 1 && s.fields[0].name.is_none() {
+                        self.schema_value(s.fields[0].schema)
+                    } else {
+                        self.struct_value(variant.schema, s)
+                    };
+                    set_keyword(&mut value, "title", Value::from(&*variant.name));
+                    any_of.push(value);
+                }
+                json!({ "anyOf": any_of })
+            }
+            _ => unreachable!("Only structs and enums have definitions"),
+        };
+        definition["title"] = schema.name.as_str().into();
+        definition
+    }
+
+    /// Get the JSON Schema for a struct with named or unnamed fields.
+    fn struct_value(&mut self, schema: &'static Schema, s: &StructSchemaInfo) -> Value {
+        let mut properties = Map::new();
+        if schema.type_data.get::<SchemaVersion>().is_some() {
+            properties.insert(
+                VERSION_KEY.into(),
+                json!({ "type": "integer", "minimum": 0 }),
+            );
+        }
+        let mut items = Vec::new();
+        for field in s.fields.iter().filter(|f| !f.skip_deserialize()) {
+            let mut value = self.schema_value(field.schema);
+            if let Some(doc) = field.doc() {
+                set_keyword(&mut value, "description", doc.into());
+            }
+            if let Some(name) = field.serialized_name() {
+                properties.insert(name.into(), value.clone());
+            }
+            items.push(value);
+        }
+
+        // Structs may be deserialized from a map of their named fields, or from a list of all of
+        // their fields. The other keywords only apply to values of the matching type.
+        json!({
+            "type": ["object", "array"],
+            "properties": properties,
+            "additionalProperties": false,
+            "items": items,
+            "additionalItems": false,
+        })
+    }
+}
+
+/// Generate a JSON Schema document that validates data for the given schema.
+///
+/// This is a shortcut for [`JsonSchemaGenerator::document()`] with a new generator.
+pub fn json_schema(schema: &'static Schema) -> Value {
+    JsonSchemaGenerator::new().document(schema)
+}
+
+/// Get the name of the definition of a struct or enum schema in a JSON Schema document.
+///
+/// The name is the [full name][SchemaData::full_name] of the schema, with `::` replaced by `.` and
+/// other characters that aren't allowed in a `$ref` replaced by `_`. Generic types with the same
+/// name may have a numbered suffix added to their definition name.
+pub fn json_schema_definition_name(schema: &Schema) -> String {
+    schema
+        .full_name
+        .replace("::", ".")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Set a keyword of a JSON Schema, like its `title` or `description`.
+fn set_keyword(value: &mut Value, keyword: &str, keyword_value: Value) {
+    // Other keywords next to a `$ref` are ignored, so references are wrapped in an `allOf`.
+    if value.get("$ref").is_some() {
+        *value = json!({ "allOf": [value.take()] });
+    }
+    if let Value::Object(value) = value {
+        value.insert(keyword.into(), keyword_value);
+    }
+}
+
+/// Get whether an enum variant has no fields, in which case it is deserialized from its name.
+fn is_unit_variant(variant: &VariantInfo) -> bool {
+    variant
+        .schema
+        .kind
+        .as_struct()
+        .is_some_and(|s| s.fields.is_empty())
+}
+
+/// Get the JSON Schema for a primitive.
+fn primitive_value(primitive: &Primitive) -> Value {
+    macro_rules! int {
+        ($ty:ty) => {{
+            let (min, max) = (<$ty>::MIN, <$ty>::MAX);
+            json!({ "type": "integer", "minimum": min, "maximum": max })
+        }};
+    }
+    match primitive {
+        Primitive::Bool => json!({ "type": "boolean" }),
+        Primitive::U8 => int!(u8),
+        Primitive::U16 => int!(u16),
+        Primitive::U32 => int!(u32),
+        Primitive::U64 => int!(u64),
+        Primitive::I8 => int!(i8),
+        Primitive::I16 => int!(i16),
+        Primitive::I32 => int!(i32),
+        Primitive::I64 => int!(i64),
+        // 128-bit integers don't fit in JSON numbers.
+        Primitive::U128 => json!({ "type": "integer", "minimum": 0 }),
+        Primitive::I128 => json!({ "type": "integer" }),
+        Primitive::F32 | Primitive::F64 => json!({ "type": "number" }),
+        Primitive::String => json!({ "type": "string" }),
+        Primitive::Opaque { .. } => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use bones_schema_macros::HasSchema;
+
+    #[derive(HasSchema, Clone, Default)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Settings {
+        /// The volume of the music.
+        volume: f32,
+        #[schema_attr(rename = "lang")]
+        language: Ustr,
+        #[schema_attr(skip)]
+        cache: u32,
+        shapes: SVec<Shape>,
+        keys: SMap<String, Option<u8>>,
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    #[schema_module(crate)]
+    #[repr(C, u8)]
+    enum Shape {
+        #[default]
+        Empty,
+        Circle(f32),
+        Rect {
+            width: f32,
+            height: f32,
+        },
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    #[schema(version = 2)]
+    struct Versioned {
+        value: u32,
+    }
+
+    fn definition<'a>(document: &'a Value, schema: &Schema) -> &'a Value {
+        &document["definitions"][json_schema_definition_name(schema)]
+    }
+
+    #[test]
+    fn structs() {
+        let document = json_schema(Settings::schema());
+        assert_eq!(document["$schema"], JSON_SCHEMA_DRAFT);
+        assert_eq!(
+            document["allOf"][0]["$ref"],
+            format!(
+                "#/definitions/{}",
+                json_schema_definition_name(Settings::schema())
+            )
+        );
+
+        let settings = definition(&document, Settings::schema());
+        assert_eq!(settings["title"], "Settings");
+        assert_eq!(
+            settings["properties"]["volume"],
+            json!({ "type": "number", "description": "The volume of the music." })
+        );
+        assert_eq!(settings["properties"]["lang"], json!({ "type": "string" }));
+        assert!(settings["properties"].get("language").is_none());
+        assert!(settings["properties"].get("cache").is_none());
+        assert_eq!(settings["items"].as_array().unwrap().len(), 4);
+        assert_eq!(
+            settings["properties"]["keys"],
+            json!({
+                "type": "object",
+                "additionalProperties": {
+                    "anyOf": [
+                        { "type": "integer", "minimum": 0, "maximum": 255 },
+                        { "type": "null" },
+                    ],
+                },
+            })
+        );
+
+        let versioned = json_schema(Versioned::schema());
+        let versioned = definition(&versioned, Versioned::schema());
+        assert_eq!(versioned["properties"][VERSION_KEY]["type"], "integer");
+    }
+
+    #[test]
+    fn enums() {
+        let document = json_schema(Settings::schema());
+        let shape = definition(&document, Shape::schema());
+        assert_eq!(shape["title"], "Shape");
+        assert_eq!(
+            shape["anyOf"][0],
+            json!({ "type": "string", "enum": ["Empty"] })
+        );
+        assert_eq!(
+            shape["anyOf"][1],
+            json!({ "type": "number", "title": "Circle" })
+        );
+        assert_eq!(shape["anyOf"][2]["title"], "Rect");
+        assert_eq!(
+            shape["anyOf"][2]["properties"]["width"],
+            json!({ "type": "number" })
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let document = JsonSchemaGenerator::new()
+            .with_override(|_, schema| {
+                (schema == Shape::schema()).then(|| json!({ "type": "string", "format": "shape" }))
+            })
+            .document(Settings::schema());
+        let settings = definition(&document, Settings::schema());
+        assert_eq!(
+            settings["properties"]["shapes"]["items"],
+            json!({ "type": "string", "format": "shape" })
+        );
+        assert!(definition(&document, Shape::schema()).is_null());
+    }
+}
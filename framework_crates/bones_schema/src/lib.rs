@@ -16,14 +16,17 @@ pub use bones_schema_macros::*;
 
 /// The prelude.
 pub mod prelude {
-    #[cfg(feature = "serde")]
-    pub use crate::ser_de::*;
+    #[cfg(feature = "json_schema")]
+    pub use crate::json_schema::*;
     pub use crate::{
         alloc::{SMap, SVec, SchemaMap, SchemaVec},
+        binary::*,
         ptr::*,
         registry::*,
         schema::*,
     };
+    #[cfg(feature = "serde")]
+    pub use crate::{migration::*, ser_de::*};
     #[cfg(feature = "derive")]
     pub use bones_schema_macros::*;
     pub use bones_utils;
@@ -33,6 +36,7 @@ mod schema;
 pub use schema::*;
 
 pub mod alloc;
+pub mod binary;
 pub mod ptr;
 pub mod raw_fns;
 pub mod registry;
@@ -44,6 +48,12 @@ mod std_impls;
 #[cfg(feature = "serde")]
 pub mod ser_de;
 
+#[cfg(feature = "serde")]
+pub mod migration;
+
+#[cfg(feature = "json_schema")]
+pub mod json_schema;
+
 #[cfg(test)]
 mod test {
     #[cfg(feature = "derive")]
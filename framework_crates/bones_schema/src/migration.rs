@@ -0,0 +1,646 @@
+//! Versioning for schemas, and migrations for data that was serialized with older versions of a
+//! schema.
+//!
+//! A struct may declare its version with the `#[schema(version = N)]` attribute when deriving
+//! [`HasSchema`], which adds the [`SchemaVersion`] type data to its schema. The
+//! [`SchemaSerializer`] stores the version along with the struct's fields, under the
+//! [`VERSION_KEY`]. Data without a version, such as data that was saved before the struct was
+//! versioned, is treated as version `0`.
+//!
+//! When the [`SchemaDeserializer`] loads data with an older version, the data is first read into a
+//! [`SerializedValue`], and then the [`SchemaMigrations`] registered in the schema's type data are
+//! applied, one version at a time, before the data is loaded.
+//!
+//! ```
+//! # use bones_schema::prelude::*;
+//! # use serde::de::DeserializeSeed;
+//! #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+//! #[repr(C)]
+//! #[schema(version = 1)]
+//! #[type_data(SchemaMigrations::default().migration(0, rename_hp))]
+//! struct Player {
+//!     health: u32,
+//! }
+//!
+//! /// Version `0` called the `health` field `hp`.
+//! fn rename_hp(value: &mut SerializedValue) -> Result<(), String> {
+//!     value.rename("hp", "health");
+//!     Ok(())
+//! }
+//!
+//! let player = SchemaDeserializer(Player::schema())
+//!     .deserialize(serde_yaml::Deserializer::from_str("hp: 10"))
+//!     .unwrap();
+//! assert_eq!(player.cast_ref::<Player>(), &Player { health: 10 });
+//! ```
+//!
+//! Versions are only stored for structs with named fields. Deriving [`HasSchema`] with a version
+//! for any other type is an error, and the version is ignored for other types that add the
+//! [`SchemaVersion`] type data manually.
+
+use std::any::type_name;
+
+use serde::{
+    de::{
+        value::{Error as ValueError, MapDeserializer, SeqDeserializer},
+        EnumAccess, Error, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use crate::prelude::*;
+
+/// The key that the version of a struct is serialized with.
+pub const VERSION_KEY: &str = "$version";
+
+/// A function that migrates serialized data to the next version of a schema.
+pub type SchemaMigrationFn = fn(&mut SerializedValue) -> Result<(), String>;
+
+/// Schema [type data][SchemaData::type_data] containing the current version of the schema.
+///
+/// This is usually added with the `#[schema(version = N)]` attribute when deriving [`HasSchema`].
+/// See the [module documentation][self].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchemaVersion(pub u32);
+
+/// Schema [type data][SchemaData::type_data] containing the migrations that are used to load data
+/// that was serialized with older versions of a schema.
+///
+/// See the [module documentation][self].
+#[derive(Clone, Default)]
+pub struct SchemaMigrations {
+    /// The migrations.
+    pub migrations: Vec<SchemaMigration>,
+}
+
+/// A migration in [`SchemaMigrations`].
+#[derive(Clone, Copy)]
+pub struct SchemaMigration {
+    /// The version that the migration migrates data from, to the next version.
+    pub from_version: u32,
+    /// The function that migrates the data.
+    pub migrate_fn: SchemaMigrationFn,
+}
+
+impl SchemaMigrations {
+    /// Add a migration from `from_version` to the next version.
+    pub fn migration(mut self, from_version: u32, migrate_fn: SchemaMigrationFn) -> Self {
+        self.migrations.push(SchemaMigration {
+            from_version,
+            migrate_fn,
+        });
+        self
+    }
+}
+
+impl SchemaVersion {
+    /// Deserialize data of a versioned schema, and apply the schema's migrations to update it from
+    /// the version that it was serialized with to the current version.
+    ///
+    /// The [`VERSION_KEY`] is removed from the returned value.
+    pub fn migrate<'de, D: Deserializer<'de>>(
+        &self,
+        schema: &Schema,
+        deserializer: D,
+    ) -> Result<SerializedValue, D::Error> {
+        let mut value = SerializedValue::deserialize(deserializer)?;
+        let data_version = match value.remove(VERSION_KEY) {
+            Some(SerializedValue::U64(v)) => u32::try_from(v).ok(),
+            Some(SerializedValue::I64(v)) => u32::try_from(v).ok(),
+            Some(_) => None,
+            None => Some(0),
+        }
+        .ok_or_else(|| D::Error::custom(format!("`{VERSION_KEY}` must be a version number")))?;
+
+        if data_version > self.0 {
+            return Err(D::Error::custom(format!(
+                "Data for `{}` has version {data_version}, which is newer than the current \
+                version {}",
+                schema.full_name, self.0
+            )));
+        }
+
+        if let Some(migrations) = schema.type_data.get::<SchemaMigrations>() {
+            for version in data_version..self.0 {
+                for migration in migrations
+                    .migrations
+                    .iter()
+                    .filter(|x| x.from_version == version)
+                {
+                    (migration.migrate_fn)(&mut value).map_err(|e| {
+                        D::Error::custom(format!(
+                            "Error migrating `{}` from version {version}: {e}",
+                            schema.full_name
+                        ))
+                    })?;
+                }
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// A self-describing copy of serialized data, which can be modified by [`SchemaMigrations`].
+///
+/// [`SerializedValue`] implements [`Deserializer`], so that the migrated value can be loaded with
+/// a schema.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SerializedValue {
+    /// A unit, or an empty value like `null` or [`None`].
+    Unit,
+    /// A boolean.
+    Bool(bool),
+    /// A signed integer.
+    I64(i64),
+    /// An unsigned integer.
+    U64(u64),
+    /// A floating point number.
+    F64(f64),
+    /// A string.
+    String(String),
+    /// A sequence of values.
+    Seq(Vec<SerializedValue>),
+    /// A map of keys to values, in the order that they were serialized.
+    Map(Vec<(SerializedValue, SerializedValue)>),
+    /// An enum variant with its name and value.
+    Variant(String, Box<SerializedValue>),
+}
+
+impl SerializedValue {
+    /// Get the value of a key, if this is a map that contains the key.
+    pub fn get(&self, key: &str) -> Option<&SerializedValue> {
+        match self {
+            SerializedValue::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a key mutably, if this is a map that contains the key.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut SerializedValue> {
+        match self {
+            SerializedValue::Map(entries) => entries
+                .iter_mut()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Insert a value with the given key, if this is a map, returning the previous value.
+    ///
+    /// Does nothing if this is not a map.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<SerializedValue>,
+    ) -> Option<SerializedValue> {
+        let key = key.into();
+        let value = value.into();
+        if let Some(existing) = self.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+        if let SerializedValue::Map(entries) = self {
+            entries.push((SerializedValue::String(key), value));
+        }
+        None
+    }
+
+    /// Remove a key from this value, if this is a map that contains the key.
+    pub fn remove(&mut self, key: &str) -> Option<SerializedValue> {
+        match self {
+            SerializedValue::Map(entries) => {
+                let idx = entries.iter().position(|(k, _)| k.as_str() == Some(key))?;
+                Some(entries.remove(idx).1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Rename a key, if this is a map that contains the key.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let SerializedValue::Map(entries) = self {
+            if let Some((key, _)) = entries.iter_mut().find(|(k, _)| k.as_str() == Some(from)) {
+                *key = SerializedValue::String(to.to_string());
+            }
+        }
+    }
+
+    /// Get the string, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SerializedValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for SerializedValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+impl From<i64> for SerializedValue {
+    fn from(value: i64) -> Self {
+        Self::I64(value)
+    }
+}
+impl From<u64> for SerializedValue {
+    fn from(value: u64) -> Self {
+        Self::U64(value)
+    }
+}
+impl From<f64> for SerializedValue {
+    fn from(value: f64) -> Self {
+        Self::F64(value)
+    }
+}
+impl From<&str> for SerializedValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+impl From<String> for SerializedValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for SerializedValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SerializedValueVisitor)
+    }
+}
+
+struct SerializedValueVisitor;
+impl<'de> Visitor<'de> for SerializedValueVisitor {
+    type Value = SerializedValue;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "any value")
+    }
+
+    fn visit_bool<E: Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(SerializedValue::Bool(v))
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(SerializedValue::I64(v))
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(SerializedValue::U64(v))
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(SerializedValue::F64(v))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(SerializedValue::String(v.to_string()))
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(SerializedValue::String(v))
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(SerializedValue::Unit)
+    }
+
+    fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(SerializedValue::Unit)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        SerializedValue::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        SerializedValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(SerializedValue::Seq(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(SerializedValue::Map(entries))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (name, variant) = data.variant::<String>()?;
+        let value = variant.newtype_variant()?;
+        Ok(SerializedValue::Variant(name, Box::new(value)))
+    }
+}
+
+impl<'de> Deserializer<'de> for SerializedValue {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            SerializedValue::Unit => visitor.visit_unit(),
+            SerializedValue::Bool(v) => visitor.visit_bool(v),
+            SerializedValue::I64(v) => visitor.visit_i64(v),
+            SerializedValue::U64(v) => visitor.visit_u64(v),
+            SerializedValue::F64(v) => visitor.visit_f64(v),
+            SerializedValue::String(v) => visitor.visit_string(v),
+            SerializedValue::Seq(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            SerializedValue::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            SerializedValue::Variant(name, value) => visitor.visit_enum(VariantDeserializer {
+                name,
+                value: *value,
+            }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            SerializedValue::Unit => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            SerializedValue::String(variant) => {
+                visitor.visit_enum(IntoDeserializer::<Self::Error>::into_deserializer(variant))
+            }
+            value @ SerializedValue::Variant(..) => value.deserialize_any(visitor),
+            _ => Err(Self::Error::custom(format!(
+                "expected an enum variant of `{name}`, one of {variants:?}"
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ValueError> for SerializedValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Helper for deserializing a [`SerializedValue::Variant`].
+struct VariantDeserializer {
+    name: String,
+    value: SerializedValue,
+}
+
+impl<'de> EnumAccess<'de> for VariantDeserializer {
+    type Error = ValueError;
+    type Variant = SerializedValue;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let name = seed.deserialize(SerializedValue::String(self.name))?;
+        Ok((name, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for SerializedValue {
+    type Error = ValueError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_any(visitor)
+    }
+}
+
+unsafe impl HasSchema for SchemaVersion {
+    fn schema() -> &'static Schema {
+        use crate::raw_fns::*;
+        use std::{alloc::Layout, any::TypeId, sync::OnceLock};
+        static S: OnceLock<&'static Schema> = OnceLock::new();
+        let layout = Layout::new::<Self>();
+        S.get_or_init(|| {
+            SCHEMA_REGISTRY.register(SchemaData {
+                name: type_name::<Self>().into(),
+                full_name: format!("{}::{}", module_path!(), type_name::<Self>()).into(),
+                kind: SchemaKind::Primitive(Primitive::Opaque {
+                    size: layout.size(),
+                    align: layout.align(),
+                }),
+                type_id: Some(TypeId::of::<Self>()),
+                clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
+                drop_fn: None,
+                default_fn: None,
+                hash_fn: None,
+                eq_fn: None,
+                type_data: Default::default(),
+            })
+        })
+    }
+}
+
+unsafe impl HasSchema for SchemaMigrations {
+    fn schema() -> &'static Schema {
+        use crate::raw_fns::*;
+        use std::{alloc::Layout, any::TypeId, sync::OnceLock};
+        static S: OnceLock<&'static Schema> = OnceLock::new();
+        let layout = Layout::new::<Self>();
+        S.get_or_init(|| {
+            SCHEMA_REGISTRY.register(SchemaData {
+                name: type_name::<Self>().into(),
+                full_name: format!("{}::{}", module_path!(), type_name::<Self>()).into(),
+                kind: SchemaKind::Primitive(Primitive::Opaque {
+                    size: layout.size(),
+                    align: layout.align(),
+                }),
+                type_id: Some(TypeId::of::<Self>()),
+                clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
+                drop_fn: Some(<Self as RawDrop>::raw_drop_cb()),
+                default_fn: Some(<Self as RawDefault>::raw_default_cb()),
+                hash_fn: None,
+                eq_fn: None,
+                type_data: Default::default(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bones_schema_macros::HasSchema;
+    use serde::de::DeserializeSeed;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    #[schema(version = 2)]
+    #[type_data(SchemaMigrations::default().migration(0, rename_hp).migration(1, double_speed))]
+    struct Player {
+        health: u32,
+        speed: f32,
+        weapon: Weapon,
+        pet: Option<String>,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C, u8)]
+    enum Weapon {
+        #[default]
+        Fists,
+        Sword {
+            damage: u32,
+        },
+    }
+
+    fn rename_hp(value: &mut SerializedValue) -> Result<(), String> {
+        value.rename("hp", "health");
+        Ok(())
+    }
+
+    fn double_speed(value: &mut SerializedValue) -> Result<(), String> {
+        match value.get_mut("speed") {
+            Some(SerializedValue::F64(speed)) => *speed *= 2.0,
+            Some(_) => return Err("`speed` must be a number".into()),
+            None => (),
+        }
+        Ok(())
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    #[type_data(SchemaVersion(1))]
+    #[type_data(SchemaMigrations::default().migration(0, double_components))]
+    struct Velocity(f32, f32);
+
+    fn double_components(value: &mut SerializedValue) -> Result<(), String> {
+        if let SerializedValue::Seq(items) = value {
+            for item in items {
+                if let SerializedValue::F64(x) = item {
+                    *x *= 2.0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn load(yaml: &str) -> Result<Player, serde_yaml::Error> {
+        Ok(SchemaDeserializer(Player::schema())
+            .deserialize(serde_yaml::Deserializer::from_str(yaml))?
+            .cast_into())
+    }
+
+    #[test]
+    fn migrate_unversioned_data() {
+        let player = load("hp: 10\nspeed: 1.5\nweapon: !Sword { damage: 3 }\npet: Spot").unwrap();
+        assert_eq!(
+            player,
+            Player {
+                health: 10,
+                speed: 3.0,
+                weapon: Weapon::Sword { damage: 3 },
+                pet: Some("Spot".into()),
+            }
+        );
+
+        let error = load("hp: 10\nspeed: fast").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("from version 1: `speed` must be a number"));
+    }
+
+    #[test]
+    fn versioned_round_trip() {
+        let player = Player {
+            health: 5,
+            speed: 2.0,
+            weapon: Weapon::Fists,
+            pet: None,
+        };
+        let yaml = serde_yaml::to_string(&SchemaSerializer(player.as_schema_ref())).unwrap();
+        assert!(yaml.contains("version: 2"));
+
+        // Data with the current version isn't migrated.
+        assert_eq!(load(&yaml).unwrap(), player);
+
+        // Data from version 1 is only migrated from version 1.
+        assert_eq!(
+            load("$version: 1\nhealth: 1\nspeed: 1.0").unwrap().speed,
+            2.0
+        );
+
+        let error = load("$version: 3\nhealth: 1").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("newer than the current version 2"));
+    }
+
+    #[test]
+    fn tuple_structs_are_not_migrated() {
+        let velocity = Velocity(1.0, 2.0);
+        let yaml = serde_yaml::to_string(&SchemaSerializer(velocity.as_schema_ref())).unwrap();
+        let loaded = SchemaDeserializer(Velocity::schema())
+            .deserialize(serde_yaml::Deserializer::from_str(&yaml))
+            .unwrap();
+        assert_eq!(loaded.cast_ref::<Velocity>(), &velocity);
+    }
+}
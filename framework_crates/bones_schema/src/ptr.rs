@@ -130,7 +130,7 @@ impl<'pointer> SchemaRef<'pointer> {
 
     /// Borrow the schema ref as a [`SchemaBox`] if it is one.
     pub fn as_box(&self) -> Option<SchemaRef<'pointer>> {
-        matches!(self.schema.kind, SchemaKind::Vec(_))
+        matches!(self.schema.kind, SchemaKind::Box(_))
             // SOUND: Schema asserts this is a schema box
             .then(|| unsafe { self.cast_into_unchecked::<SchemaBox>().as_ref() })
     }
@@ -213,6 +213,8 @@ pub enum SchemaRefAccess<'a> {
     Enum(EnumRefAccess<'a>),
     /// Access a map.
     Map(SchemaMapAccess<'a>),
+    /// Access an option.
+    Option(OptionRefAccess<'a>),
     /// Access a struct.
     Primitive(PrimitiveRef<'a>),
 }
@@ -254,6 +256,13 @@ impl std::fmt::Debug for SchemaRefAccess<'_> {
                 }
                 builder.finish()
             }
+            SchemaRefAccess::Option(o) => match o.value() {
+                Some(value) => f
+                    .debug_tuple("Some")
+                    .field(&SchemaRefValueDebug(value))
+                    .finish(),
+                None => f.write_str("None"),
+            },
             SchemaRefAccess::Primitive(p) => match p {
                 PrimitiveRef::Bool(b) => f.write_fmt(format_args!("{b}")),
                 PrimitiveRef::U8(n) => f.write_fmt(format_args!("{n}")),
@@ -304,6 +313,7 @@ impl<'ptr> SchemaRefAccess<'ptr> {
                 orig_ref: value,
             }),
             SchemaKind::Box(_) => value.as_box().unwrap().access(),
+            SchemaKind::Option(_) => SchemaRefAccess::Option(OptionRefAccess(value)),
             SchemaKind::Primitive(_) => SchemaRefAccess::Primitive(value.into()),
         }
     }
@@ -328,15 +338,19 @@ impl<'ptr> SchemaRefAccess<'ptr> {
                 orig_ref: *value,
             }),
             SchemaKind::Box(_) => value.as_box().unwrap().access(),
+            SchemaKind::Option(_) => SchemaRefAccess::Option(OptionRefAccess(*value)),
             SchemaKind::Primitive(_) => SchemaRefAccess::Primitive((*value).into()),
         }
     }
 
     /// Get field with the given index.
+    ///
+    /// If this is an option that is `Some`, this gets the field of the value in the option.
     pub fn field<'a, I: Into<FieldIdx<'a>>>(self, field_idx: I) -> Option<Self> {
         let field_idx = field_idx.into();
         match self {
             SchemaRefAccess::Struct(s) => s.field(field_idx),
+            SchemaRefAccess::Option(o) => o.value()?.access().field(field_idx),
             SchemaRefAccess::Vec(_)
             | SchemaRefAccess::Enum(_)
             | SchemaRefAccess::Map(_)
@@ -360,6 +374,7 @@ impl<'ptr> SchemaRefAccess<'ptr> {
             SchemaRefAccess::Vec(v) => v.into_schema_ref(),
             SchemaRefAccess::Enum(e) => e.0,
             SchemaRefAccess::Map(m) => m.into_schema_ref(),
+            SchemaRefAccess::Option(o) => o.0,
             SchemaRefAccess::Primitive(p) => p.into_schema_ref(),
         }
     }
@@ -469,6 +484,8 @@ pub struct StructRefFieldIter<'a> {
 pub struct StructRefFieldIterField<'a> {
     /// The name of the field, if set.
     pub name: Option<&'static str>,
+    /// The field's info, including its attributes.
+    pub info: &'static StructFieldInfo,
     /// The field's value.
     pub value: SchemaRef<'a>,
 }
@@ -478,6 +495,7 @@ impl<'a> Iterator for StructRefFieldIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let (name, _) = self.ptr.schema.field_offsets().get(self.field_idx)?;
+        let info = &self.ptr.schema.kind.as_struct().unwrap().fields[self.field_idx];
         let ptr = self
             .ptr
             .access()
@@ -487,6 +505,7 @@ impl<'a> Iterator for StructRefFieldIter<'a> {
         self.field_idx += 1;
         Some(StructRefFieldIterField {
             name: name.as_ref().map(|x| x.as_str()),
+            info,
             value: ptr,
         })
     }
@@ -552,6 +571,48 @@ impl<'a> EnumRefAccess<'a> {
     }
 }
 
+/// Helper for accessing the inner data of an [`Option`] at runtime.
+#[derive(Clone, Copy)]
+pub struct OptionRefAccess<'a>(pub SchemaRef<'a>);
+
+impl<'a> OptionRefAccess<'a> {
+    /// Get the option's schema.
+    pub fn schema(&self) -> &'static Schema {
+        self.0.schema
+    }
+
+    /// Get the option schema info.
+    pub fn info(&self) -> &'static OptionSchemaInfo {
+        let SchemaKind::Option(info) = &self.0.schema.kind else {
+            panic!("Not an option");
+        };
+        info
+    }
+
+    /// Get whether the option is `Some`.
+    pub fn is_some(&self) -> bool {
+        self.value().is_some()
+    }
+
+    /// Get whether the option is `None`.
+    pub fn is_none(&self) -> bool {
+        self.value().is_none()
+    }
+
+    /// Get a reference to the value in the option, or [`None`] if the option is `None`.
+    pub fn value(&self) -> Option<SchemaRef<'a>> {
+        let info = self.info();
+        // SOUND: the schema asserts that the pointer is an option that may be accessed with the
+        // functions in its schema info.
+        let ptr = unsafe { (info.get_fn.get())(self.0.as_ptr()) };
+        NonNull::new(ptr as *mut c_void).map(|ptr| SchemaRef {
+            ptr,
+            schema: info.value,
+            _phantom: PhantomData,
+        })
+    }
+}
+
 /// Helper for accessing the inner data of a schema ref at runtime.
 #[derive(Clone, Copy, Debug)]
 pub enum PrimitiveRef<'a> {
@@ -775,7 +836,7 @@ impl<'pointer> SchemaRefMut<'pointer> {
 
     /// Borrow the schema ref as a [`SchemaBox`] if it is one.
     pub fn into_box(self) -> Result<SchemaRefMut<'pointer>, Self> {
-        matches!(self.schema.kind, SchemaKind::Vec(_))
+        matches!(self.schema.kind, SchemaKind::Box(_))
             // SOUND: Schema asserts this is a schema box
             .then(|| unsafe { (*(self.ptr.as_ptr() as *mut SchemaBox)).as_mut() })
             .ok_or(self)
@@ -897,6 +958,8 @@ pub enum SchemaRefMutAccess<'a> {
     Enum(EnumRefMutAccess<'a>),
     /// Access a map.
     Map(SchemaMapMutAccess<'a>),
+    /// Access an option.
+    Option(OptionRefMutAccess<'a>),
     /// Access a struct.
     Primitive(PrimitiveRefMut<'a>),
 }
@@ -972,6 +1035,7 @@ impl<'pointer> SchemaRefMutAccess<'pointer> {
                 map: value.into_map().unwrap(),
             }),
             SchemaKind::Box(_) => value.into_box().unwrap().into_access_mut(),
+            SchemaKind::Option(_) => SchemaRefMutAccess::Option(OptionRefMutAccess(value)),
             SchemaKind::Primitive(_) => SchemaRefMutAccess::Primitive(value.into()),
         }
     }
@@ -1002,6 +1066,9 @@ impl<'pointer> SchemaRefMutAccess<'pointer> {
                 map: value.reborrow().into_map().unwrap(),
             }),
             SchemaKind::Box(_) => value.reborrow().into_box().unwrap().into_access_mut(),
+            SchemaKind::Option(_) => {
+                SchemaRefMutAccess::Option(OptionRefMutAccess(value.reborrow()))
+            }
             SchemaKind::Primitive(_) => SchemaRefMutAccess::Primitive(value.reborrow().into()),
         }
     }
@@ -1013,17 +1080,36 @@ impl<'pointer> SchemaRefMutAccess<'pointer> {
             SchemaRefMutAccess::Vec(v) => v.as_mut(),
             SchemaRefMutAccess::Enum(e) => e.0,
             SchemaRefMutAccess::Map(m) => m.into_schema_ref_mut(),
+            SchemaRefMutAccess::Option(o) => o.0,
             SchemaRefMutAccess::Primitive(p) => p.into_schema_ref_mut(),
         }
     }
 
     /// Get field with the given index.
+    ///
+    /// If this is an option that is `Some`, this gets the field of the value in the option.
     pub fn field<'a, I: Into<FieldIdx<'a>>>(self, field_idx: I) -> Result<Self, Self> {
         let field_idx = field_idx.into();
         match self {
             SchemaRefMutAccess::Struct(s) => {
                 s.into_field(field_idx).map_err(SchemaRefMutAccess::Struct)
             }
+            SchemaRefMutAccess::Option(o) => {
+                // Check that the field exists first, so that we can return the option if it
+                // doesn't.
+                let has_field = o
+                    .as_ref()
+                    .value()
+                    .and_then(|value| value.access().field(field_idx))
+                    .is_some();
+                if !has_field {
+                    return Err(SchemaRefMutAccess::Option(o));
+                }
+                let Ok(value) = o.into_value() else {
+                    unreachable!()
+                };
+                Ok(value.into_access_mut().field(field_idx).ok().unwrap())
+            }
             other @ (SchemaRefMutAccess::Vec(_)
             | SchemaRefMutAccess::Enum(_)
             | SchemaRefMutAccess::Map(_)
@@ -1065,6 +1151,7 @@ impl<'pointer> SchemaRefMutAccess<'pointer> {
                     )
                 },
             }),
+            SchemaRefMutAccess::Option(o) => SchemaRefAccess::Option(OptionRefAccess(o.0.as_ref())),
             SchemaRefMutAccess::Primitive(p) => SchemaRefAccess::Primitive(p.as_ref()),
         }
     }
@@ -1154,6 +1241,8 @@ pub struct StructRefMutFieldIter<'a> {
 pub struct StructRefMutFieldIterField<'a> {
     /// The name of the field, if set.
     pub name: Option<&'static str>,
+    /// The field's info, including its attributes.
+    pub info: &'static StructFieldInfo,
     /// The field's value.
     pub value: SchemaRefMut<'a>,
 }
@@ -1162,26 +1251,26 @@ impl<'a> Iterator for StructRefMutFieldIter<'a> {
     type Item = StructRefMutFieldIterField<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let field_schema = self
+        let info = self
             .ptr
             .schema
             .kind
             .as_struct()
             .unwrap()
             .fields
-            .get(self.field_idx)?
-            .schema;
+            .get(self.field_idx)?;
         let (name, field_offset) = self.ptr.schema.field_offsets().get(self.field_idx)?;
         self.field_idx += 1;
 
         Some(StructRefMutFieldIterField {
             name: name.as_ref().map(|x| x.as_str()),
+            info,
             // SOUND: Return a new SchemaRefMut with the 'a lifetime. This is sound because we
             // don't return mutliple `SchemaRefMut`s to the same data.
             value: unsafe {
                 SchemaRefMut {
                     ptr: NonNull::new_unchecked(self.ptr.as_ptr().add(*field_offset)),
-                    schema: field_schema,
+                    schema: info.schema,
                     _phantom: PhantomData,
                 }
             },
@@ -1238,6 +1327,86 @@ impl<'a> EnumRefMutAccess<'a> {
     }
 }
 
+/// Helper for accessing the inner data of an [`Option`] at runtime.
+pub struct OptionRefMutAccess<'a>(pub SchemaRefMut<'a>);
+
+impl<'a> OptionRefMutAccess<'a> {
+    /// Get the option's schema.
+    pub fn schema(&self) -> &'static Schema {
+        self.0.schema
+    }
+
+    /// Get the option schema info.
+    pub fn info(&self) -> &'static OptionSchemaInfo {
+        let SchemaKind::Option(info) = &self.0.schema.kind else {
+            panic!("Not an option");
+        };
+        info
+    }
+
+    /// Get whether the option is `Some`.
+    pub fn is_some(&self) -> bool {
+        self.as_ref().is_some()
+    }
+
+    /// Get whether the option is `None`.
+    pub fn is_none(&self) -> bool {
+        self.as_ref().is_none()
+    }
+
+    /// Borrow this [`OptionRefMutAccess`] as an [`OptionRefAccess`].
+    pub fn as_ref(&self) -> OptionRefAccess<'_> {
+        OptionRefAccess(self.0.as_ref())
+    }
+
+    /// Get a mutable reference to the value in the option, or [`None`] if the option is `None`.
+    pub fn value(&mut self) -> Option<SchemaRefMut<'_>> {
+        let info = self.info();
+        let ptr = self.as_ref().value()?.as_ptr();
+        // SOUND: we hold an exclusive borrow of the option, and the pointer points to its value.
+        Some(unsafe { SchemaRefMut::from_ptr_schema(ptr as *mut c_void, info.value) })
+    }
+
+    /// Convert this into a mutable reference to the value in the option.
+    ///
+    /// # Errors
+    /// Returns `self` if the option is `None`.
+    pub fn into_value(self) -> Result<SchemaRefMut<'a>, Self> {
+        let info = self.info();
+        let ptr = self.as_ref().value().map(|value| value.as_ptr());
+        match ptr {
+            // SOUND: we take ownership of the exclusive borrow of the option, and the pointer
+            // points to its value.
+            Some(ptr) => {
+                Ok(unsafe { SchemaRefMut::from_ptr_schema(ptr as *mut c_void, info.value) })
+            }
+            None => Err(self),
+        }
+    }
+
+    /// Set the option to `None`, dropping the previous value if there was one.
+    pub fn set_none(&mut self) {
+        // SOUND: the schema asserts that the pointer is an option that may be accessed with the
+        // functions in its schema info.
+        unsafe { (self.info().set_none_fn.get())(self.0.as_ptr()) }
+    }
+
+    /// Set the option to `Some`, moving the `value` into it and dropping the previous value if there
+    /// was one.
+    ///
+    /// # Errors
+    /// Errors if the schema of the value doesn't match the schema of the option's value.
+    pub fn set_some(&mut self, value: SchemaBox) -> Result<(), SchemaMismatchError> {
+        let info = self.info();
+        info.value.ensure_match(value.schema())?;
+        // SOUND: we checked that the value has the schema of the option's value.
+        unsafe { (info.set_some_fn.get())(self.0.as_ptr(), value.as_ptr()) };
+        // The value has been moved into the option, so we deallocate the box without dropping it.
+        value.forget();
+        Ok(())
+    }
+}
+
 /// Helper for accessing the inner data of a schema ref at runtime.
 pub enum PrimitiveRefMut<'a> {
     /// A [`bool`]
@@ -2,7 +2,7 @@
 
 use std::{alloc::Layout, any::TypeId, borrow::Cow, ffi::c_void};
 
-use ustr::Ustr;
+use ustr::{ustr, Ustr};
 
 use crate::{alloc::TypeDatas, prelude::*};
 
@@ -254,6 +254,8 @@ pub enum SchemaKind {
     },
     /// The represents a [`SchemaBox`] with given type inside.
     Box(&'static Schema),
+    /// The type represents an [`Option`] of the value with the given schema.
+    Option(OptionSchemaInfo),
     /// The type represents a primitive value.
     Primitive(Primitive),
 }
@@ -291,6 +293,14 @@ impl SchemaKind {
             None
         }
     }
+    /// Get the option, if this is an option.
+    pub fn as_option(&self) -> Option<&OptionSchemaInfo> {
+        if let Self::Option(o) = self {
+            Some(o)
+        } else {
+            None
+        }
+    }
 }
 
 /// Layout information computed for [`SchemaData`].
@@ -322,6 +332,33 @@ pub struct EnumSchemaInfo {
     pub variants: Vec<VariantInfo>,
 }
 
+/// Schema data for an [`Option`].
+///
+/// Rust doesn't specify the memory layout of an `Option<T>`. Depending on `T`, the `None` variant
+/// may be stored in a niche of the value, such as the null pointer of a [`Box`], instead of in a
+/// separate tag. Instead of describing the tag, the schema stores the layout of the option and
+/// functions that are generated for the specific `Option<T>` type to access it.
+#[derive(Debug, Clone)]
+pub struct OptionSchemaInfo {
+    /// The schema of the value inside the option.
+    pub value: &'static Schema,
+    /// The layout of the option.
+    pub layout: Layout,
+    /// The function pointer that may be used to get a pointer to the value inside the option.
+    ///
+    /// Returns a null pointer if the option is `None`.
+    pub get_fn: Unsafe<unsafe fn(*const c_void) -> *const c_void>,
+    /// The function pointer that may be used to set the option to `None`, dropping the previous
+    /// value if there was one.
+    pub set_none_fn: Unsafe<unsafe fn(*mut c_void)>,
+    /// The function pointer that may be used to move the value pointed to by the second pointer
+    /// into the option pointed to by the first pointer, dropping the previous value if there was
+    /// one.
+    ///
+    /// The value that was moved must not be used or dropped afterward.
+    pub set_some_fn: Unsafe<unsafe fn(*mut c_void, *mut c_void)>,
+}
+
 /// A type for an enum tag for [`EnumSchemaInfo`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EnumTagType {
@@ -360,11 +397,267 @@ pub struct StructFieldInfo {
     pub name: Option<Ustr>,
     /// The schema of the field.
     pub schema: &'static Schema,
-    // TODO: Investigate adding attribute info to `StructFieldInfo`.
-    // It could be very useful if the derive macro could capture custom attribute data for struct
-    // fields and put it into the schema data. This would allow type data implementations that
-    // implement `FromType` to have access to custom attributes that could be used to modify various
-    // behavior, without requiring a new macro.
+    /// Custom attributes on the field.
+    ///
+    /// When deriving [`HasSchema`], these are added with the `#[schema_attr(...)]` attribute, and
+    /// the doc comment of the field is added as a `doc` attribute.
+    pub attrs: Vec<SchemaAttr>,
+}
+
+impl StructFieldInfo {
+    /// Get the attribute with the given name, if the field has one.
+    pub fn attr(&self, name: &str) -> Option<&SchemaAttr> {
+        self.attrs.iter().find(|attr| attr.name.as_str() == name)
+    }
+
+    /// Get whether the field has an attribute with the given name.
+    pub fn has_attr(&self, name: &str) -> bool {
+        self.attr(name).is_some()
+    }
+
+    /// Get the documentation of the field from its `doc` attribute, if it has one.
+    pub fn doc(&self) -> Option<&str> {
+        match self.attr("doc").and_then(|attr| attr.value.as_ref()) {
+            Some(SchemaAttrValue::String(doc)) => Some(doc.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Get the name that the field is serialized with, which may be changed with the `rename`
+    /// attribute.
+    pub fn serialized_name(&self) -> Option<&str> {
+        match self.attr("rename").and_then(|attr| attr.value.as_ref()) {
+            Some(SchemaAttrValue::String(name)) => Some(name.as_ref()),
+            _ => self.name.as_ref().map(|name| name.as_str()),
+        }
+    }
+
+    /// Get whether the field should be skipped when serializing, because it has the `skip` or
+    /// `skip_serialize` attribute.
+    pub fn skip_serialize(&self) -> bool {
+        self.has_attr("skip") || self.has_attr("skip_serialize")
+    }
+
+    /// Get whether the field should be skipped when deserializing, because it has the `skip` or
+    /// `skip_deserialize` attribute.
+    pub fn skip_deserialize(&self) -> bool {
+        self.has_attr("skip") || self.has_attr("skip_deserialize")
+    }
+
+    /// Set the field to its default value if it has the `default` attribute. This is used for
+    /// fields that were missing when deserializing.
+    ///
+    /// A `default` flag sets the field to the default value of its schema, and `default = value`
+    /// sets it to the given value. Returns an error if the value doesn't match the field's schema.
+    pub fn apply_default(&self, mut field: SchemaRefMut) -> Result<(), SchemaMismatchError> {
+        let Some(attr) = self.attr("default") else {
+            return Ok(());
+        };
+        match &attr.value {
+            Some(value) => value.write_to(field),
+            None => field.write(SchemaBox::default(self.schema).as_ref()),
+        }
+    }
+}
+
+impl StructSchemaInfo {
+    /// Get the index of the field that is deserialized from the given key, taking the `rename` and
+    /// `skip_deserialize` attributes into account.
+    pub fn deserialized_field_idx(&self, key: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|field| !field.skip_deserialize() && field.serialized_name() == Some(key))
+    }
+
+    /// Apply the [`default`][StructFieldInfo::apply_default] attribute to the fields of a struct
+    /// that weren't deserialized.
+    ///
+    /// `value` must be a struct with this schema info, and `deserialized` must have an entry for
+    /// each of its fields.
+    pub fn apply_defaults(
+        &self,
+        mut value: SchemaRefMut,
+        deserialized: &[bool],
+    ) -> Result<(), FieldDefaultError> {
+        for (i, (field, deserialized)) in self.fields.iter().zip(deserialized).enumerate() {
+            if !deserialized {
+                let field_value = value.field(i).unwrap();
+                field
+                    .apply_default(field_value)
+                    .map_err(|_| FieldDefaultError {
+                        field: field.serialized_name().unwrap_or_default().to_string(),
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the error message for a key that doesn't match any of the fields when deserializing,
+    /// listing the fields that may be deserialized.
+    pub fn unknown_field_message(&self, key: &str) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| !x.skip_deserialize())
+            .map(|(i, x)| match x.serialized_name() {
+                Some(name) => format!("`{name}`"),
+                None => format!("`{i}`"),
+            })
+            .collect::<Vec<_>>();
+        let mut msg = format!("unknown field `{key}`, ");
+        if !fields.is_empty() {
+            msg += "expected one of ";
+            msg += &fields.join(", ");
+        } else {
+            msg += "there are no fields"
+        }
+        msg
+    }
+}
+
+/// Error returned by [`StructSchemaInfo::apply_defaults()`] when the `default` attribute of a field
+/// doesn't match the field's type.
+#[derive(Debug, Clone)]
+pub struct FieldDefaultError {
+    /// The serialized name of the field.
+    pub field: String,
+}
+impl std::error::Error for FieldDefaultError {}
+impl std::fmt::Display for FieldDefaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The `default` attribute of field `{}` doesn't match the field's type",
+            self.field
+        )
+    }
+}
+
+/// A custom attribute on a [`StructFieldInfo`].
+///
+/// Attributes may be used to store extra information about a field, such as the range or tooltip
+/// of a value that is edited in a UI. Some attributes change how the field is serialized and
+/// deserialized:
+///
+/// - `rename = "name"`: serialize and deserialize the field with a different name.
+/// - `skip`: don't serialize or deserialize the field.
+/// - `skip_serialize`: don't serialize the field.
+/// - `skip_deserialize`: don't deserialize the field.
+/// - `default` or `default = value`: if the field is not deserialized, set it to the default
+///   value of its type, or to the given value.
+///
+/// When deriving [`HasSchema`], the doc comment of the field is added as a `doc` attribute.
+///
+/// ```
+/// # use bones_schema::prelude::*;
+/// #[derive(HasSchema, Clone, Default)]
+/// #[repr(C)]
+/// struct Player {
+///     #[schema_attr(range(0.0, 1.0), tooltip = "How fast the player is.")]
+///     speed: f32,
+///     #[schema_attr(rename = "hp", default = 100)]
+///     health: u32,
+/// }
+///
+/// let fields = &Player::schema().kind.as_struct().unwrap().fields;
+/// assert_eq!(
+///     fields[0].attr("range").unwrap().value,
+///     Some(SchemaAttrValue::List(vec![0.0.into(), 1.0.into()]))
+/// );
+/// assert_eq!(fields[1].serialized_name(), Some("hp"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaAttr {
+    /// The name of the attribute.
+    pub name: Ustr,
+    /// The value of the attribute, or [`None`] if it is a flag without a value.
+    pub value: Option<SchemaAttrValue>,
+}
+
+/// The value of a [`SchemaAttr`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaAttrValue {
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    String(Cow<'static, str>),
+    /// A list of values, like the `(0.0, 1.0)` in `range(0.0, 1.0)`.
+    List(Vec<SchemaAttrValue>),
+}
+
+impl From<bool> for SchemaAttrValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+impl From<i64> for SchemaAttrValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+impl From<f64> for SchemaAttrValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+impl From<&'static str> for SchemaAttrValue {
+    fn from(value: &'static str) -> Self {
+        Self::String(value.into())
+    }
+}
+impl From<String> for SchemaAttrValue {
+    fn from(value: String) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl SchemaAttrValue {
+    /// Write this value to a primitive, [`String`], or [`Ustr`].
+    ///
+    /// Integers may be written to any number type that they fit in, and floats may be written to
+    /// [`f32`] or [`f64`]. Returns an error if the value can't be written to the target's schema.
+    pub fn write_to(&self, mut target: SchemaRefMut) -> Result<(), SchemaMismatchError> {
+        if target.schema() == Ustr::schema() {
+            let Self::String(s) = self else {
+                return Err(SchemaMismatchError);
+            };
+            *target.cast_mut::<Ustr>() = ustr(s);
+            return Ok(());
+        }
+        let SchemaKind::Primitive(p) = &target.schema().kind else {
+            return Err(SchemaMismatchError);
+        };
+        macro_rules! int {
+            ($n:ident, $t:ty) => {
+                *target.cast_mut::<$t>() = (*$n).try_into().map_err(|_| SchemaMismatchError)?
+            };
+        }
+        match (self, p) {
+            (Self::Bool(b), Primitive::Bool) => *target.cast_mut::<bool>() = *b,
+            (Self::Int(n), Primitive::U8) => int!(n, u8),
+            (Self::Int(n), Primitive::U16) => int!(n, u16),
+            (Self::Int(n), Primitive::U32) => int!(n, u32),
+            (Self::Int(n), Primitive::U64) => int!(n, u64),
+            (Self::Int(n), Primitive::U128) => int!(n, u128),
+            (Self::Int(n), Primitive::I8) => int!(n, i8),
+            (Self::Int(n), Primitive::I16) => int!(n, i16),
+            (Self::Int(n), Primitive::I32) => int!(n, i32),
+            (Self::Int(n), Primitive::I64) => *target.cast_mut::<i64>() = *n,
+            (Self::Int(n), Primitive::I128) => *target.cast_mut::<i128>() = *n as i128,
+            (Self::Int(n), Primitive::F32) => *target.cast_mut::<f32>() = *n as f32,
+            (Self::Int(n), Primitive::F64) => *target.cast_mut::<f64>() = *n as f64,
+            (Self::Float(n), Primitive::F32) => *target.cast_mut::<f32>() = *n as f32,
+            (Self::Float(n), Primitive::F64) => *target.cast_mut::<f64>() = *n,
+            (Self::String(s), Primitive::String) => *target.cast_mut::<String>() = s.to_string(),
+            _ => return Err(SchemaMismatchError),
+        }
+        Ok(())
+    }
 }
 
 /// A type of primitive.
@@ -454,6 +747,9 @@ impl SchemaKind {
             SchemaKind::Map { .. } => {
                 extend_layout(&mut layout, Layout::new::<SchemaMap>());
             }
+            SchemaKind::Option(o) => {
+                extend_layout(&mut layout, o.layout);
+            }
             SchemaKind::Enum(e) => {
                 let enum_tag_layout = e.tag_type.layout();
                 extend_layout(&mut layout, enum_tag_layout);
@@ -519,6 +815,7 @@ impl SchemaKind {
             SchemaKind::Struct(s) => s.fields.iter().any(|field| field.schema.kind.has_opaque()),
             SchemaKind::Vec(v) => v.kind.has_opaque(),
             SchemaKind::Box(b) => b.schema().kind.has_opaque(),
+            SchemaKind::Option(o) => o.value.kind.has_opaque(),
             SchemaKind::Enum(e) => e.variants.iter().any(|var| var.schema.kind.has_opaque()),
             SchemaKind::Map { key, value } => {
                 key.schema().kind.has_opaque() || value.schema().kind.has_opaque()
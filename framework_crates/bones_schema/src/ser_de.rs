@@ -41,19 +41,27 @@ mod serializer_deserializer {
                     } else {
                         let named = s.fields().nth(0).map(|x| x.name.is_some()).unwrap_or(false);
 
+                        let field_count = s.fields().filter(|x| !x.info.skip_serialize()).count();
+
                         if named {
-                            let mut ser_struct = serializer
-                                .serialize_struct(&self.0.schema().name, s.fields().count())?;
-                            for field in s.fields() {
+                            let version = self.0.schema().type_data.get::<SchemaVersion>();
+                            let mut ser_struct = serializer.serialize_struct(
+                                &self.0.schema().name,
+                                field_count + version.is_some() as usize,
+                            )?;
+                            if let Some(version) = version {
+                                ser_struct.serialize_field(VERSION_KEY, &version.0)?;
+                            }
+                            for field in s.fields().filter(|x| !x.info.skip_serialize()) {
                                 ser_struct.serialize_field(
-                                    field.name.as_ref().unwrap(),
+                                    field.info.serialized_name().unwrap(),
                                     &SchemaSerializer(field.value),
                                 )?;
                             }
                             ser_struct.end()
                         } else {
-                            let mut seq = serializer.serialize_seq(Some(s.fields().count()))?;
-                            for field in s.fields() {
+                            let mut seq = serializer.serialize_seq(Some(field_count))?;
+                            for field in s.fields().filter(|x| !x.info.skip_serialize()) {
                                 seq.serialize_element(&SchemaSerializer(field.value))?;
                             }
                             seq.end()
@@ -98,12 +106,12 @@ mod serializer_deserializer {
                             &self.0.schema().name,
                             variant_idx,
                             &variant_info.name,
-                            field_count,
+                            access.fields().filter(|x| !x.info.skip_serialize()).count(),
                         )?;
 
-                        for field in access.fields() {
+                        for field in access.fields().filter(|x| !x.info.skip_serialize()) {
                             ser_struct.serialize_field(
-                                field.name.as_ref().unwrap(),
+                                field.info.serialized_name().unwrap(),
                                 &SchemaSerializer(field.value),
                             )?;
                         }
@@ -111,6 +119,10 @@ mod serializer_deserializer {
                         ser_struct.end()
                     }
                 }
+                SchemaRefAccess::Option(o) => match o.value() {
+                    Some(value) => serializer.serialize_some(&SchemaSerializer(value)),
+                    None => serializer.serialize_none(),
+                },
                 SchemaRefAccess::Primitive(p) => match p {
                     PrimitiveRef::Bool(b) => serializer.serialize_bool(*b),
                     PrimitiveRef::U8(n) => serializer.serialize_u8(*n),
@@ -178,6 +190,17 @@ mod serializer_deserializer {
                         // SOUND: it is safe to cast a struct with one field to it's field type
                         unsafe { SchemaRefMut::from_ptr_schema(self.as_ptr(), s.fields[0].schema) }
                             .deserialize(deserializer)?
+                    } else if let Some(version) = self
+                        .schema()
+                        .type_data
+                        .get::<SchemaVersion>()
+                        // The version is only serialized for structs with named fields.
+                        .filter(|_| s.fields.first().is_some_and(|f| f.name.is_some()))
+                    {
+                        // Migrate data that was serialized with an older version of the schema.
+                        let value = version.migrate(self.schema(), deserializer)?;
+                        serde::Deserializer::deserialize_any(value, StructVisitor(self))
+                            .map_err(D::Error::custom)?
                     } else {
                         deserializer.deserialize_any(StructVisitor(self))?
                     }
@@ -186,6 +209,7 @@ mod serializer_deserializer {
                 SchemaKind::Map { .. } => deserializer.deserialize_map(MapVisitor(self))?,
                 SchemaKind::Enum(_) => deserializer.deserialize_any(EnumVisitor(self))?,
                 SchemaKind::Box(_) => self.into_box().unwrap().deserialize(deserializer)?,
+                SchemaKind::Option(_) => deserializer.deserialize_option(OptionVisitor(self))?,
                 SchemaKind::Primitive(p) => {
                     match p {
                         Primitive::Bool => *self.cast_mut() = bool::deserialize(deserializer)?,
@@ -231,56 +255,49 @@ mod serializer_deserializer {
         where
             A: serde::de::SeqAccess<'de>,
         {
-            let field_count = self.0.schema().kind.as_struct().unwrap().fields.len();
+            let info = self.0.schema().kind.as_struct().unwrap();
+            let mut deserialized = vec![false; info.fields.len()];
 
-            for i in 0..field_count {
+            for (i, _) in info
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| !x.skip_deserialize())
+            {
                 let field = self.0.access_mut().field(i).unwrap().into_schema_ref_mut();
                 if seq.next_element_seed(field)?.is_none() {
                     break;
                 }
+                deserialized[i] = true;
             }
 
-            Ok(())
+            info.apply_defaults(self.0, &deserialized).map_err(A::Error::custom)
         }
 
         fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
         where
             A: serde::de::MapAccess<'de>,
         {
+            let info = self.0.schema().kind.as_struct().unwrap();
+            let mut deserialized = vec![false; info.fields.len()];
+
             while let Some(key) = map.next_key::<String>()? {
-                match self
-                    .0
-                    .access_mut()
-                    .field(&key)
-                    .map(|x| x.into_schema_ref_mut())
-                {
-                    Ok(field) => {
+                match info.deserialized_field_idx(&key) {
+                    Some(idx) => {
+                        let field = self
+                            .0
+                            .access_mut()
+                            .field(idx)
+                            .unwrap()
+                            .into_schema_ref_mut();
                         map.next_value_seed(field)?;
+                        deserialized[idx] = true;
                     }
-                    Err(_) => {
-                        let fields = &self.0.schema().kind.as_struct().unwrap().fields;
-                        let mut msg = format!("unknown field `{key}`, ");
-                        if !fields.is_empty() {
-                            msg += "expected one of ";
-                            for (i, field) in fields.iter().enumerate() {
-                                msg += &field
-                                    .name
-                                    .as_ref()
-                                    .map(|x| format!("`{x}`"))
-                                    .unwrap_or_else(|| format!("`{i}`"));
-                                if i < fields.len() - 1 {
-                                    msg += ", "
-                                }
-                            }
-                        } else {
-                            msg += "there are no fields"
-                        }
-                        return Err(A::Error::custom(msg));
-                    }
+                    None => return Err(A::Error::custom(info.unknown_field_message(&key))),
                 }
             }
 
-            Ok(())
+            info.apply_defaults(self.0, &deserialized).map_err(A::Error::custom)
         }
     }
 
@@ -351,6 +368,45 @@ mod serializer_deserializer {
             Ok(())
         }
     }
+    struct OptionVisitor<'a>(SchemaRefMut<'a>);
+    impl<'a, 'de> Visitor<'de> for OptionVisitor<'a> {
+        type Value = ();
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(
+                formatter,
+                "asset metadata matching the schema: {:#?}",
+                self.0.schema()
+            )
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            OptionRefMutAccess(self.0).set_none();
+            Ok(())
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            self.visit_none()
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value_schema = self.0.schema().kind.as_option().unwrap().value;
+            let mut value = SchemaBox::default(value_schema);
+            value.as_mut().deserialize(deserializer)?;
+            // The value was created with the schema of the option's value, so it matches.
+            OptionRefMutAccess(self.0).set_some(value).unwrap();
+            Ok(())
+        }
+    }
+
     struct EnumVisitor<'a>(SchemaRefMut<'a>);
     impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
         type Value = ();
@@ -550,11 +606,33 @@ mod test {
         map: SMap<String, String>,
     }
 
-    #[derive(HasSchema, Clone, Default)]
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
     #[schema_module(crate)]
     #[repr(C)]
     struct Age(u32);
 
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Pet {
+        name: Option<String>,
+        age: Option<Age>,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq)]
+    #[schema_module(crate)]
+    #[repr(C)]
+    struct Settings {
+        #[schema_attr(rename = "vol", range(0.0, 1.0))]
+        volume: f32,
+        #[schema_attr(skip)]
+        cache: u32,
+        #[schema_attr(default = 60)]
+        fps: u32,
+        #[schema_attr(skip_serialize, default = "guest")]
+        user: String,
+    }
+
     const DEMO_YAML: &str = r"name: John
 age: 8
 favorite_things:
@@ -610,4 +688,78 @@ map:
 
         assert_eq!(DEMO_YAML, String::from_utf8(data).unwrap());
     }
+
+    #[test]
+    fn option_round_trip() {
+        let pet = Pet {
+            name: Some("Spot".into()),
+            age: None,
+        };
+
+        let yaml = serde_yaml::to_string(&SchemaSerializer(pet.as_schema_ref())).unwrap();
+        assert_eq!(yaml, "name: Spot\nage: null\n");
+
+        let loaded = SchemaDeserializer(Pet::schema())
+            .deserialize(serde_yaml::Deserializer::from_str(&yaml))
+            .unwrap()
+            .cast_into::<Pet>();
+        assert_eq!(loaded, pet);
+
+        // Missing fields keep their default value of `None`.
+        let loaded = SchemaDeserializer(Pet::schema())
+            .deserialize(serde_yaml::Deserializer::from_str("age: 3"))
+            .unwrap()
+            .cast_into::<Pet>();
+        assert_eq!(
+            loaded,
+            Pet {
+                name: None,
+                age: Some(Age(3)),
+            }
+        );
+    }
+
+    #[test]
+    fn field_attrs() {
+        let settings = Settings {
+            volume: 0.5,
+            cache: 7,
+            fps: 30,
+            user: "fish".into(),
+        };
+
+        let yaml = serde_yaml::to_string(&SchemaSerializer(settings.as_schema_ref())).unwrap();
+        assert_eq!(yaml, "vol: 0.5\nfps: 30\n");
+
+        let loaded = SchemaDeserializer(Settings::schema())
+            .deserialize(serde_yaml::Deserializer::from_str(&yaml))
+            .unwrap()
+            .cast_into::<Settings>();
+        assert_eq!(
+            loaded,
+            Settings {
+                volume: 0.5,
+                cache: 0,
+                fps: 30,
+                user: "guest".into(),
+            }
+        );
+
+        // Missing fields with the `default` attribute are set to their default.
+        let loaded = SchemaDeserializer(Settings::schema())
+            .deserialize(serde_yaml::Deserializer::from_str("vol: 1.0"))
+            .unwrap()
+            .cast_into::<Settings>();
+        assert_eq!(loaded.fps, 60);
+
+        // Fields are only deserialized with their serialized name.
+        for yaml in ["volume: 1.0", "cache: 3"] {
+            let error = SchemaDeserializer(Settings::schema())
+                .deserialize(serde_yaml::Deserializer::from_str(yaml))
+                .unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("expected one of `vol`, `fps`, `user`"));
+        }
+    }
 }
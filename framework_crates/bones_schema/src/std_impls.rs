@@ -1,6 +1,16 @@
-use std::{alloc::Layout, any::TypeId, ffi::c_void, hash::Hasher, sync::OnceLock, time::Duration};
+use std::{
+    alloc::Layout,
+    any::{type_name, TypeId},
+    ffi::c_void,
+    hash::Hasher,
+    mem::MaybeUninit,
+    sync::OnceLock,
+    time::Duration,
+};
 
+use bones_utils::{default, HashMap};
 use fxhash::FxHasher;
+use parking_lot::RwLock;
 #[cfg(feature = "serde")]
 use serde::{de::Error, Deserialize};
 use ustr::Ustr;
@@ -227,6 +237,112 @@ unsafe impl HasSchema for Duration {
     }
 }
 
+unsafe impl<T: HasSchema> HasSchema for Option<T> {
+    fn schema() -> &'static Schema {
+        static S: OnceLock<RwLock<HashMap<TypeId, &'static Schema>>> = OnceLock::new();
+        let schema = {
+            S.get_or_init(default)
+                .read()
+                .get(&TypeId::of::<Self>())
+                .copied()
+        };
+        schema.unwrap_or_else(|| {
+            let value = T::schema();
+            let schema = SCHEMA_REGISTRY.register(SchemaData {
+                name: type_name::<Self>().into(),
+                full_name: format!("{}::{}", module_path!(), type_name::<Self>()).into(),
+                kind: SchemaKind::Option(OptionSchemaInfo {
+                    value,
+                    layout: Layout::new::<Self>(),
+                    get_fn: unsafe { Unsafe::new(option_get::<T>) },
+                    set_none_fn: unsafe { Unsafe::new(option_set_none::<T>) },
+                    set_some_fn: unsafe { Unsafe::new(option_set_some::<T>) },
+                }),
+                type_id: Some(TypeId::of::<Self>()),
+                // The value may not be cloneable, hashable, or comparable, so we use the functions
+                // from its schema, if it has them.
+                clone_fn: value.clone_fn.is_some().then(|| unsafe {
+                    Unsafe::new(Box::leak(Box::new(|a, b| option_clone::<T>(a, b))) as &'static _)
+                }),
+                drop_fn: Some(<Self as RawDrop>::raw_drop_cb()),
+                default_fn: Some(<Self as RawDefault>::raw_default_cb()),
+                hash_fn: value.hash_fn.is_some().then(|| unsafe {
+                    Unsafe::new(Box::leak(Box::new(|a| option_hash::<T>(a))) as &'static _)
+                }),
+                eq_fn: value.eq_fn.is_some().then(|| unsafe {
+                    Unsafe::new(Box::leak(Box::new(|a, b| option_eq::<T>(a, b))) as &'static _)
+                }),
+                type_data: Default::default(),
+            });
+
+            S.get_or_init(default)
+                .write()
+                .insert(TypeId::of::<Self>(), schema);
+
+            schema
+        })
+    }
+}
+
+/// Get a pointer to the value in an `Option<T>`, or a null pointer if it is `None`.
+unsafe fn option_get<T>(ptr: *const c_void) -> *const c_void {
+    match &*(ptr as *const Option<T>) {
+        Some(value) => value as *const T as *const c_void,
+        None => std::ptr::null(),
+    }
+}
+
+/// Set an `Option<T>` to `None`.
+unsafe fn option_set_none<T>(ptr: *mut c_void) {
+    *(ptr as *mut Option<T>) = None;
+}
+
+/// Move the `T` pointed to by `value` into an `Option<T>`.
+unsafe fn option_set_some<T>(ptr: *mut c_void, value: *mut c_void) {
+    *(ptr as *mut Option<T>) = Some((value as *mut T).read());
+}
+
+/// Clone an `Option<T>` using the clone function from the schema of `T`.
+unsafe fn option_clone<T: HasSchema>(src: *const c_void, dst: *mut c_void) {
+    let clone_fn = T::schema().clone_fn.as_ref().unwrap();
+    let value = (*(src as *const Option<T>)).as_ref().map(|value| {
+        let mut clone = MaybeUninit::<T>::uninit();
+        (clone_fn.get())(
+            value as *const T as *const c_void,
+            clone.as_mut_ptr() as *mut c_void,
+        );
+        clone.assume_init()
+    });
+    (dst as *mut Option<T>).write(value);
+}
+
+/// Hash an `Option<T>` using the hash function from the schema of `T`.
+unsafe fn option_hash<T: HasSchema>(ptr: *const c_void) -> u64 {
+    let hash_fn = T::schema().hash_fn.as_ref().unwrap();
+    let mut hasher = FxHasher::default();
+    match &*(ptr as *const Option<T>) {
+        Some(value) => {
+            hasher.write_u8(1);
+            hasher.write_u64((hash_fn.get())(value as *const T as *const c_void));
+        }
+        None => hasher.write_u8(0),
+    }
+    hasher.finish()
+}
+
+/// Compare two `Option<T>`s using the eq function from the schema of `T`.
+unsafe fn option_eq<T: HasSchema>(a: *const c_void, b: *const c_void) -> bool {
+    let eq_fn = T::schema().eq_fn.as_ref().unwrap();
+    match (&*(a as *const Option<T>), &*(b as *const Option<T>)) {
+        (Some(a), Some(b)) => (eq_fn.get())(
+            a as *const T as *const c_void,
+            b as *const T as *const c_void,
+        ),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
 #[cfg(feature = "glam")]
 mod impl_glam {
     use super::*;
@@ -275,6 +391,7 @@ mod impl_glam {
                                     StructFieldInfo {
                                         name: Some(stringify!($field).into()),
                                         schema: $nprim::schema(),
+                                        attrs: Vec::new(),
                                     }
                                 ),*
                             ],
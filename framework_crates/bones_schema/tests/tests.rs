@@ -284,6 +284,44 @@ fn eq_hash() {
     );
 }
 
+#[test]
+fn option() {
+    // `Option<String>` stores `None` in a niche of the string, and `Option<u8>` uses a tag.
+    let mut data = (Some(String::from("hello")), Option::<u8>::None);
+    let [mut string, mut byte] = [
+        SchemaRefMut::new(&mut data.0),
+        SchemaRefMut::new(&mut data.1),
+    ];
+
+    let SchemaRefMutAccess::Option(mut string) = string.access_mut() else {
+        panic!("Not an option");
+    };
+    assert_eq!(*string.value().unwrap().cast_into_mut::<String>(), "hello");
+    string.set_none();
+    assert!(string.is_none());
+
+    let SchemaRefMutAccess::Option(mut byte) = byte.access_mut() else {
+        panic!("Not an option");
+    };
+    assert!(byte.value().is_none());
+    byte.set_some(SchemaBox::new(7u8)).unwrap();
+    assert!(byte.set_some(SchemaBox::new(7u32)).is_err());
+
+    assert_eq!(data, (None, Some(7)));
+
+    let hash = |value: Option<u32>| value.as_schema_ref().hash().unwrap();
+    assert_eq!(hash(Some(3)), hash(Some(3)));
+    assert_ne!(hash(Some(3)), hash(None));
+    assert_eq!(
+        Some(DataB(1.0, 2.0))
+            .as_schema_ref()
+            .field(1)
+            .unwrap()
+            .cast::<f32>(),
+        &2.0
+    );
+}
+
 #[test]
 fn zst() {
     let b = SchemaBox::new(Zst);
@@ -307,6 +345,14 @@ fn schema_layout_matches_rust_layout() {
         d: SVec<Vec2>,
     }
 
+    #[derive(HasSchema, Default, Clone)]
+    #[repr(C)]
+    struct C {
+        a: Option<String>,
+        b: Option<u8>,
+        c: Option<DataB>,
+    }
+
     macro_rules! layout_eq {
         ( $( $t:ident ),* ) => {
             $(
@@ -314,7 +360,7 @@ fn schema_layout_matches_rust_layout() {
             )*
         };
     }
-    layout_eq!(A, B);
+    layout_eq!(A, B, C);
 }
 
 #[derive(HasSchema, Clone)]
@@ -326,5 +372,6 @@ fn generic_has_schema_impls_dont_match() {
     assert_ne!(SMap::<u32, u32>::schema(), SMap::<u32, u8>::schema());
     assert_ne!(SVec::<u32>::schema(), SVec::<u8>::schema());
     assert_ne!(SBox::<u32>::schema(), SBox::<u8>::schema());
+    assert_ne!(Option::<u32>::schema(), Option::<u8>::schema());
     assert_ne!(HasGeneric::<u32>::schema(), HasGeneric::<u64>::schema());
 }
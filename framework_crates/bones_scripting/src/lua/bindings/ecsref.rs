@@ -106,7 +106,7 @@ pub enum EcsRefData {
 
 /// A kind of borrow into an [`EcsRef`].
 pub enum EcsRefBorrowKind<'a> {
-    Resource(Ref<'a, Option<SchemaBox>>),
+    Resource(Option<Ref<'a, SchemaBox>>),
     Component(ComponentBorrow<'a>),
     Free(Ref<'a, SchemaBox>),
     Asset(Option<MappedRef<'a, Cid, LoadedAsset, SchemaBox>>),
@@ -153,10 +153,10 @@ impl EcsRefBorrowKind<'_> {
     /// that is not set for a given entity.
     pub fn schema_ref(&self) -> Result<SchemaRef<'_>, EcsRefBorrowError> {
         match self {
-            EcsRefBorrowKind::Resource(r) => Ok(r
-                .as_ref()
-                .ok_or(EcsRefBorrowError::MissingResource)?
-                .as_ref()),
+            EcsRefBorrowKind::Resource(r) => r
+                .as_deref()
+                .map(|x| x.as_ref())
+                .ok_or(EcsRefBorrowError::MissingResource),
             EcsRefBorrowKind::Component(c) => {
                 c.borrow
                     .get_ref(c.entity)
@@ -188,7 +188,7 @@ pub struct ComponentBorrowMut<'a> {
 
 /// A kind of mutable borrow of an [`EcsRef`].
 pub enum EcsRefBorrowMutKind<'a> {
-    Resource(RefMut<'a, Option<SchemaBox>>),
+    Resource(Option<RefMut<'a, SchemaBox>>),
     Component(ComponentBorrowMut<'a>),
     Free(RefMut<'a, SchemaBox>),
     Asset(Option<MappedRefMut<'a, Cid, LoadedAsset, SchemaBox>>),
@@ -201,10 +201,10 @@ impl EcsRefBorrowMutKind<'_> {
     /// that is not set for a given entity.
     pub fn schema_ref_mut(&mut self) -> Result<SchemaRefMut<'_>, EcsRefBorrowError> {
         match self {
-            EcsRefBorrowMutKind::Resource(r) => Ok(r
-                .as_mut()
-                .ok_or(EcsRefBorrowError::MissingResource)?
-                .as_mut()),
+            EcsRefBorrowMutKind::Resource(r) => r
+                .as_deref_mut()
+                .map(|x| x.as_mut())
+                .ok_or(EcsRefBorrowError::MissingResource),
             EcsRefBorrowMutKind::Component(c) => {
                 c.borrow
                     .get_ref_mut(c.entity)
@@ -325,7 +325,20 @@ pub fn metatable(ctx: Context) -> Table {
                 newref.path = ustr(&format!("{}.{key}", this.path));
                 let b = newref.borrow();
 
-                match b.schema_ref()?.access() {
+                // Options are transparent to scripts: `None` is `nil`, and `Some` is the value
+                // inside of it.
+                let access = match b.schema_ref()?.access() {
+                    SchemaRefAccess::Option(o) => match o.value() {
+                        Some(value) => value.access(),
+                        None => {
+                            stack.push_front(Value::Nil);
+                            return Ok(CallbackReturn::Return);
+                        }
+                    },
+                    access => access,
+                };
+
+                match access {
                     SchemaRefAccess::Primitive(p) if !matches!(p, PrimitiveRef::Opaque { .. }) => {
                         match p {
                             PrimitiveRef::Bool(b) => stack.push_front(Value::Boolean(*b)),
@@ -366,13 +379,42 @@ pub fn metatable(ctx: Context) -> Table {
                 let mut this = this.clone();
                 this.path = ustr(&format!("{}.{key}", this.path));
                 let mut b = this.borrow_mut();
-                let mut this_ref = b.schema_ref_mut()?;
+
+                // Assigning `nil` to an option sets it to `None`, and assigning anything else
+                // sets the value inside of it.
+                let mut this_ref = match b.schema_ref_mut()?.into_access_mut() {
+                    SchemaRefMutAccess::Option(mut o) => {
+                        if let Value::Nil = newvalue {
+                            o.set_none();
+                            return Ok(CallbackReturn::Return);
+                        }
+                        if o.is_none() {
+                            let value_schema = o.info().value;
+                            if value_schema.default_fn.is_none() {
+                                return Err(anyhow::format_err!(
+                                    "Cannot assign to `{}` because it is `nil`, and `{}` has no \
+                                    default value",
+                                    this.path,
+                                    value_schema.full_name
+                                )
+                                .into());
+                            }
+                            o.set_some(SchemaBox::default(value_schema))?;
+                        }
+                        let Ok(value) = o.into_value() else {
+                            unreachable!()
+                        };
+                        value
+                    }
+                    access => access.into_schema_ref_mut(),
+                };
 
                 match this_ref.access_mut() {
                     SchemaRefMutAccess::Struct(_)
                     | SchemaRefMutAccess::Vec(_)
                     | SchemaRefMutAccess::Enum(_)
-                    | SchemaRefMutAccess::Map(_) => {
+                    | SchemaRefMutAccess::Map(_)
+                    | SchemaRefMutAccess::Option(_) => {
                         let newvalue = newvalue.as_static_user_data::<EcsRef>()?;
                         let newvalue_b = newvalue.borrow();
                         let newvalue_ref = newvalue_b.schema_ref()?;
@@ -1,6 +1,6 @@
 use lua::Variadic;
 
-use crate::prelude::bindings::schema::WithoutSchema;
+use crate::prelude::bindings::schema::{WithSchema, WithoutSchema};
 
 use super::*;
 
@@ -80,7 +80,11 @@ pub fn entities_metatable(ctx: Context) -> Table {
                     {
                         let components = world.components.get_by_schema(without_schema.0);
                         let components = components.borrow();
-                        bitset.bit_and(components.bitset().clone().bit_not());
+                        bitset.bit_andnot(components.bitset());
+                    } else if let Ok(with_schema) = schema_arg.downcast_static::<WithSchema>() {
+                        let components = world.components.get_by_schema(with_schema.0);
+                        let components = components.borrow();
+                        bitset.bit_and(components.bitset());
                     } else {
                         return Err(anyhow::format_err!(
                             "Invalid type for argument to `entities:iter_with()`: {schema_arg:?}"
@@ -4,6 +4,10 @@ use super::*;
 /// an `entities:iter_with()` lua call.
 pub(super) struct WithoutSchema(pub &'static Schema);
 
+/// A wrapper around [`Schema`] that indicates that it should be used to filter, for example,
+/// an `entities:iter_with()` lua call, without retrieving the component.
+pub(super) struct WithSchema(pub &'static Schema);
+
 pub fn schema_fn(ctx: Context) -> Callback {
     Callback::from_fn(&ctx, move |ctx, _fuel, mut stack| {
         let singletons = ctx.singletons();
@@ -94,6 +98,16 @@ pub fn metatable(ctx: Context) -> Table {
         }),
     );
 
+    let with_fn = ctx.registry().stash(
+        &ctx,
+        Callback::from_fn(&ctx, move |ctx, _fuel, mut stack| {
+            let this: UserData = stack.consume(ctx)?;
+            let this = this.downcast_static::<&Schema>()?;
+            stack.replace(ctx, UserData::new_static(&ctx, WithSchema(this)));
+            Ok(CallbackReturn::Return)
+        }),
+    );
+
     let eq_fn = Callback::from_fn(&ctx, move |ctx, _fuel, mut stack| {
         let (this, other): (UserData, UserData) = stack.consume(ctx)?;
         let (this, other) = (
@@ -130,6 +144,7 @@ pub fn metatable(ctx: Context) -> Table {
                         );
                     }
                     b"create" => stack.replace(ctx, ctx.registry().fetch(&create_fn)),
+                    b"with" => stack.replace(ctx, ctx.registry().fetch(&with_fn)),
                     b"without" => stack.replace(ctx, ctx.registry().fetch(&without_fn)),
                     _ => (),
                 }
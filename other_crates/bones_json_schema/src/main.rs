@@ -0,0 +1,91 @@
+#![doc = include_str!("../README.md")]
+// This cfg_attr is needed because `rustdoc::all` includes lints not supported on stable
+#![cfg_attr(doc, allow(unknown_lints))]
+#![deny(rustdoc::all)]
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use bones_framework::prelude::*;
+use bones_framework::AssetServerExt;
+use clap::Parser;
+use serde_json::{json, Map, Value};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Config {
+    /// The directory to write the JSON Schema documents to
+    #[clap(short, long, default_value = ".")]
+    out_dir: PathBuf,
+    /// Export every registered schema, instead of only the metadata assets
+    #[clap(long)]
+    all: bool,
+    /// Print the VS Code settings that associate the documents with asset files
+    #[clap(long, conflicts_with = "all")]
+    vscode_settings: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Config::parse();
+
+    // Register the schemas of the default asset types.
+    AssetServer::default().register_default_assets();
+
+    fs::create_dir_all(&args.out_dir)
+        .with_context(|| format!("Creating output directory {:?}", args.out_dir))?;
+
+    let mut yaml_schemas = Map::new();
+    let mut json_schemas = Vec::new();
+    if args.all {
+        let mut names = HashSet::new();
+        for (schema, document) in metadata_json_schema_generator().registered_documents() {
+            // Generic types have the same full name, so they are numbered to avoid overwriting
+            // each other.
+            let base_name = json_schema_definition_name(schema);
+            let mut name = base_name.clone();
+            let mut i = 1;
+            while !names.insert(name.clone()) {
+                name = format!("{base_name}_{i}");
+                i += 1;
+            }
+            write_document(&args.out_dir.join(format!("{name}.schema.json")), &document)?;
+        }
+    } else {
+        for metadata in metadata_asset_json_schemas() {
+            let path = args
+                .out_dir
+                .join(format!("{}.schema.json", metadata.extension));
+            write_document(&path, &metadata.document)?;
+
+            let url = path.to_string_lossy().replace('\\', "/");
+            let ext = &metadata.extension;
+            yaml_schemas.insert(
+                url.clone(),
+                json!([format!("*.{ext}.yaml"), format!("*.{ext}.yml")]),
+            );
+            json_schemas.push(json!({ "fileMatch": [format!("*.{ext}.json")], "url": url }));
+        }
+    }
+
+    if args.vscode_settings {
+        let settings = json!({
+            "yaml.schemas": yaml_schemas,
+            "json.schemas": json_schemas,
+        });
+        println!("{}", serde_json::to_string_pretty(&settings)?);
+    }
+
+    Ok(())
+}
+
+/// Write a JSON Schema document to a file.
+fn write_document(path: &Path, document: &Value) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(document)? + "\n")
+        .with_context(|| format!("Writing {path:?}"))?;
+    eprintln!("Wrote {}", path.display());
+    Ok(())
+}